@@ -0,0 +1,240 @@
+use crate::parse_msg::split_sections;
+use crate::{Error, MessagePath, Msg, Result};
+use lazy_static::lazy_static;
+use regex::RegexBuilder;
+use serde_derive::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
+use std::fmt::Formatter;
+
+const AUTOGENERATED: &str =
+    "# ====== DO NOT MODIFY! AUTOGENERATED FROM AN ACTION DEFINITION ======\n";
+
+/// A ROS action parsed from an `action` file.
+///
+/// The seven messages that `genaction` generates for an action are synthesized,
+/// with sources that result in the same MD5 sums.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "ActionSerde")]
+#[serde(try_from = "ActionSerde")]
+pub struct Action {
+    path: MessagePath,
+    source: String,
+    goal: Msg,
+    result: Msg,
+    feedback: Msg,
+    action_goal: Msg,
+    action_result: Msg,
+    action_feedback: Msg,
+    action: Msg,
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.source.fmt(f)
+    }
+}
+
+impl Action {
+    /// Create an action from a passed in path and source.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is an error parsing the action source.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros_message::Action;
+    /// # use std::convert::TryInto;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let action = Action::new(
+    ///     "foo/Bar".try_into()?,
+    ///     r#"# goal
+    ///     int32 order
+    /// ---
+    ///     int32[] sequence
+    /// ---
+    ///     int32[] sequence
+    ///     "#,
+    /// )?;
+    ///
+    /// assert_eq!(action.path(), &"foo/Bar".try_into()?);
+    /// assert_eq!(action.goal().path(), &"foo/BarGoal".try_into()?);
+    /// assert_eq!(action.action_goal().fields().len(), 3);
+    /// assert_eq!(action.messages().len(), 7);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(path: MessagePath, source: impl Into<String>) -> Result<Action> {
+        lazy_static! {
+            static ref RE_SPLIT: regex::Regex = RegexBuilder::new("^---.*$")
+                .multi_line(true)
+                .build()
+                .expect("Invalid regex `^---.*$`");
+        }
+        let source = source.into();
+        let (goal, result, feedback) = match split_sections(&RE_SPLIT, &source)[..] {
+            [goal, result, feedback] => (goal, result, feedback),
+            ref v => {
+                return Err(Error::BadMessageContent(format!(
+                    "Action {} is split into {} parts instead of 3",
+                    path,
+                    v.len()
+                )))
+            }
+        };
+        let name = path.name();
+        // The autogenerated line comes first, so the section starts on the line after it
+        let parse_section = |suffix: &str, (source, first_line): (&str, usize)| {
+            Msg::parse(
+                &path.peer(format!("{}{}", name, suffix)),
+                &format!("{}{}", AUTOGENERATED, source),
+                first_line - 1,
+            )
+        };
+        let sections = (
+            parse_section("Goal", goal),
+            parse_section("Result", result),
+            parse_section("Feedback", feedback),
+        );
+        let (goal, result, feedback) = match sections {
+            (Ok(goal), Ok(result), Ok(feedback)) => (goal, result, feedback),
+            (goal, result, feedback) => {
+                return Err(Error::InvalidSource {
+                    file: format!("{}.action", path),
+                    diagnostics: goal
+                        .err()
+                        .into_iter()
+                        .chain(result.err())
+                        .chain(feedback.err())
+                        .flatten()
+                        .collect(),
+                })
+            }
+        };
+        let message = |suffix: &str, source: &str| {
+            Msg::new(
+                path.peer(format!("{}{}", name, suffix)),
+                &format!("{}{}", AUTOGENERATED, source),
+            )
+        };
+        Ok(Action {
+            goal,
+            result,
+            feedback,
+            action_goal: message(
+                "ActionGoal",
+                &format!(
+                    "\nHeader header\nactionlib_msgs/GoalID goal_id\n{}Goal goal\n",
+                    name
+                ),
+            )?,
+            action_result: message(
+                "ActionResult",
+                &format!(
+                    "\nHeader header\nactionlib_msgs/GoalStatus status\n{}Result result\n",
+                    name
+                ),
+            )?,
+            action_feedback: message(
+                "ActionFeedback",
+                &format!(
+                    "\nHeader header\nactionlib_msgs/GoalStatus status\n{}Feedback feedback\n",
+                    name
+                ),
+            )?,
+            action: message(
+                "Action",
+                &format!(
+                    "\n{0}ActionGoal action_goal\n{0}ActionResult action_result\n{0}ActionFeedback action_feedback\n",
+                    name
+                ),
+            )?,
+            path,
+            source,
+        })
+    }
+
+    /// Returns the path of the action.
+    pub fn path(&self) -> &MessagePath {
+        &self.path
+    }
+
+    /// Returns the original source.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns the goal message, `<Name>Goal`.
+    pub fn goal(&self) -> &Msg {
+        &self.goal
+    }
+
+    /// Returns the result message, `<Name>Result`.
+    pub fn result(&self) -> &Msg {
+        &self.result
+    }
+
+    /// Returns the feedback message, `<Name>Feedback`.
+    pub fn feedback(&self) -> &Msg {
+        &self.feedback
+    }
+
+    /// Returns the goal message wrapped with a header and goal ID, `<Name>ActionGoal`.
+    pub fn action_goal(&self) -> &Msg {
+        &self.action_goal
+    }
+
+    /// Returns the result message wrapped with a header and goal status, `<Name>ActionResult`.
+    pub fn action_result(&self) -> &Msg {
+        &self.action_result
+    }
+
+    /// Returns the feedback message wrapped with a header and goal status, `<Name>ActionFeedback`.
+    pub fn action_feedback(&self) -> &Msg {
+        &self.action_feedback
+    }
+
+    /// Returns the message combining all wrapped messages, `<Name>Action`.
+    pub fn action(&self) -> &Msg {
+        &self.action
+    }
+
+    /// Returns all seven messages generated for the action.
+    pub fn messages(&self) -> [&Msg; 7] {
+        [
+            &self.goal,
+            &self.result,
+            &self.feedback,
+            &self.action_goal,
+            &self.action_result,
+            &self.action_feedback,
+            &self.action,
+        ]
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ActionSerde {
+    path: MessagePath,
+    source: String,
+}
+
+impl TryFrom<ActionSerde> for Action {
+    type Error = Error;
+
+    fn try_from(src: ActionSerde) -> Result<Self> {
+        Self::new(src.path, src.source)
+    }
+}
+
+impl From<Action> for ActionSerde {
+    fn from(src: Action) -> Self {
+        Self {
+            path: src.path,
+            source: src.source,
+        }
+    }
+}
@@ -234,6 +234,48 @@ impl DataType {
         }
     }
 
+    /// Returns the path of the message type, with local names resolved relative to `parent`.
+    ///
+    /// Built in types have no path.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros_message::{DataType, MessagePath};
+    /// # use std::convert::TryInto;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let parent: MessagePath = "geometry_msgs/Pose".try_into()?;
+    /// assert_eq!(
+    ///     DataType::parse("Point")?.message_path(&parent),
+    ///     Some("geometry_msgs/Point".try_into()?),
+    /// );
+    /// assert_eq!(
+    ///     DataType::parse("Header")?.message_path(&parent),
+    ///     Some("std_msgs/Header".try_into()?),
+    /// );
+    /// assert_eq!(DataType::parse("int16")?.message_path(&parent), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn message_path(&self, parent: &MessagePath) -> Option<MessagePath> {
+        match self {
+            DataType::LocalMessage(name) => Some(parent.peer(name.as_str())),
+            DataType::GlobalMessage(path) => Some(path.clone()),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the data type, with messages written out with their package.
+    ///
+    /// Local names are resolved relative to `parent`, like in `message_path`.
+    pub fn qualified_name(&self, parent: &MessagePath) -> String {
+        match self.message_path(parent) {
+            Some(path) => path.to_string(),
+            None => self.to_string(),
+        }
+    }
+
     /// Returns the representation of the data type when constructing the MD5 sum.
     ///
     /// For built in types, it is the same as the data type name.
@@ -0,0 +1,90 @@
+use std::fmt::{self, Display, Formatter};
+use std::ops::Range;
+
+/// Problem found at a specific place in a `msg`, `srv` or `action` file.
+///
+/// # Examples
+///
+/// ```
+/// # use ros_message::{MessagePath, Msg};
+/// # use std::convert::TryInto;
+/// #
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let path: MessagePath = "foo/Bar".try_into()?;
+/// let error = Msg::new(path, "int32 a\nfloat64[x] b\nuint8 C=-1").unwrap_err();
+/// let diagnostics = error.diagnostics();
+/// assert_eq!(diagnostics.len(), 2);
+/// assert_eq!(diagnostics[0].line(), 2);
+/// assert_eq!(diagnostics[0].columns(), 1..13);
+/// assert_eq!(diagnostics[0].snippet(), "float64[x] b");
+/// assert_eq!(diagnostics[1].line(), 3);
+/// assert_eq!(diagnostics[1].columns(), 9..11);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    line: usize,
+    columns: Range<usize>,
+    snippet: String,
+    message: String,
+}
+
+impl Diagnostic {
+    /// Creates a diagnostic for the part of the line within the byte range.
+    pub(crate) fn new(line: usize, snippet: &str, span: Range<usize>, message: String) -> Self {
+        let column = |byte: usize| snippet[..byte].chars().count() + 1;
+        Self {
+            line,
+            columns: column(span.start)..column(span.end),
+            snippet: snippet.into(),
+            message,
+        }
+    }
+
+    /// Line of the problem, starting from 1.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Columns of the problematic part of the line, starting from 1, with the end excluded.
+    pub fn columns(&self) -> Range<usize> {
+        self.columns.clone()
+    }
+
+    /// The whole line containing the problem.
+    pub fn snippet(&self) -> &str {
+        &self.snippet
+    }
+
+    /// Description of the problem.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let line = self.line.to_string();
+        let gutter = " ".repeat(line.len());
+        writeln!(f, "{}:{}: {}", self.line, self.columns.start, self.message)?;
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", line, self.snippet)?;
+        write!(
+            f,
+            "{} | {}{}",
+            gutter,
+            " ".repeat(self.columns.start - 1),
+            "^".repeat((self.columns.end - self.columns.start).max(1)),
+        )
+    }
+}
+
+/// Formats all diagnostics of a file, each prefixed with the file name.
+pub(crate) fn format_diagnostics(file: &str, diagnostics: &[Diagnostic]) -> String {
+    diagnostics
+        .iter()
+        .map(|diagnostic| format!("{}:{}", file, diagnostic))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
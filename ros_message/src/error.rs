@@ -1,3 +1,5 @@
+use crate::diagnostic::{format_diagnostics, Diagnostic};
+
 /// Enumeration of all errors that can be returned.
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -13,6 +15,14 @@ pub enum Error {
         /// Reason for the failure.
         reason: String,
     },
+    /// Field path has invalid syntax.
+    #[error("field path `{path}` is invalid, {reason}")]
+    InvalidFieldPath {
+        /// The invalid path.
+        path: String,
+        /// Reason for the failure.
+        reason: String,
+    },
     /// Field in the `msg` or `srv` file has a name that doesn't fit any data type category.
     #[error("data type `{name}` is invalid, {reason}")]
     UnsupportedDataType {
@@ -24,6 +34,17 @@ pub enum Error {
     /// The `msg` or `srv` file being parsed has invalid content.
     #[error("bad content in message: `{0}`")]
     BadMessageContent(String),
+    /// Lines of a `msg`, `srv` or `action` file failed to parse.
+    ///
+    /// All failing lines of the file are reported at once.
+    #[error("{}", format_diagnostics(.file, .diagnostics))]
+    InvalidSource {
+        /// Name of the file, which is the message path with an extension unless set with
+        /// [`Error::with_file`].
+        file: String,
+        /// Problems found in the file, in order.
+        diagnostics: Vec<Diagnostic>,
+    },
     /// Certain operations on a `msg` or `srv` file require first handling all messages it depends upon.
     ///
     /// For example, to calculate an MD5 sum for a message, you first need to calculate it for
@@ -47,5 +68,28 @@ pub enum Error {
     },
 }
 
+impl Error {
+    /// Sets the name of the file that diagnostics point into, like its path on disk.
+    ///
+    /// Other errors are returned unchanged.
+    pub fn with_file(self, file: impl Into<String>) -> Self {
+        match self {
+            Error::InvalidSource { diagnostics, .. } => Error::InvalidSource {
+                file: file.into(),
+                diagnostics,
+            },
+            err => err,
+        }
+    }
+
+    /// Returns the problems found in a parsed file, if there are any.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        match self {
+            Error::InvalidSource { diagnostics, .. } => diagnostics,
+            _ => &[],
+        }
+    }
+}
+
 /// Convenience type for shorter return value syntax of this crate's errors.
 pub type Result<T> = std::result::Result<T, Error>;
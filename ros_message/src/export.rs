@@ -0,0 +1,271 @@
+use crate::{DataType, FieldCase, FieldInfo, I8Variant, MessagePath, Msg, Srv, Value};
+use std::fmt::Write;
+
+impl Msg {
+    /// Returns the equivalent ROS 2 `msg` file.
+    ///
+    /// Times and durations become `builtin_interfaces` messages, `byte` and `char` become the
+    /// integers they are aliases of, and names are converted to the case that ROS 2 requires,
+    /// like `frameId` to `frame_id`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros_message::Msg;
+    /// # use std::convert::TryInto;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let message = Msg::new(
+    ///     "foo/Bar".try_into()?,
+    ///     "Header header\ntime[2] stamps\nbyte rawByte\nstring NAME=bar",
+    /// )?;
+    /// assert_eq!(
+    ///     message.to_ros2_msg(),
+    ///     "string NAME=\"bar\"\nstd_msgs/Header header\n\
+    ///      builtin_interfaces/Time[2] stamps\nint8 raw_byte\n",
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_ros2_msg(&self) -> String {
+        let mut output = String::new();
+        for (field, value) in self.ros2_constants() {
+            let _ = writeln!(
+                output,
+                "{} {}={}",
+                ros2_msg_type(self.path(), field.datatype()),
+                field.name().to_uppercase(),
+                constant_literal(&value, false),
+            );
+        }
+        for field in self.fields().iter().filter(|v| !v.is_constant()) {
+            let datatype = ros2_msg_type(self.path(), field.datatype());
+            let datatype = match field.case() {
+                FieldCase::Vector => format!("{}[]", datatype),
+                FieldCase::Array(l) => format!("{}[{}]", datatype, l),
+                FieldCase::Unit | FieldCase::Const(_) => datatype,
+            };
+            let _ = writeln!(output, "{} {}", datatype, snake_case(field.name()));
+        }
+        output
+    }
+
+    /// Returns the equivalent ROS 2 IDL file, as `rosidl_adapter` would generate it.
+    ///
+    /// Types are converted like with [`Msg::to_ros2_msg`].
+    pub fn to_ros2_idl(&self) -> String {
+        let mut output = String::new();
+        let _ = writeln!(output, "// Converted from {}\n", self.path());
+        write_includes(&mut output, &[self]);
+        let _ = writeln!(output, "module {} {{", self.path().package());
+        let _ = writeln!(output, "  module msg {{");
+        write_idl_struct(&mut output, self, self.path().name());
+        let _ = writeln!(output, "  }};");
+        let _ = writeln!(output, "}};");
+        output
+    }
+
+    fn ros2_constants(&self) -> Vec<(&FieldInfo, Value)> {
+        let mut constants = self.constants();
+        self.fields()
+            .iter()
+            .filter_map(|field| Some((field, constants.remove(field.name())?)))
+            .collect()
+    }
+}
+
+impl Srv {
+    /// Returns the equivalent ROS 2 `srv` file.
+    ///
+    /// Types are converted like with [`Msg::to_ros2_msg`].
+    pub fn to_ros2_srv(&self) -> String {
+        format!(
+            "{}---\n{}",
+            self.request().to_ros2_msg(),
+            self.response().to_ros2_msg()
+        )
+    }
+
+    /// Returns the equivalent ROS 2 IDL file, with `_Request` and `_Response` structures.
+    ///
+    /// Types are converted like with [`Msg::to_ros2_msg`].
+    pub fn to_ros2_idl(&self) -> String {
+        let mut output = String::new();
+        let _ = writeln!(output, "// Converted from {}\n", self.path());
+        write_includes(&mut output, &[self.request(), self.response()]);
+        let _ = writeln!(output, "module {} {{", self.path().package());
+        let _ = writeln!(output, "  module srv {{");
+        let name = self.path().name();
+        write_idl_struct(&mut output, self.request(), &format!("{}_Request", name));
+        write_idl_struct(&mut output, self.response(), &format!("{}_Response", name));
+        let _ = writeln!(output, "  }};");
+        let _ = writeln!(output, "}};");
+        output
+    }
+}
+
+fn ros2_message_path(parent: &MessagePath, datatype: &DataType) -> Option<MessagePath> {
+    match datatype {
+        DataType::Time => MessagePath::new("builtin_interfaces", "Time").ok(),
+        DataType::Duration => MessagePath::new("builtin_interfaces", "Duration").ok(),
+        datatype => datatype.message_path(parent),
+    }
+}
+
+fn ros2_msg_type(parent: &MessagePath, datatype: &DataType) -> String {
+    match datatype {
+        DataType::I8(_) => "int8".into(),
+        DataType::U8(_) => "uint8".into(),
+        DataType::LocalMessage(name) => name.clone(),
+        _ => match ros2_message_path(parent, datatype) {
+            Some(path) => path.to_string(),
+            None => datatype.to_string(),
+        },
+    }
+}
+
+fn idl_type(parent: &MessagePath, datatype: &DataType) -> String {
+    match datatype {
+        DataType::Bool => "boolean".into(),
+        DataType::I8(I8Variant::Int8) | DataType::I8(I8Variant::Byte) => "int8".into(),
+        DataType::I16 => "int16".into(),
+        DataType::I32 => "int32".into(),
+        DataType::I64 => "int64".into(),
+        DataType::U8(_) => "uint8".into(),
+        DataType::U16 => "uint16".into(),
+        DataType::U32 => "uint32".into(),
+        DataType::U64 => "uint64".into(),
+        DataType::F32 => "float".into(),
+        DataType::F64 => "double".into(),
+        DataType::String => "string".into(),
+        DataType::Time
+        | DataType::Duration
+        | DataType::LocalMessage(_)
+        | DataType::GlobalMessage(_) => match ros2_message_path(parent, datatype) {
+            Some(path) => format!("{}::msg::{}", path.package(), path.name()),
+            None => datatype.to_string(),
+        },
+    }
+}
+
+fn write_includes(output: &mut String, messages: &[&Msg]) {
+    let mut includes = messages
+        .iter()
+        .flat_map(|msg| {
+            msg.fields()
+                .iter()
+                .filter_map(move |field| ros2_message_path(msg.path(), field.datatype()))
+        })
+        .map(|path| format!("#include \"{}/msg/{}.idl\"", path.package(), path.name()))
+        .collect::<Vec<_>>();
+    includes.sort();
+    includes.dedup();
+    for include in &includes {
+        let _ = writeln!(output, "{}", include);
+    }
+    if !includes.is_empty() {
+        output.push('\n');
+    }
+}
+
+fn write_idl_struct(output: &mut String, msg: &Msg, name: &str) {
+    let constants = msg.ros2_constants();
+    if !constants.is_empty() {
+        let _ = writeln!(output, "    module {}_Constants {{", name);
+        for (field, value) in constants {
+            let _ = writeln!(
+                output,
+                "      const {} {} = {};",
+                idl_type(msg.path(), field.datatype()),
+                field.name().to_uppercase(),
+                constant_literal(&value, true),
+            );
+        }
+        let _ = writeln!(output, "    }};");
+    }
+    let _ = writeln!(output, "    struct {} {{", name);
+    let fields = msg
+        .fields()
+        .iter()
+        .filter(|v| !v.is_constant())
+        .collect::<Vec<_>>();
+    if fields.is_empty() {
+        // IDL does not allow empty structures, so rosidl adds a placeholder
+        let _ = writeln!(output, "      uint8 structure_needs_at_least_one_member;");
+    }
+    for field in fields {
+        let datatype = idl_type(msg.path(), field.datatype());
+        let name = snake_case(field.name());
+        let _ = match field.case() {
+            FieldCase::Vector => writeln!(output, "      sequence<{}> {};", datatype, name),
+            FieldCase::Array(l) => writeln!(output, "      {} {}[{}];", datatype, name, l),
+            FieldCase::Unit | FieldCase::Const(_) => {
+                writeln!(output, "      {} {};", datatype, name)
+            }
+        };
+    }
+    let _ = writeln!(output, "    }};");
+}
+
+fn constant_literal(value: &Value, idl: bool) -> String {
+    match value {
+        Value::Bool(v) if idl => if *v { "TRUE" } else { "FALSE" }.into(),
+        Value::Bool(v) => v.to_string(),
+        Value::String(v) if idl => idl_string_literal(v),
+        Value::String(v) => msg_string_literal(v),
+        Value::F32(v) => format!("{:?}", v),
+        Value::F64(v) => format!("{:?}", v),
+        value => value.to_string(),
+    }
+}
+
+/// Quotes the string for a ROS 2 `msg` file, whose parser only unescapes quotes.
+fn msg_string_literal(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\\\""))
+}
+
+/// Quotes the string as an IDL string literal, escaping it like section 7.2.6.3 of IDL 4.2.
+fn idl_string_literal(value: &str) -> String {
+    let mut output = String::from('"');
+    for c in value.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\t' => output.push_str("\\t"),
+            '\r' => output.push_str("\\r"),
+            c if c.is_ascii_control() => {
+                let _ = write!(output, "\\x{:02x}", c as u32);
+            }
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+    output
+}
+
+/// Converts names like `frameId` or `Frame_ID` to `frame_id`.
+fn snake_case(name: &str) -> String {
+    let mut output = String::new();
+    let chars = name.chars().collect::<Vec<_>>();
+    for (idx, &c) in chars.iter().enumerate() {
+        if c.is_ascii_uppercase() {
+            let previous = idx.checked_sub(1).map(|i| chars[i]);
+            let next = chars.get(idx + 1);
+            let starts_word = match previous {
+                Some(p) if p.is_ascii_lowercase() || p.is_ascii_digit() => true,
+                Some(p) if p.is_ascii_uppercase() => {
+                    matches!(next, Some(n) if n.is_ascii_lowercase())
+                }
+                _ => false,
+            };
+            if starts_word && !output.ends_with('_') {
+                output.push('_');
+            }
+            output.push(c.to_ascii_lowercase());
+        } else {
+            output.push(c);
+        }
+    }
+    output
+}
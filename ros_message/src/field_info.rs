@@ -153,6 +153,35 @@ impl FieldInfo {
         &self.case
     }
 
+    /// Returns the type of the field with its array length, and with messages written out
+    /// with their package, like `geometry_msgs/Point[]`.
+    ///
+    /// Local names are resolved relative to `parent`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros_message::{FieldInfo, FieldCase, MessagePath};
+    /// # use std::convert::TryInto;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let parent: MessagePath = "geometry_msgs/Polygon".try_into()?;
+    /// let field = FieldInfo::new("Point32", "points", FieldCase::Vector)?;
+    /// assert_eq!(field.qualified_type(&parent), "geometry_msgs/Point32[]");
+    /// let field = FieldInfo::new("float64", "covariance", FieldCase::Array(36))?;
+    /// assert_eq!(field.qualified_type(&parent), "float64[36]");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn qualified_type(&self, parent: &MessagePath) -> String {
+        let datatype = self.datatype.qualified_name(parent);
+        match self.case {
+            FieldCase::Vector => format!("{}[]", datatype),
+            FieldCase::Array(l) => format!("{}[{}]", datatype, l),
+            FieldCase::Unit | FieldCase::Const(_) => datatype,
+        }
+    }
+
     /// Returns the stored value if a constant field.
     pub fn const_value(&self) -> Option<&Value> {
         self.const_value.inner.as_ref()
@@ -0,0 +1,310 @@
+use crate::{Error, MessageValue, Result, Value};
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::fmt::{self, Display, Formatter};
+use std::iter::FromIterator;
+use std::str::FromStr;
+
+/// Path to values inside of a message, like `pose.position.x` or `status[*].name`.
+///
+/// Each component names a field, optionally followed by a selection of array items:
+/// a single index like `ranges[10]`, a range like `ranges[2:8]`, `ranges[:8]` or
+/// `ranges[2:]`, or all items with `ranges[*]`. The default path has no components,
+/// and points to the message itself.
+///
+/// # Examples
+///
+/// ```
+/// # use ros_message::{FieldPath, MessageValue, Value};
+/// # use std::borrow::Cow;
+/// #
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut position = MessageValue::new();
+/// position.insert("x".into(), Value::F64(1.5));
+/// let mut message = MessageValue::new();
+/// message.insert("position".into(), Value::Message(position));
+/// message.insert("ranges".into(), vec![1u8, 2, 3, 4].into());
+///
+/// let path: FieldPath = "position.x".parse()?;
+/// assert_eq!(path.get(&message).as_deref(), Some(&Value::F64(1.5)));
+///
+/// let path: FieldPath = "ranges[1:3]".parse()?;
+/// assert_eq!(
+///     path.select(&message),
+///     vec![Cow::Borrowed(&Value::U8(2)), Cow::Borrowed(&Value::U8(3))],
+/// );
+/// assert!(path.get(&message).is_none());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FieldPath {
+    components: Vec<PathComponent>,
+}
+
+/// Field name and array selection of one component of a `FieldPath`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PathComponent {
+    name: String,
+    selection: Option<Selection>,
+}
+
+/// Items selected from an array field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Selection {
+    /// A single item, like `[3]`.
+    Index(usize),
+    /// Items in the range, like `[2:8]`, with open ends reaching the ends of the array.
+    Range(Option<usize>, Option<usize>),
+    /// All items, `[*]`.
+    All,
+}
+
+impl FieldPath {
+    /// Parses a field path.
+    ///
+    /// # Errors
+    ///
+    /// An error will be returned if the path has invalid syntax.
+    pub fn new(path: &str) -> Result<Self> {
+        let error = |reason: &str| Error::InvalidFieldPath {
+            path: path.into(),
+            reason: reason.into(),
+        };
+        if path.is_empty() {
+            return Err(error("path is empty"));
+        }
+        let components = path
+            .split('.')
+            .map(|component| PathComponent::parse(component).map_err(error))
+            .collect::<Result<_>>()?;
+        Ok(Self { components })
+    }
+
+    /// Returns the path to a field of the message this path points to.
+    pub fn field(&self, name: impl Into<String>) -> Self {
+        let mut components = self.components.clone();
+        components.push(PathComponent::new(name, None));
+        Self { components }
+    }
+
+    /// Returns the path with items of its last field selected, like `poses[1]` for `poses`.
+    pub fn with_selection(&self, selection: Selection) -> Self {
+        let mut components = self.components.clone();
+        if let Some(last) = components.last_mut() {
+            last.selection = Some(selection);
+        }
+        Self { components }
+    }
+
+    /// Returns the components of the path, one for each nested field.
+    pub fn components(&self) -> &[PathComponent] {
+        &self.components
+    }
+
+    /// Returns whether the path points to at most one value, without ranges or `[*]`.
+    pub fn is_single(&self) -> bool {
+        self.components.iter().all(|v| match v.selection {
+            None | Some(Selection::Index(_)) => true,
+            Some(Selection::Range(..)) | Some(Selection::All) => false,
+        })
+    }
+
+    /// Returns the value the path points to, if the path is single and the value exists.
+    ///
+    /// Values are borrowed from the message, except for items of typed arrays like
+    /// `Value::Bytes`, which are not stored as `Value`s.
+    pub fn get<'a>(&self, message: &'a MessageValue) -> Option<Cow<'a, Value>> {
+        if !self.is_single() {
+            return None;
+        }
+        self.select(message).pop()
+    }
+
+    /// Returns all values the path points to, in order.
+    ///
+    /// Missing fields, indices out of bounds and values of the wrong type are skipped.
+    pub fn select<'a>(&self, message: &'a MessageValue) -> Vec<Cow<'a, Value>> {
+        let mut output = vec![];
+        select_components(&self.components, message, &mut output);
+        output
+    }
+}
+
+fn select_components<'a>(
+    components: &[PathComponent],
+    message: &'a MessageValue,
+    output: &mut Vec<Cow<'a, Value>>,
+) {
+    let (component, rest) = match components.split_first() {
+        Some(v) => v,
+        None => return,
+    };
+    let value = match message.get(&component.name) {
+        Some(value) => value,
+        None => return,
+    };
+    let mut visit = |value: &'a Value| {
+        if rest.is_empty() {
+            output.push(Cow::Borrowed(value));
+        } else if let Value::Message(message) = value {
+            select_components(rest, message, output);
+        }
+    };
+    match (component.selection, value) {
+        (None, value) => visit(value),
+        (Some(selection), Value::Array(items)) => {
+            items[selection.range(items.len())].iter().for_each(visit)
+        }
+        // Typed arrays only hold numbers, so there is nothing to descend into
+        (Some(selection), value) if rest.is_empty() => {
+            if let Some(len) = value.array_len() {
+                output.extend(
+                    selection
+                        .range(len)
+                        .filter_map(|i| value.array_item(i))
+                        .map(Cow::Owned),
+                );
+            }
+        }
+        (Some(_), _) => {}
+    }
+}
+
+impl PathComponent {
+    /// Creates a component for the field, with an optional selection of array items.
+    pub fn new(name: impl Into<String>, selection: Option<Selection>) -> Self {
+        Self {
+            name: name.into(),
+            selection,
+        }
+    }
+
+    fn parse(component: &str) -> std::result::Result<Self, &'static str> {
+        let (name, selection) = match component.find('[') {
+            Some(start) => {
+                let selection = component[start + 1..]
+                    .strip_suffix(']')
+                    .ok_or("array selection needs to end with `]`")?;
+                (&component[..start], Some(Selection::parse(selection)?))
+            }
+            None => (component, None),
+        };
+        let mut chars = name.chars();
+        if !matches!(chars.next(), Some(v) if v.is_ascii_alphabetic()) {
+            return Err("field names need to start with a letter");
+        }
+        if !chars.all(|v| v.is_ascii_alphanumeric() || v == '_') {
+            return Err("field names can only contain letters, digits and underscores");
+        }
+        Ok(Self::new(name, selection))
+    }
+
+    /// Name of the field.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Items selected from the field, if it is an array.
+    pub fn selection(&self) -> Option<Selection> {
+        self.selection
+    }
+}
+
+impl Selection {
+    fn parse(selection: &str) -> std::result::Result<Self, &'static str> {
+        let index = |v: &str| -> std::result::Result<Option<usize>, &'static str> {
+            let v = v.trim();
+            if v.is_empty() {
+                return Ok(None);
+            }
+            v.parse()
+                .map(Some)
+                .map_err(|_| "array indices need to be non-negative integers")
+        };
+        if selection.trim() == "*" {
+            return Ok(Selection::All);
+        }
+        match selection.split_once(':') {
+            Some((start, end)) => match (index(start)?, index(end)?) {
+                (Some(start), Some(end)) if start > end => {
+                    Err("range start needs to be before its end")
+                }
+                (start, end) => Ok(Selection::Range(start, end)),
+            },
+            None => index(selection)?
+                .map(Selection::Index)
+                .ok_or("array selection is empty"),
+        }
+    }
+
+    /// Returns the range of selected items in an array of the given length.
+    ///
+    /// The range is clamped to the array, so it's empty if no items are selected.
+    pub fn range(self, len: usize) -> std::ops::Range<usize> {
+        let (start, end) = match self {
+            Selection::Index(index) => (index, index.saturating_add(1)),
+            Selection::Range(start, end) => (start.unwrap_or(0), end.unwrap_or(len)),
+            Selection::All => (0, len),
+        };
+        let end = end.min(len);
+        start.min(end)..end
+    }
+}
+
+impl Display for FieldPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (idx, component) in self.components.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(".")?;
+            }
+            component.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for PathComponent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)?;
+        match self.selection {
+            Some(selection) => write!(f, "[{}]", selection),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Display for Selection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let index = |v: Option<usize>| v.map(|v| v.to_string()).unwrap_or_default();
+        match self {
+            Selection::Index(index) => index.fmt(f),
+            Selection::Range(start, end) => write!(f, "{}:{}", index(*start), index(*end)),
+            Selection::All => f.write_str("*"),
+        }
+    }
+}
+
+impl FromIterator<PathComponent> for FieldPath {
+    fn from_iter<T: IntoIterator<Item = PathComponent>>(iter: T) -> Self {
+        Self {
+            components: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl FromStr for FieldPath {
+    type Err = Error;
+
+    fn from_str(path: &str) -> Result<Self> {
+        Self::new(path)
+    }
+}
+
+impl<'a> TryFrom<&'a str> for FieldPath {
+    type Error = Error;
+
+    fn try_from(path: &'a str) -> Result<Self> {
+        Self::new(path)
+    }
+}
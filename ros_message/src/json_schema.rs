@@ -0,0 +1,141 @@
+use crate::{DataType, Error, FieldCase, FieldInfo, MessagePath, Msg, Result};
+use serde_json::{json, Map};
+use std::collections::HashMap;
+
+const JSON_SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+impl Msg {
+    /// Returns a JSON Schema for messages serialized to JSON, like with the `serde` support
+    /// of generated messages.
+    ///
+    /// Nested messages are resolved from `dependencies` and placed in `$defs`.
+    ///
+    /// # Errors
+    ///
+    /// An error will be returned if a nested message is missing from `dependencies`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros_message::{MessagePath, Msg};
+    /// # use std::collections::HashMap;
+    /// # use std::convert::TryInto;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let point = Msg::new("geometry_msgs/Point".try_into()?, "float64 x\nfloat64 y")?;
+    /// let message = Msg::new("foo/Bar".try_into()?, "uint8 id\ngeometry_msgs/Point[] points")?;
+    /// let mut dependencies = HashMap::new();
+    /// dependencies.insert(point.path().clone(), point);
+    ///
+    /// let schema = message.json_schema(&dependencies)?;
+    /// assert_eq!(schema["properties"]["id"]["maximum"], 255);
+    /// assert_eq!(
+    ///     schema["properties"]["points"]["items"]["$ref"],
+    ///     "#/$defs/geometry_msgs~1Point",
+    /// );
+    /// assert_eq!(schema["$defs"]["geometry_msgs/Point"]["required"][1], "y");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn json_schema(
+        &self,
+        dependencies: &HashMap<MessagePath, Msg>,
+    ) -> Result<serde_json::Value> {
+        let mut definitions = Map::new();
+        let mut pending = self.dependencies();
+        while let Some(path) = pending.pop() {
+            let key = path.to_string();
+            if definitions.contains_key(&key) {
+                continue;
+            }
+            let dependency =
+                dependencies
+                    .get(&path)
+                    .ok_or_else(|| Error::MessageDependencyMissing {
+                        package: path.package().into(),
+                        name: path.name().into(),
+                    })?;
+            pending.extend(dependency.dependencies());
+            definitions.insert(key, object_schema(dependency));
+        }
+        let mut schema = object_schema(self);
+        schema["$schema"] = JSON_SCHEMA_DIALECT.into();
+        schema["title"] = self.path().to_string().into();
+        if !definitions.is_empty() {
+            schema["$defs"] = definitions.into();
+        }
+        Ok(schema)
+    }
+}
+
+fn object_schema(msg: &Msg) -> serde_json::Value {
+    let fields = msg
+        .fields()
+        .iter()
+        .filter(|v| !v.is_constant())
+        .collect::<Vec<_>>();
+    let properties = fields
+        .iter()
+        .map(|field| (field.name().to_owned(), field_schema(msg.path(), field)))
+        .collect::<Map<_, _>>();
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": fields.iter().map(|v| v.name()).collect::<Vec<_>>(),
+        "additionalProperties": false,
+    })
+}
+
+fn field_schema(parent: &MessagePath, field: &FieldInfo) -> serde_json::Value {
+    let items = item_schema(parent, field.datatype());
+    match field.case() {
+        FieldCase::Unit | FieldCase::Const(_) => items,
+        FieldCase::Vector => json!({ "type": "array", "items": items }),
+        FieldCase::Array(l) => json!({
+            "type": "array",
+            "items": items,
+            "minItems": l,
+            "maxItems": l,
+        }),
+    }
+}
+
+fn item_schema(parent: &MessagePath, datatype: &DataType) -> serde_json::Value {
+    let integer = |min: i64, max: u64| json!({ "type": "integer", "minimum": min, "maximum": max });
+    let time = |sec: serde_json::Value, nsec: serde_json::Value| {
+        json!({
+            "type": "object",
+            "properties": { "sec": sec, "nsec": nsec },
+            "required": ["sec", "nsec"],
+            "additionalProperties": false,
+        })
+    };
+    match datatype {
+        DataType::Bool => json!({ "type": "boolean" }),
+        DataType::I8(_) => integer(i8::MIN.into(), i8::MAX as u64),
+        DataType::I16 => integer(i16::MIN.into(), i16::MAX as u64),
+        DataType::I32 => integer(i32::MIN.into(), i32::MAX as u64),
+        DataType::I64 => integer(i64::MIN, i64::MAX as u64),
+        DataType::U8(_) => integer(0, u8::MAX.into()),
+        DataType::U16 => integer(0, u16::MAX.into()),
+        DataType::U32 => integer(0, u32::MAX.into()),
+        DataType::U64 => integer(0, u64::MAX),
+        DataType::F32 | DataType::F64 => json!({ "type": "number" }),
+        DataType::String => json!({ "type": "string" }),
+        DataType::Time => time(
+            item_schema(parent, &DataType::U32),
+            item_schema(parent, &DataType::U32),
+        ),
+        DataType::Duration => time(
+            item_schema(parent, &DataType::I32),
+            item_schema(parent, &DataType::I32),
+        ),
+        DataType::LocalMessage(name) => message_ref(&parent.peer(name.as_str())),
+        DataType::GlobalMessage(path) => message_ref(path),
+    }
+}
+
+fn message_ref(path: &MessagePath) -> serde_json::Value {
+    // Slashes in JSON pointers need to be escaped
+    json!({ "$ref": format!("#/$defs/{}~1{}", path.package(), path.name()) })
+}
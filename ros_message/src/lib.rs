@@ -1,9 +1,15 @@
-//! Crate for describing ROS messages, and handling MSG and SRV files.
+//! Crate for describing ROS messages, and handling MSG, SRV and ACTION files.
 #![warn(missing_docs)]
 
+mod action;
 mod data_type;
+mod diagnostic;
 mod error;
+mod export;
 mod field_info;
+mod field_path;
+#[cfg(feature = "json_schema")]
+mod json_schema;
 mod message_path;
 mod msg;
 mod parse_msg;
@@ -12,12 +18,18 @@ mod srv;
 mod tests;
 mod time;
 mod value;
+mod value_diff;
+pub mod wire;
 
+pub use action::Action;
 pub use data_type::{DataType, I8Variant, U8Variant};
+pub use diagnostic::Diagnostic;
 pub use error::{Error, Result};
 pub use field_info::{FieldCase, FieldInfo};
-pub use message_path::MessagePath;
+pub use field_path::{FieldPath, PathComponent, Selection};
+pub use message_path::{is_valid_package_name, MessagePath};
 pub use msg::Msg;
 pub use srv::Srv;
 pub use time::{Duration, Time};
 pub use value::{MessageValue, Value};
+pub use value_diff::{Comparator, ValueChange};
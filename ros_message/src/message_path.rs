@@ -15,6 +15,17 @@ pub struct MessagePath {
     name: String,
 }
 
+/// Checks whether a package name follows [REP 144](https://www.ros.org/reps/rep-0144.html).
+///
+/// # Examples
+///
+/// ```
+/// # use ros_message::is_valid_package_name;
+/// #
+/// assert!(is_valid_package_name("geometry_msgs"));
+/// assert!(!is_valid_package_name("GeometryMsgs"));
+/// assert!(!is_valid_package_name("geometry__msgs"));
+/// ```
 pub fn is_valid_package_name(package: &str) -> bool {
     lazy_static! {
         static ref RE_PACKAGE_CORRECT_CHAR_SET_AND_LENGTH: Regex =
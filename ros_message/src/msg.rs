@@ -1,4 +1,4 @@
-use crate::{parse_msg::match_lines, DataType, Error, FieldInfo, MessagePath, Result, Value};
+use crate::{parse_msg::match_lines, Diagnostic, Error, FieldInfo, MessagePath, Result, Value};
 use serde_derive::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::convert::TryFrom;
@@ -26,7 +26,8 @@ impl Msg {
     ///
     /// # Errors
     ///
-    /// Returns an error if there is an error parsing the message source.
+    /// Returns an error if there is an error parsing the message source, with a
+    /// [`Diagnostic`] for every line that failed.
     ///
     /// # Examples
     ///
@@ -53,12 +54,24 @@ impl Msg {
     /// # }
     /// ```
     pub fn new(path: MessagePath, source: &str) -> Result<Msg> {
-        let source = source.trim().to_owned();
-        let fields = match_lines(&source)?;
+        Self::parse(&path, source, 1).map_err(|diagnostics| Error::InvalidSource {
+            file: format!("{}.msg", path),
+            diagnostics,
+        })
+    }
+
+    /// Parses a message whose source starts at the given line of its file.
+    pub(crate) fn parse(
+        path: &MessagePath,
+        source: &str,
+        first_line: usize,
+    ) -> std::result::Result<Msg, Vec<Diagnostic>> {
+        // Lines are parsed before trimming, so they keep their numbers and columns
+        let fields = match_lines(source, first_line)?;
         Ok(Msg {
-            path,
+            path: path.clone(),
             fields,
-            source,
+            source: source.trim().to_owned(),
         })
     }
 
@@ -159,11 +172,7 @@ impl Msg {
     pub fn dependencies(&self) -> Vec<MessagePath> {
         self.fields
             .iter()
-            .filter_map(|field| match field.datatype() {
-                DataType::LocalMessage(ref name) => Some(self.path.peer(name)),
-                DataType::GlobalMessage(ref message) => Some(message.clone()),
-                _ => None,
-            })
+            .filter_map(|field| field.datatype().message_path(&self.path))
             .collect()
     }
 
@@ -176,7 +185,6 @@ impl Msg {
     /// # Errors
     ///
     /// An error is returned if some dependency is missing in the hashes.
-    #[cfg(test)]
     pub fn calculate_md5(&self, hashes: &HashMap<MessagePath, String>) -> Result<String> {
         use md5::{Digest, Md5};
 
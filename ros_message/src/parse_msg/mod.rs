@@ -1,4 +1,4 @@
-use crate::{Error, FieldCase, FieldInfo, Result};
+use crate::{Diagnostic, Error, FieldCase, FieldInfo, Result};
 use lazy_static::lazy_static;
 use regex::Regex;
 
@@ -15,11 +15,72 @@ struct FieldLine {
     field_name: String,
 }
 
-#[inline]
-pub fn match_lines(data: &str) -> Result<Vec<FieldInfo>> {
-    data.split('\n')
-        .filter_map(match_line)
-        .collect::<Result<_>>()
+/// Parses all lines, numbering them from `first_line`.
+///
+/// Every line that fails to parse is reported, rather than just the first one.
+pub fn match_lines(
+    data: &str,
+    first_line: usize,
+) -> std::result::Result<Vec<FieldInfo>, Vec<Diagnostic>> {
+    let mut fields = vec![];
+    let mut diagnostics = vec![];
+    for (idx, line) in data.split('\n').enumerate() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        match match_line(line) {
+            None => {}
+            Some(Ok(field)) => fields.push(field),
+            Some(Err(err)) => diagnostics.push(Diagnostic::new(
+                first_line + idx,
+                line,
+                error_span(line, &err),
+                err.to_string(),
+            )),
+        }
+    }
+    if diagnostics.is_empty() {
+        Ok(fields)
+    } else {
+        Err(diagnostics)
+    }
+}
+
+/// Splits the source at lines matching the separator, returning each section with its first line.
+pub fn split_sections<'a>(separator: &Regex, source: &'a str) -> Vec<(&'a str, usize)> {
+    let mut sections = vec![];
+    let mut start = 0;
+    for separator in separator.find_iter(source) {
+        sections.push((&source[start..separator.start()], line_at(source, start)));
+        start = separator.end();
+    }
+    sections.push((&source[start..], line_at(source, start)));
+    sections
+}
+
+fn line_at(source: &str, byte: usize) -> usize {
+    source[..byte].matches('\n').count() + 1
+}
+
+/// Finds the part of the line that caused the error, falling back to all of its content.
+fn error_span(line: &str, err: &Error) -> std::ops::Range<usize> {
+    let content = line.split('#').next().unwrap_or_default();
+    let start = content.len() - content.trim_start().len();
+    let end = content.trim_end().len().max(start);
+    // Constant values come after the name, which could contain the same text
+    let (culprit, search_from) = match err {
+        Error::UnsupportedDataType { name, .. } | Error::InvalidMessagePath { name, .. } => {
+            (name.as_str(), start)
+        }
+        Error::BadConstant { value, .. } => {
+            (value.as_str(), content.find('=').map_or(start, |v| v + 1))
+        }
+        _ => ("", start),
+    };
+    match line[search_from..].find(culprit) {
+        Some(offset) if !culprit.is_empty() => {
+            search_from + offset..search_from + offset + culprit.len()
+        }
+        _ => start..end,
+    }
 }
 
 fn match_line(data: &str) -> Option<Result<FieldInfo>> {
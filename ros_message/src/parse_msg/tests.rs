@@ -111,9 +111,10 @@ fn match_line_works_on_legal_data() {
 
 #[test]
 fn match_lines_parses_real_messages() {
-    let data = match_lines(include_str!(
-        "../../../msg_examples/geometry_msgs/msg/TwistWithCovariance.msg"
-    ))
+    let data = match_lines(
+        include_str!("../../../msg_examples/geometry_msgs/msg/TwistWithCovariance.msg"),
+        1,
+    )
     .unwrap();
     assert_eq!(
         vec![
@@ -123,9 +124,10 @@ fn match_lines_parses_real_messages() {
         data
     );
 
-    let data = match_lines(include_str!(
-        "../../../msg_examples/geometry_msgs/msg/PoseStamped.msg"
-    ))
+    let data = match_lines(
+        include_str!("../../../msg_examples/geometry_msgs/msg/PoseStamped.msg"),
+        1,
+    )
     .unwrap();
     assert_eq!(
         vec![
@@ -135,3 +137,37 @@ fn match_lines_parses_real_messages() {
         data
     );
 }
+
+#[test]
+fn match_lines_reports_all_bad_lines() {
+    let diagnostics = match_lines(
+        "int32 a\nfloat64[x] b # comment\n\n  a/b/c d\nuint8 X300=300\r\nstring ok=#",
+        3,
+    )
+    .unwrap_err();
+    let positions = diagnostics
+        .iter()
+        .map(|v| (v.line(), v.columns(), v.snippet()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            (4, 1..13, "float64[x] b # comment"),
+            (6, 3..8, "  a/b/c d"),
+            (7, 12..15, "uint8 X300=300"),
+        ],
+        positions
+    );
+    assert_eq!(
+        "7:12: bad constant value `300` of type uint8 in field X300\n  |\n7 | uint8 X300=300\n  |            ^^^",
+        diagnostics[2].to_string()
+    );
+}
+
+#[test]
+fn split_sections_tracks_lines() {
+    let separator = Regex::new("(?m)^---$").unwrap();
+    assert_eq!(
+        vec![("a\nb\n", 1), ("\nc\n", 3), ("", 5)],
+        split_sections(&separator, "a\nb\n---\nc\n---")
+    );
+}
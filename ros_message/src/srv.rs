@@ -1,3 +1,4 @@
+use crate::parse_msg::split_sections;
 use crate::{Error, MessagePath, Msg, Result};
 use lazy_static::lazy_static;
 use regex::RegexBuilder;
@@ -97,16 +98,10 @@ impl Srv {
                 .build()
                 .expect("Invalid regex `^---$`");
         }
-        let (req, res) = match RE_SPLIT.split(source).collect::<Vec<_>>().as_slice() {
-            &[req] => (req, ""),
-            &[req, res] => (req, res),
-            &[] => {
-                return Err(Error::BadMessageContent(format!(
-                    "Service {} does not have any content",
-                    path
-                )))
-            }
-            v => {
+        let ((req, req_line), (res, res_line)) = match split_sections(&RE_SPLIT, source)[..] {
+            [req] => (req, ("", req.1)),
+            [req, res] => (req, res),
+            ref v => {
                 return Err(Error::BadMessageContent(format!(
                     "Service {} is split into {} parts",
                     path,
@@ -115,10 +110,15 @@ impl Srv {
             }
         };
 
-        Ok((
-            Msg::new(path.peer(format!("{}Req", path.name())), req)?,
-            Msg::new(path.peer(format!("{}Res", path.name())), res)?,
-        ))
+        let req = Msg::parse(&path.peer(format!("{}Req", path.name())), req, req_line);
+        let res = Msg::parse(&path.peer(format!("{}Res", path.name())), res, res_line);
+        match (req, res) {
+            (Ok(req), Ok(res)) => Ok((req, res)),
+            (req, res) => Err(Error::InvalidSource {
+                file: format!("{}.srv", path),
+                diagnostics: req.err().into_iter().chain(res.err()).flatten().collect(),
+            }),
+        }
     }
 }
 
@@ -0,0 +1,55 @@
+use crate::{Action, MessagePath};
+use std::collections::HashMap;
+use std::convert::TryInto;
+
+fn path(name: &str) -> MessagePath {
+    name.try_into().expect("Unexpectedly bad message path")
+}
+
+#[test]
+fn md5_matches_genaction() {
+    let action = Action::new(
+        path("actionlib_tutorials/Fibonacci"),
+        "#goal definition\nint32 order\n---\n#result definition\nint32[] sequence\n---\n#feedback\nint32[] sequence\n",
+    )
+    .unwrap();
+
+    let mut hashes = HashMap::new();
+    hashes.insert(
+        path("std_msgs/Header"),
+        "2176decaecbce78abc3b96ef049fabed".to_owned(),
+    );
+    hashes.insert(
+        path("actionlib_msgs/GoalID"),
+        "302881f31927c1df708a2dbab0e80ee8".to_owned(),
+    );
+    hashes.insert(
+        path("actionlib_msgs/GoalStatus"),
+        "d388f9b87b3c471f784434d671988d4a".to_owned(),
+    );
+    let expected = [
+        ("FibonacciGoal", "6889063349a00b249bd1661df429d822"),
+        ("FibonacciResult", "b81e37d2a31925a0e8ae261a8699cb79"),
+        ("FibonacciFeedback", "b81e37d2a31925a0e8ae261a8699cb79"),
+        ("FibonacciActionGoal", "006871c7fa1d0e3d5fe2226bf17b2a94"),
+        ("FibonacciActionResult", "bee73a9fe29ae25e966e105f5553dd03"),
+        (
+            "FibonacciActionFeedback",
+            "73b8497a9f629a31c0020900e4148f07",
+        ),
+        ("FibonacciAction", "f59df5767bf7634684781c92598b2406"),
+    ];
+    for (message, (name, md5sum)) in action.messages().iter().zip(expected.iter()) {
+        assert_eq!(message.path().name(), *name);
+        let hash = message.calculate_md5(&hashes).unwrap();
+        hashes.insert(message.path().clone(), hash.clone());
+        assert_eq!(hash, *md5sum, "MD5 sum mismatch for {}", name);
+    }
+}
+
+#[test]
+fn requires_three_parts() {
+    assert!(Action::new(path("foo/Bar"), "int32 a\n---\nint32 b\n").is_err());
+    assert!(Action::new(path("foo/Bar"), "---\n---\n---\n").is_err());
+    assert!(Action::new(path("foo/Bar"), "---\n---\n").is_ok());
+}
@@ -0,0 +1,51 @@
+use crate::{Action, Error, MessagePath, Msg, Srv};
+use std::convert::TryInto;
+
+fn path(name: &str) -> MessagePath {
+    name.try_into().expect("Unexpectedly bad message path")
+}
+
+fn lines(err: &Error) -> Vec<usize> {
+    err.diagnostics().iter().map(|v| v.line()).collect()
+}
+
+#[test]
+fn message_errors_point_into_file() {
+    let err = Msg::new(path("foo/Bar"), "\n\n# header\nint32[ a\nint32 b\n0a/B c\n").unwrap_err();
+    assert_eq!(lines(&err), vec![4, 6]);
+    assert_eq!(
+        err.to_string(),
+        "foo/Bar.msg:4:1: bad content in message: `int32[ a`\n  |\n4 | int32[ a\n  | ^^^^^^^^\n\n\
+         foo/Bar.msg:6:1: message path `0a/B` is invalid, package name needs to follow REP 144 \
+         rules (https://www.ros.org/reps/rep-0144.html)\n  |\n6 | 0a/B c\n  | ^^^^",
+    );
+
+    let err = err.with_file("/share/foo/msg/Bar.msg");
+    assert!(err.to_string().starts_with("/share/foo/msg/Bar.msg:4:1: "));
+    assert!(Error::BadMessageContent("x".into())
+        .with_file("Bar.msg")
+        .diagnostics()
+        .is_empty());
+}
+
+#[test]
+fn service_errors_cover_request_and_response() {
+    let err = Srv::new(
+        path("foo/Bar"),
+        "int32 a\nint8 b=300\n---\nint32 c\nbool[-1] d\n",
+    )
+    .unwrap_err();
+    assert_eq!(lines(&err), vec![2, 5]);
+    assert!(err.to_string().starts_with("foo/Bar.srv:2:8: "));
+}
+
+#[test]
+fn action_errors_cover_all_sections() {
+    let err = Action::new(
+        path("foo/Bar"),
+        "int32 a b\n---\n# result\nint32 c\n---\n\nint32 d=\n",
+    )
+    .unwrap_err();
+    assert_eq!(lines(&err), vec![1, 7]);
+    assert!(err.to_string().starts_with("foo/Bar.action:1:1: "));
+}
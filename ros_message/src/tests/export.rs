@@ -0,0 +1,96 @@
+use crate::{MessagePath, Msg, Srv};
+use std::convert::TryInto;
+
+fn msg(path: &str, source: &str) -> Msg {
+    Msg::new(path.try_into().unwrap(), source).unwrap()
+}
+
+#[test]
+fn converts_msg_to_ros2() {
+    let message = msg(
+        "foo/Bar",
+        "char C=99\nfloat64 PI=3.14\nbool ENABLED=1\n\
+         Header header\ncharData dataPoints\nduration[] timeouts\nchar lastByte\nuint8[16] HTTPHeader",
+    );
+    assert_eq!(
+        message.to_ros2_msg(),
+        "uint8 C=99\nfloat64 PI=3.14\nbool ENABLED=true\n\
+         std_msgs/Header header\ncharData data_points\n\
+         builtin_interfaces/Duration[] timeouts\nuint8 last_byte\nuint8[16] http_header\n",
+    );
+}
+
+#[test]
+fn converts_msg_to_ros2_idl() {
+    let message = msg(
+        "foo/Bar",
+        "string NAME=a \"b\"\nbool FLAG=0\nHeader header\ntime[] stamps\n\
+         geometry_msgs/Point[2] points\nfloat32 scaleX",
+    );
+    assert_eq!(
+        message.to_ros2_idl(),
+        r#"// Converted from foo/Bar
+
+#include "builtin_interfaces/msg/Time.idl"
+#include "geometry_msgs/msg/Point.idl"
+#include "std_msgs/msg/Header.idl"
+
+module foo {
+  module msg {
+    module Bar_Constants {
+      const string NAME = "a \"b\"";
+      const boolean FLAG = FALSE;
+    };
+    struct Bar {
+      std_msgs::msg::Header header;
+      sequence<builtin_interfaces::msg::Time> stamps;
+      geometry_msgs::msg::Point points[2];
+      float scale_x;
+    };
+  };
+};
+"#,
+    );
+}
+
+#[test]
+fn escapes_string_constants() {
+    let message = msg("foo/Bar", "string PATH=C:\\temp \"x\"\x07\u{e9}");
+    assert_eq!(
+        message.to_ros2_msg(),
+        "string PATH=\"C:\\temp \\\"x\\\"\x07\u{e9}\"\n",
+    );
+    assert!(message
+        .to_ros2_idl()
+        .contains("const string PATH = \"C:\\\\temp \\\"x\\\"\\x07\u{e9}\";"));
+}
+
+#[test]
+fn converts_srv_to_ros2() {
+    let path: MessagePath = "foo/Trigger".try_into().unwrap();
+    let srv = Srv::new(path, "int64 RETRIES=3\n---\nbool success\nstring message").unwrap();
+    assert_eq!(
+        srv.to_ros2_srv(),
+        "int64 RETRIES=3\n---\nbool success\nstring message\n",
+    );
+    assert_eq!(
+        srv.to_ros2_idl(),
+        r#"// Converted from foo/Trigger
+
+module foo {
+  module srv {
+    module Trigger_Request_Constants {
+      const int64 RETRIES = 3;
+    };
+    struct Trigger_Request {
+      uint8 structure_needs_at_least_one_member;
+    };
+    struct Trigger_Response {
+      boolean success;
+      string message;
+    };
+  };
+};
+"#,
+    );
+}
@@ -0,0 +1,134 @@
+use crate::{FieldPath, MessageValue, Selection, Value};
+use std::borrow::Cow;
+
+fn message() -> MessageValue {
+    let status = |name: &str| {
+        let mut status = MessageValue::new();
+        status.insert("name".into(), Value::String(name.into()));
+        Value::Message(status)
+    };
+    let mut position = MessageValue::new();
+    position.insert("x".into(), Value::F64(1.5));
+    let mut pose = MessageValue::new();
+    pose.insert("position".into(), Value::Message(position));
+    let mut message = MessageValue::new();
+    message.insert("pose".into(), Value::Message(pose));
+    message.insert("ranges".into(), (0..10u16).collect());
+    message.insert(
+        "status".into(),
+        Value::Array(vec![status("a"), status("b"), status("c")]),
+    );
+    message
+}
+
+#[test]
+fn parses_paths() {
+    let path = FieldPath::new("pose.position.x").unwrap();
+    assert_eq!(path.components().len(), 3);
+    assert_eq!(path.components()[1].name(), "position");
+    assert!(path.is_single());
+
+    let selections = ["a[3]", "a[2:8]", "a[:8]", "a[2:]", "a[*]", "a[:]"]
+        .iter()
+        .map(|v| FieldPath::new(v).unwrap().components()[0].selection())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        selections,
+        vec![
+            Some(Selection::Index(3)),
+            Some(Selection::Range(Some(2), Some(8))),
+            Some(Selection::Range(None, Some(8))),
+            Some(Selection::Range(Some(2), None)),
+            Some(Selection::All),
+            Some(Selection::Range(None, None)),
+        ]
+    );
+    assert!(!FieldPath::new("status[*].name").unwrap().is_single());
+}
+
+#[test]
+fn displays_like_parsed_source() {
+    for path in &[
+        "pose.position.x",
+        "ranges[10]",
+        "ranges[2:8]",
+        "a[:3].b[4:]",
+        "s[*].name",
+    ] {
+        assert_eq!(FieldPath::new(path).unwrap().to_string(), *path);
+    }
+}
+
+#[test]
+fn builds_paths_from_fields() {
+    let root = FieldPath::default();
+    assert_eq!(root.to_string(), "");
+    let path = root
+        .field("status")
+        .with_selection(Selection::Index(1))
+        .field("name");
+    assert_eq!(path, FieldPath::new("status[1].name").unwrap());
+    assert_eq!(
+        root.field("poses")
+            .with_selection(Selection::All)
+            .field("x"),
+        FieldPath::new("poses[*].x").unwrap()
+    );
+}
+
+#[test]
+fn rejects_bad_syntax() {
+    for path in &[
+        "", "a.", ".a", "a..b", "a[", "a[]", "a[-1]", "a[x]", "a[3:1]", "1a", "a-b", "a[1][2]",
+    ] {
+        assert!(FieldPath::new(path).is_err(), "{} should fail", path);
+    }
+}
+
+#[test]
+fn selects_values() {
+    let message = message();
+    let select = |path: &str| FieldPath::new(path).unwrap().select(&message);
+    assert_eq!(
+        select("pose.position.x"),
+        vec![Cow::Borrowed(&Value::F64(1.5))]
+    );
+    assert_eq!(select("ranges[3]"), vec![Cow::Borrowed(&Value::U16(3))]);
+    assert_eq!(
+        select("ranges[8:]"),
+        vec![Cow::Borrowed(&Value::U16(8)), Cow::Borrowed(&Value::U16(9))]
+    );
+    assert_eq!(
+        select("ranges[:2]"),
+        vec![Cow::Borrowed(&Value::U16(0)), Cow::Borrowed(&Value::U16(1))]
+    );
+    assert_eq!(select("ranges[8:20]").len(), 2);
+    assert!(select("ranges[20]").is_empty());
+    assert_eq!(
+        select("status[*].name"),
+        vec![
+            Cow::Borrowed(&Value::String("a".into())),
+            Cow::Borrowed(&Value::String("b".into())),
+            Cow::Borrowed(&Value::String("c".into())),
+        ]
+    );
+    assert!(select("status[*].name")
+        .iter()
+        .all(|v| matches!(v, Cow::Borrowed(_))));
+    // Items of typed arrays are not stored as values, so they can't be borrowed
+    assert!(matches!(select("ranges[3]")[0], Cow::Owned(Value::U16(3))));
+    assert!(select("pose.orientation").is_empty());
+    assert!(select("pose[0]").is_empty());
+    assert!(select("ranges.x").is_empty());
+
+    let path = FieldPath::new("status[1].name").unwrap();
+    assert_eq!(
+        path.get(&message).as_deref(),
+        Some(&Value::String("b".into()))
+    );
+    assert!(matches!(path.get(&message), Some(Cow::Borrowed(_))));
+    assert!(FieldPath::new("ranges[1:2]")
+        .unwrap()
+        .get(&message)
+        .is_none());
+}
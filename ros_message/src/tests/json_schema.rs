@@ -0,0 +1,72 @@
+use crate::{Error, Msg};
+use std::collections::HashMap;
+use std::convert::TryInto;
+
+fn msg(path: &str, source: &str) -> Msg {
+    Msg::new(path.try_into().unwrap(), source).unwrap()
+}
+
+#[test]
+fn json_schema_describes_fields() {
+    let message = msg(
+        "foo/Bar",
+        "int8 A=1\nint8 a\nfloat32 b\nstring c\nduration d\nuint64[3] e\nbool[] f",
+    );
+    let schema = message.json_schema(&HashMap::new()).unwrap();
+    assert_eq!(schema["title"], "foo/Bar");
+    assert_eq!(schema["additionalProperties"], false);
+    assert_eq!(
+        schema["required"],
+        serde_json::json!(["a", "b", "c", "d", "e", "f"]),
+    );
+    let properties = &schema["properties"];
+    assert!(properties.get("A").is_none());
+    assert_eq!(
+        properties["a"],
+        serde_json::json!({ "type": "integer", "minimum": -128, "maximum": 127 }),
+    );
+    assert_eq!(properties["b"]["type"], "number");
+    assert_eq!(properties["c"]["type"], "string");
+    assert_eq!(
+        properties["d"]["required"],
+        serde_json::json!(["sec", "nsec"])
+    );
+    assert_eq!(properties["d"]["properties"]["nsec"]["minimum"], i32::MIN);
+    assert_eq!(properties["e"]["items"]["maximum"], u64::MAX);
+    assert_eq!(properties["e"]["minItems"], 3);
+    assert_eq!(properties["e"]["maxItems"], 3);
+    assert_eq!(properties["f"]["items"]["type"], "boolean");
+    assert!(properties["f"].get("minItems").is_none());
+    assert!(schema.get("$defs").is_none());
+}
+
+#[test]
+fn json_schema_collects_nested_definitions() {
+    let header = msg("std_msgs/Header", "uint32 seq\ntime stamp\nstring frame_id");
+    let inner = msg("foo/Inner", "Header header");
+    let outer = msg("foo/Outer", "Inner[] items\nInner single");
+    let mut dependencies = HashMap::new();
+    dependencies.insert(inner.path().clone(), inner);
+
+    match outer.json_schema(&dependencies) {
+        Err(Error::MessageDependencyMissing { package, name }) => {
+            assert_eq!(package, "std_msgs");
+            assert_eq!(name, "Header");
+        }
+        other => panic!("Unexpected result: {:?}", other),
+    }
+
+    dependencies.insert(header.path().clone(), header);
+    let schema = outer.json_schema(&dependencies).unwrap();
+    assert_eq!(schema["properties"]["single"]["$ref"], "#/$defs/foo~1Inner");
+    let definitions = schema["$defs"].as_object().unwrap();
+    assert_eq!(definitions.len(), 2);
+    assert_eq!(
+        definitions["foo/Inner"]["properties"]["header"]["$ref"],
+        "#/$defs/std_msgs~1Header",
+    );
+    assert_eq!(
+        definitions["std_msgs/Header"]["properties"]["stamp"]["properties"]["sec"]["maximum"],
+        u32::MAX,
+    );
+}
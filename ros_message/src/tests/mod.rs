@@ -1,6 +1,14 @@
+mod action;
 mod data_type;
+mod diagnostic;
+mod export;
 mod field_info;
+mod field_path;
+#[cfg(feature = "json_schema")]
+mod json_schema;
 mod message_path;
 mod msg;
 mod time;
 mod value;
+mod value_diff;
+mod wire;
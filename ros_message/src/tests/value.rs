@@ -1,4 +1,5 @@
 use crate::{MessageValue, Time, Value};
+use std::convert::TryFrom;
 
 #[test]
 fn display() {
@@ -79,3 +80,88 @@ poses:
       z: 10"#
     )
 }
+
+#[test]
+fn packs_numeric_arrays() {
+    assert_eq!(
+        Value::from(vec![1u8, 2, 3]).as_bytes(),
+        Some(&[1, 2, 3][..])
+    );
+    assert_eq!(
+        Value::from([1.5f32, 2.5]).as_f32_slice(),
+        Some(&[1.5, 2.5][..])
+    );
+    assert_eq!(
+        vec![1.0f64, 2.0]
+            .into_iter()
+            .collect::<Value>()
+            .as_f64_slice(),
+        Some(&[1.0, 2.0][..]),
+    );
+    assert!(matches!(Value::from(vec![1i16]), Value::I16Array(_)));
+    assert!(matches!(Value::from(vec![true, false]), Value::Array(_)));
+    assert!(matches!(Value::from(Vec::<u8>::new()), Value::Array(_)));
+    assert!(matches!(
+        Value::from(vec![Value::U8(1), Value::U16(2)]),
+        Value::Array(_)
+    ));
+
+    assert_eq!(
+        Vec::<u8>::try_from(Value::Bytes(vec![4, 5])),
+        Ok(vec![4, 5])
+    );
+    assert_eq!(
+        <[f64; 2]>::try_from(Value::F64Array(vec![1.0, 2.0])),
+        Ok([1.0, 2.0])
+    );
+    assert!(Vec::<u16>::try_from(Value::Bytes(vec![4, 5])).is_err());
+}
+
+#[test]
+fn typed_arrays_equal_boxed_arrays() {
+    let boxed = Value::Array(vec![Value::F32(1.0), Value::F32(2.0)]);
+    assert_eq!(Value::F32Array(vec![1.0, 2.0]), boxed);
+    assert_eq!(boxed, Value::F32Array(vec![1.0, 2.0]));
+    assert_ne!(Value::F32Array(vec![1.0]), boxed);
+    assert_ne!(Value::F64Array(vec![1.0, 2.0]), boxed);
+    assert_eq!(Value::Bytes(vec![]), Value::Array(vec![]));
+    assert_ne!(Value::Bytes(vec![]), Value::U8(0));
+}
+
+#[test]
+fn display_typed_arrays() {
+    let mut message = MessageValue::new();
+    message.insert("data".into(), Value::Bytes(vec![1, 2]));
+    message.insert("ranges".into(), Value::F32Array(vec![0.5]));
+    assert_eq!(
+        format!("{}", Value::Message(message)),
+        "\ndata: \n  - 1\n  - 2\nranges: \n  - 0.5",
+    );
+}
+
+#[test]
+fn serde_writes_typed_arrays_like_boxed_arrays() {
+    let value = Value::Bytes(vec![1, 2, 3]);
+    let json = serde_json::to_string(&value).unwrap();
+    assert_eq!(json, r#"{"Array":[{"U8":1},{"U8":2},{"U8":3}]}"#);
+    assert_eq!(
+        json,
+        serde_json::to_string(&Value::Array(vec![
+            Value::U8(1),
+            Value::U8(2),
+            Value::U8(3)
+        ]))
+        .unwrap(),
+    );
+    assert_eq!(serde_json::from_str::<Value>(&json).unwrap(), value);
+    assert_eq!(
+        serde_json::to_string(&Value::F64Array(vec![0.5])).unwrap(),
+        r#"{"Array":[{"F64":0.5}]}"#,
+    );
+
+    // Typed arrays are still read
+    assert!(matches!(
+        serde_json::from_str::<Value>(r#"{"Bytes":[1,2,3]}"#).unwrap(),
+        Value::Bytes(v) if v == [1, 2, 3]
+    ));
+}
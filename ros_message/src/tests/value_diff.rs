@@ -0,0 +1,136 @@
+use crate::{Comparator, FieldPath, MessageValue, Time, Value, ValueChange};
+
+fn path(path: &str) -> FieldPath {
+    path.parse().unwrap()
+}
+
+fn pose(x: f64) -> Value {
+    let mut position = MessageValue::new();
+    position.insert("x".into(), Value::F64(x));
+    let mut pose = MessageValue::new();
+    pose.insert("position".into(), Value::Message(position));
+    Value::Message(pose)
+}
+
+fn message(seq: u32, xs: &[f64]) -> MessageValue {
+    let mut header = MessageValue::new();
+    header.insert("seq".into(), Value::U32(seq));
+    header.insert("stamp".into(), Value::Time(Time { sec: seq, nsec: 0 }));
+    let mut message = MessageValue::new();
+    message.insert("header".into(), Value::Message(header));
+    message.insert("poses".into(), xs.iter().map(|&x| pose(x)).collect());
+    message.insert("data".into(), Value::Bytes(vec![1, 2, 3]));
+    message
+}
+
+#[test]
+fn identical_messages_have_no_changes() {
+    let value = message(1, &[1.0, 2.0]);
+    assert!(Comparator::new().matches(&value, &value));
+
+    let mut boxed = value.clone();
+    boxed.insert(
+        "data".into(),
+        Value::Array(vec![Value::U8(1), Value::U8(2), Value::U8(3)]),
+    );
+    assert!(Comparator::new().diff(&value, &boxed).is_empty());
+}
+
+#[test]
+fn lists_changes_with_paths() {
+    let expected = message(1, &[1.0, 2.0, 3.0]);
+    let mut actual = message(1, &[1.0, 2.5]);
+    actual.insert("data".into(), Value::Bytes(vec![1, 9, 3]));
+    actual.remove("header");
+    actual.insert("extra".into(), Value::Bool(true));
+
+    let changes = Comparator::new().diff(&expected, &actual);
+    assert_eq!(
+        changes,
+        vec![
+            ValueChange::Changed {
+                path: path("data[1]"),
+                expected: Value::U8(2),
+                actual: Value::U8(9),
+            },
+            ValueChange::FieldAdded {
+                path: path("extra")
+            },
+            ValueChange::FieldRemoved {
+                path: path("header")
+            },
+            ValueChange::LengthChanged {
+                path: path("poses"),
+                expected: 3,
+                actual: 2,
+            },
+            ValueChange::Changed {
+                path: path("poses[1].position.x"),
+                expected: Value::F64(2.0),
+                actual: Value::F64(2.5),
+            },
+        ]
+    );
+    assert_eq!(
+        changes[4].to_string(),
+        "`poses[1].position.x` changed from 2 to 2.5"
+    );
+    assert_eq!(changes[3].to_string(), "`poses` changed length from 3 to 2");
+}
+
+#[test]
+fn applies_float_tolerances() {
+    let expected = message(1, &[1.0, 1000.0]);
+    let actual = message(1, &[1.0 + 1e-7, 1000.01]);
+    assert_eq!(Comparator::new().diff(&expected, &actual).len(), 2);
+    assert_eq!(
+        Comparator::new()
+            .absolute_tolerance(1e-6)
+            .diff(&expected, &actual)
+            .len(),
+        1
+    );
+    assert!(Comparator::new()
+        .absolute_tolerance(1e-6)
+        .relative_tolerance(1e-4)
+        .matches(&expected, &actual));
+
+    let nan = message(1, &[f64::NAN]);
+    assert!(Comparator::new().matches(&nan, &nan));
+    let mut floats = MessageValue::new();
+    floats.insert("ranges".into(), Value::F32Array(vec![1.0, 2.0]));
+    let mut close = MessageValue::new();
+    close.insert("ranges".into(), Value::F32Array(vec![1.0, 2.001]));
+    assert!(!Comparator::new().matches(&floats, &close));
+    assert!(Comparator::new()
+        .absolute_tolerance(0.01)
+        .matches(&floats, &close));
+}
+
+#[test]
+fn skips_ignored_fields() {
+    let expected = message(1, &[1.0, 2.0, 3.0]);
+    let actual = message(2, &[1.0, 5.0, 6.0]);
+    let comparator = Comparator::new()
+        .ignore("header.seq".parse().unwrap())
+        .ignore("header.stamp".parse().unwrap());
+    assert_eq!(comparator.diff(&expected, &actual).len(), 2);
+    assert!(comparator
+        .clone()
+        .ignore("poses[1:].position".parse().unwrap())
+        .matches(&expected, &actual));
+    assert_eq!(
+        comparator
+            .clone()
+            .ignore("poses[1]".parse().unwrap())
+            .diff(&expected, &actual),
+        vec![ValueChange::Changed {
+            path: path("poses[2].position.x"),
+            expected: Value::F64(3.0),
+            actual: Value::F64(6.0),
+        }]
+    );
+    assert!(comparator
+        .ignore("poses".parse().unwrap())
+        .matches(&expected, &actual));
+}
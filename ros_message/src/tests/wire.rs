@@ -0,0 +1,155 @@
+use crate::wire::{self, Error};
+use crate::{Duration, Time};
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Header {
+    seq: u32,
+    stamp: Time,
+    frame_id: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Sample {
+    header: Header,
+    valid: bool,
+    offset: Duration,
+    position: [f64; 3],
+    covariance_type: (u8, i16),
+    data: Vec<i16>,
+    names: Vec<String>,
+}
+
+fn sample() -> Sample {
+    Sample {
+        header: Header {
+            seq: 7,
+            stamp: Time { sec: 1, nsec: 2 },
+            frame_id: "map".into(),
+        },
+        valid: true,
+        offset: Duration { sec: -1, nsec: 5 },
+        position: [1.0, -2.5, 0.0],
+        covariance_type: (3, -4),
+        data: vec![1, -1],
+        names: vec!["a".into(), "".into()],
+    }
+}
+
+#[test]
+fn encodes_primitives_little_endian() {
+    assert_eq!(
+        wire::to_vec(&0x1234_5678u32).unwrap(),
+        [4, 0, 0, 0, 0x78, 0x56, 0x34, 0x12]
+    );
+    assert_eq!(wire::to_vec(&-2i16).unwrap(), [2, 0, 0, 0, 0xfe, 0xff]);
+    assert_eq!(wire::to_vec(&true).unwrap(), [1, 0, 0, 0, 1]);
+    assert_eq!(
+        wire::to_vec(&1.5f64).unwrap(),
+        [8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xf8, 0x3f]
+    );
+}
+
+#[test]
+fn encodes_strings_and_sequences_with_length() {
+    assert_eq!(
+        wire::to_vec("ab").unwrap(),
+        [6, 0, 0, 0, 2, 0, 0, 0, b'a', b'b']
+    );
+    assert_eq!(
+        wire::to_vec(&vec![1u16, 2]).unwrap(),
+        [8, 0, 0, 0, 2, 0, 0, 0, 1, 0, 2, 0]
+    );
+    assert_eq!(wire::to_vec(&[1u16, 2]).unwrap(), [4, 0, 0, 0, 1, 0, 2, 0]);
+    assert_eq!(wire::to_vec(&(1u8, 2u8)).unwrap(), [2, 0, 0, 0, 1, 2]);
+}
+
+#[test]
+fn encodes_structs_as_fields_in_order() {
+    let mut data = vec![];
+    wire::to_writer(&mut data, &sample()).unwrap();
+    let mut expected = vec![];
+    expected.extend_from_slice(&7u32.to_le_bytes());
+    expected.extend_from_slice(&[1, 0, 0, 0, 2, 0, 0, 0]);
+    expected.extend_from_slice(&[3, 0, 0, 0, b'm', b'a', b'p']);
+    expected.push(1);
+    expected.extend_from_slice(&(-1i32).to_le_bytes());
+    expected.extend_from_slice(&5i32.to_le_bytes());
+    for value in &[1.0f64, -2.5, 0.0] {
+        expected.extend_from_slice(&value.to_le_bytes());
+    }
+    expected.extend_from_slice(&[3, 0xfc, 0xff]);
+    expected.extend_from_slice(&[2, 0, 0, 0, 1, 0, 0xff, 0xff]);
+    expected.extend_from_slice(&[2, 0, 0, 0, 1, 0, 0, 0, b'a', 0, 0, 0, 0]);
+    assert_eq!(data, expected);
+}
+
+#[test]
+fn round_trips_messages() {
+    let data = wire::to_vec(&sample()).unwrap();
+    assert_eq!(wire::from_slice::<Sample>(&data).unwrap(), sample());
+    let body = &data[4..];
+    assert_eq!(wire::from_reader::<_, Sample>(body).unwrap(), sample());
+}
+
+#[test]
+fn decoding_fails_on_bad_lengths() {
+    let mut data = wire::to_vec(&sample()).unwrap();
+    data.push(0);
+    match wire::from_slice::<Sample>(&data) {
+        Err(Error::LengthMismatch { expected, actual }) => assert_eq!(expected + 1, actual),
+        v => panic!("Unexpected result: {:?}", v),
+    }
+    data.truncate(data.len() - 2);
+    let len = data.len() as u32 - 4;
+    data[..4].copy_from_slice(&len.to_le_bytes());
+    assert!(matches!(
+        wire::from_slice::<Sample>(&data),
+        Err(Error::Io(_))
+    ));
+    let hostile = [255, 255, 255, 255, b'a'];
+    assert!(matches!(
+        wire::from_reader::<_, String>(&hostile[..]),
+        Err(Error::Io(_))
+    ));
+    assert!(matches!(
+        wire::from_reader::<_, String>(&[1, 0, 0, 0, 0xff][..]),
+        Err(Error::InvalidData(_))
+    ));
+}
+
+#[test]
+fn rejects_unsupported_types() {
+    assert!(matches!(
+        wire::to_vec(&Some(1u8)),
+        Err(Error::Unsupported(_))
+    ));
+    assert!(matches!(wire::to_vec(&'a'), Err(Error::Unsupported(_))));
+    assert!(matches!(
+        wire::to_vec(&HashMap::<String, String>::new()),
+        Err(Error::Unsupported(_))
+    ));
+    assert!(matches!(
+        wire::to_vec(&(0..3).filter(|v| v % 2 == 0).collect::<IterOnly>()),
+        Err(Error::UnknownLength)
+    ));
+    assert!(matches!(
+        wire::from_reader::<_, Option<u8>>(&[1, 0][..]),
+        Err(Error::Unsupported(_))
+    ));
+}
+
+struct IterOnly(Vec<i32>);
+
+impl std::iter::FromIterator<i32> for IterOnly {
+    fn from_iter<I: IntoIterator<Item = i32>>(iter: I) -> Self {
+        IterOnly(iter.into_iter().collect())
+    }
+}
+
+impl serde::Serialize for IterOnly {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter().filter(|_| true))
+    }
+}
@@ -1,8 +1,10 @@
 use crate::{Duration, Time};
 use itertools::Itertools;
-use serde_derive::{Deserialize, Serialize};
+use serde::ser::{Serialize, SerializeSeq, Serializer};
+use serde_derive::Deserialize;
+use std::borrow::Cow;
 use std::collections::HashMap;
-use std::convert::{TryFrom, TryInto};
+use std::convert::TryFrom;
 use std::fmt;
 use std::fmt::{Display, Formatter};
 use std::iter::FromIterator;
@@ -11,7 +13,7 @@ use std::iter::FromIterator;
 pub type MessageValue = HashMap<String, Value>;
 
 /// Represents an arbitrary ROS message or value in it.
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Deserialize)]
 pub enum Value {
     /// Represents `bool`.
     Bool(bool),
@@ -44,9 +46,32 @@ pub enum Value {
     /// Represents `some_type[]` or `some_type[length]`.
     ///
     /// For example: `float32[64]`, `geometry_msgs/Point[]`.
+    ///
+    /// Arrays of numbers are usually stored in the typed variants below instead,
+    /// which compare equal to and serialize like the same items stored here.
     Array(Vec<Value>),
     /// Represents an embedded message.
     Message(MessageValue),
+    /// Represents `uint8[]` or `char[]`, and their fixed length variants.
+    Bytes(Vec<u8>),
+    /// Represents `int8[]` or `byte[]`, and their fixed length variants.
+    I8Array(Vec<i8>),
+    /// Represents `int16[]` or `int16[length]`.
+    I16Array(Vec<i16>),
+    /// Represents `int32[]` or `int32[length]`.
+    I32Array(Vec<i32>),
+    /// Represents `int64[]` or `int64[length]`.
+    I64Array(Vec<i64>),
+    /// Represents `uint16[]` or `uint16[length]`.
+    U16Array(Vec<u16>),
+    /// Represents `uint32[]` or `uint32[length]`.
+    U32Array(Vec<u32>),
+    /// Represents `uint64[]` or `uint64[length]`.
+    U64Array(Vec<u64>),
+    /// Represents `float32[]` or `float32[length]`.
+    F32Array(Vec<f32>),
+    /// Represents `float64[]` or `float64[length]`.
+    F64Array(Vec<f64>),
 }
 
 impl Value {
@@ -82,9 +107,113 @@ impl Value {
                 }
                 Ok(())
             }
+            Value::Bytes(_)
+            | Value::I8Array(_)
+            | Value::I16Array(_)
+            | Value::I32Array(_)
+            | Value::I64Array(_)
+            | Value::U16Array(_)
+            | Value::U32Array(_)
+            | Value::U64Array(_)
+            | Value::F32Array(_)
+            | Value::F64Array(_) => {
+                for item in (0..self.array_len().unwrap_or(0)).filter_map(|i| self.array_item(i)) {
+                    writeln!(f)?;
+                    write!(f, "{:indent$}- ", "", indent = indentation)?;
+                    item.fmt_indented(indentation + step, step, f)?;
+                }
+                Ok(())
+            }
         }
     }
 
+    /// Stores arrays of numbers in the matching typed variant.
+    fn from_items(items: Vec<Value>) -> Self {
+        macro_rules! pack {
+            ($item:ident, $array:ident) => {
+                if items.iter().all(|v| matches!(v, Value::$item(_))) {
+                    return Value::$array(
+                        items
+                            .into_iter()
+                            .filter_map(|v| match v {
+                                Value::$item(v) => Some(v),
+                                _ => None,
+                            })
+                            .collect(),
+                    );
+                }
+            };
+        }
+        match items.first() {
+            Some(Value::I8(_)) => pack!(I8, I8Array),
+            Some(Value::I16(_)) => pack!(I16, I16Array),
+            Some(Value::I32(_)) => pack!(I32, I32Array),
+            Some(Value::I64(_)) => pack!(I64, I64Array),
+            Some(Value::U8(_)) => pack!(U8, Bytes),
+            Some(Value::U16(_)) => pack!(U16, U16Array),
+            Some(Value::U32(_)) => pack!(U32, U32Array),
+            Some(Value::U64(_)) => pack!(U64, U64Array),
+            Some(Value::F32(_)) => pack!(F32, F32Array),
+            Some(Value::F64(_)) => pack!(F64, F64Array),
+            _ => {}
+        }
+        Value::Array(items)
+    }
+
+    /// Returns the number of items if `Value` is an array of any kind.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros_message::Value;
+    /// assert_eq!(Value::Bytes(vec![1, 2, 3]).array_len(), Some(3));
+    /// assert_eq!(Value::Array(vec![true.into()]).array_len(), Some(1));
+    /// assert!(Value::U32(12).array_len().is_none());
+    /// ```
+    pub fn array_len(&self) -> Option<usize> {
+        Some(match self {
+            Value::Array(v) => v.len(),
+            Value::Bytes(v) => v.len(),
+            Value::I8Array(v) => v.len(),
+            Value::I16Array(v) => v.len(),
+            Value::I32Array(v) => v.len(),
+            Value::I64Array(v) => v.len(),
+            Value::U16Array(v) => v.len(),
+            Value::U32Array(v) => v.len(),
+            Value::U64Array(v) => v.len(),
+            Value::F32Array(v) => v.len(),
+            Value::F64Array(v) => v.len(),
+            _ => return None,
+        })
+    }
+
+    /// Returns a copy of the item at the index if `Value` is an array of any kind.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros_message::Value;
+    /// assert_eq!(Value::F32Array(vec![1.0, 2.5]).array_item(1), Some(Value::F32(2.5)));
+    /// assert!(Value::F32Array(vec![1.0, 2.5]).array_item(2).is_none());
+    /// assert!(Value::U32(12).array_item(0).is_none());
+    /// ```
+    pub fn array_item(&self, index: usize) -> Option<Value> {
+        Some(match self {
+            Value::Array(v) => v.get(index)?.clone(),
+            Value::Bytes(v) => Value::U8(*v.get(index)?),
+            Value::I8Array(v) => Value::I8(*v.get(index)?),
+            Value::I16Array(v) => Value::I16(*v.get(index)?),
+            Value::I32Array(v) => Value::I32(*v.get(index)?),
+            Value::I64Array(v) => Value::I64(*v.get(index)?),
+            Value::U16Array(v) => Value::U16(*v.get(index)?),
+            Value::U32Array(v) => Value::U32(*v.get(index)?),
+            Value::U64Array(v) => Value::U64(*v.get(index)?),
+            Value::F32Array(v) => Value::F32(*v.get(index)?),
+            Value::F64Array(v) => Value::F64(*v.get(index)?),
+            _ => return None,
+        })
+    }
+
     /// Returns the content if `Value` is a `bool`.
     ///
     /// # Examples
@@ -347,18 +476,24 @@ impl Value {
         }
     }
 
-    /// Returns a reference to the content if `Value` is an array.
+    /// Returns a reference to the content if `Value` is an array stored in `Value::Array`.
+    ///
+    /// Arrays of numbers are usually stored in typed variants like `Value::Bytes`, which
+    /// this doesn't match. Use `as_items` to handle arrays of any kind.
     ///
     /// # Examples
     ///
     /// ```
+    /// # #![allow(deprecated)]
     /// # use ros_message::Value;
     /// assert_eq!(
-    ///     Value::Array(vec![1u32.into(), 2u32.into(), 3u32.into()]).as_slice(),
-    ///     Some(&[Value::U32(1), Value::U32(2), Value::U32(3)][..]),
+    ///     Value::Array(vec![true.into(), false.into()]).as_slice(),
+    ///     Some(&[Value::Bool(true), Value::Bool(false)][..]),
     /// );
+    /// assert!(Value::Bytes(vec![1, 2]).as_slice().is_none());
     /// assert!(Value::U32(12).as_slice().is_none());
     /// ```
+    #[deprecated(note = "does not match typed arrays like `Value::Bytes`, use `as_items` instead")]
     pub fn as_slice(&self) -> Option<&[Value]> {
         if let Value::Array(value) = self {
             Some(value)
@@ -367,7 +502,88 @@ impl Value {
         }
     }
 
-    /// Returns the content if `Value` is an array.
+    /// Returns the items if `Value` is an array of any kind.
+    ///
+    /// Items of `Value::Array` are borrowed, while items of typed arrays are copied into
+    /// one value each.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros_message::Value;
+    /// # use std::borrow::Cow;
+    /// let array = Value::Array(vec![true.into(), false.into()]);
+    /// assert!(matches!(array.as_items(), Some(Cow::Borrowed(_))));
+    /// assert_eq!(
+    ///     Value::Bytes(vec![1, 2]).as_items().as_deref(),
+    ///     Some(&[Value::U8(1), Value::U8(2)][..]),
+    /// );
+    /// assert!(Value::U32(12).as_items().is_none());
+    /// ```
+    pub fn as_items(&self) -> Option<Cow<'_, [Value]>> {
+        match self {
+            Value::Array(value) => Some(Cow::Borrowed(value)),
+            _ => {
+                let len = self.array_len()?;
+                Some(Cow::Owned(
+                    (0..len).filter_map(|i| self.array_item(i)).collect(),
+                ))
+            }
+        }
+    }
+
+    /// Returns a reference to the content if `Value` is an array of `uint8` or `char`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros_message::Value;
+    /// assert_eq!(Value::Bytes(vec![1, 2]).as_bytes(), Some(&[1, 2][..]));
+    /// assert!(Value::U32(12).as_bytes().is_none());
+    /// ```
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        if let Value::Bytes(value) = self {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// Returns a reference to the content if `Value` is an array of `float32`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros_message::Value;
+    /// assert_eq!(Value::F32Array(vec![1.5]).as_f32_slice(), Some(&[1.5][..]));
+    /// assert!(Value::U32(12).as_f32_slice().is_none());
+    /// ```
+    pub fn as_f32_slice(&self) -> Option<&[f32]> {
+        if let Value::F32Array(value) = self {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// Returns a reference to the content if `Value` is an array of `float64`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros_message::Value;
+    /// assert_eq!(Value::F64Array(vec![1.5]).as_f64_slice(), Some(&[1.5][..]));
+    /// assert!(Value::U32(12).as_f64_slice().is_none());
+    /// ```
+    pub fn as_f64_slice(&self) -> Option<&[f64]> {
+        if let Value::F64Array(value) = self {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the content if `Value` is an array of any kind, with one value per item.
     ///
     /// # Examples
     ///
@@ -377,13 +593,39 @@ impl Value {
     ///     Value::Array(vec![1u32.into(), 2u32.into(), 3u32.into()]).try_into_vec(),
     ///     Some(vec![Value::U32(1), Value::U32(2), Value::U32(3)]),
     /// );
+    /// assert_eq!(
+    ///     Value::Bytes(vec![1, 2]).try_into_vec(),
+    ///     Some(vec![Value::U8(1), Value::U8(2)]),
+    /// );
     /// assert!(Value::U32(12).try_into_vec().is_none());
     /// ```
     pub fn try_into_vec(self) -> Option<Vec<Value>> {
-        if let Value::Array(value) = self {
-            Some(value)
-        } else {
-            None
+        self.try_into_items().ok()
+    }
+
+    /// Converts the items of an array of any kind, without collecting them into values first.
+    ///
+    /// Fails if `Value` is not an array or any item fails to convert.
+    fn try_into_items<T: TryFrom<Value>>(self) -> Result<Vec<T>, ()> {
+        fn items<I: Into<Value>, T: TryFrom<Value>>(items: Vec<I>) -> Result<Vec<T>, ()> {
+            items
+                .into_iter()
+                .map(|v| T::try_from(v.into()).map_err(|_| ()))
+                .collect()
+        }
+        match self {
+            Value::Array(value) => items(value),
+            Value::Bytes(value) => items(value),
+            Value::I8Array(value) => items(value),
+            Value::I16Array(value) => items(value),
+            Value::I32Array(value) => items(value),
+            Value::I64Array(value) => items(value),
+            Value::U16Array(value) => items(value),
+            Value::U32Array(value) => items(value),
+            Value::U64Array(value) => items(value),
+            Value::F32Array(value) => items(value),
+            Value::F64Array(value) => items(value),
+            _ => Err(()),
         }
     }
 
@@ -430,6 +672,99 @@ impl Value {
     }
 }
 
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::I8(a), Value::I8(b)) => a == b,
+            (Value::I16(a), Value::I16(b)) => a == b,
+            (Value::I32(a), Value::I32(b)) => a == b,
+            (Value::I64(a), Value::I64(b)) => a == b,
+            (Value::U8(a), Value::U8(b)) => a == b,
+            (Value::U16(a), Value::U16(b)) => a == b,
+            (Value::U32(a), Value::U32(b)) => a == b,
+            (Value::U64(a), Value::U64(b)) => a == b,
+            (Value::F32(a), Value::F32(b)) => a == b,
+            (Value::F64(a), Value::F64(b)) => a == b,
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Time(a), Value::Time(b)) => a == b,
+            (Value::Duration(a), Value::Duration(b)) => a == b,
+            (Value::Message(a), Value::Message(b)) => a == b,
+            (Value::Array(a), Value::Array(b)) => a == b,
+            (Value::Bytes(a), Value::Bytes(b)) => a == b,
+            (Value::I8Array(a), Value::I8Array(b)) => a == b,
+            (Value::I16Array(a), Value::I16Array(b)) => a == b,
+            (Value::I32Array(a), Value::I32Array(b)) => a == b,
+            (Value::I64Array(a), Value::I64Array(b)) => a == b,
+            (Value::U16Array(a), Value::U16Array(b)) => a == b,
+            (Value::U32Array(a), Value::U32Array(b)) => a == b,
+            (Value::U64Array(a), Value::U64Array(b)) => a == b,
+            (Value::F32Array(a), Value::F32Array(b)) => a == b,
+            (Value::F64Array(a), Value::F64Array(b)) => a == b,
+            // The same items can be stored in a typed array or in `Value::Array`
+            _ => match (self.array_len(), other.array_len()) {
+                (Some(len), Some(other_len)) => {
+                    len == other_len && (0..len).all(|i| self.array_item(i) == other.array_item(i))
+                }
+                _ => false,
+            },
+        }
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        macro_rules! variant {
+            ($index:expr, $name:ident, $value:expr) => {
+                serializer.serialize_newtype_variant("Value", $index, stringify!($name), $value)
+            };
+        }
+        match self {
+            Value::Bool(v) => variant!(0, Bool, v),
+            Value::I8(v) => variant!(1, I8, v),
+            Value::I16(v) => variant!(2, I16, v),
+            Value::I32(v) => variant!(3, I32, v),
+            Value::I64(v) => variant!(4, I64, v),
+            Value::U8(v) => variant!(5, U8, v),
+            Value::U16(v) => variant!(6, U16, v),
+            Value::U32(v) => variant!(7, U32, v),
+            Value::U64(v) => variant!(8, U64, v),
+            Value::F32(v) => variant!(9, F32, v),
+            Value::F64(v) => variant!(10, F64, v),
+            Value::String(v) => variant!(11, String, v),
+            Value::Time(v) => variant!(12, Time, v),
+            Value::Duration(v) => variant!(13, Duration, v),
+            Value::Array(v) => variant!(14, Array, v),
+            Value::Message(v) => variant!(15, Message, v),
+            // Typed arrays are written like `Value::Array`, which existing readers expect
+            Value::Bytes(_)
+            | Value::I8Array(_)
+            | Value::I16Array(_)
+            | Value::I32Array(_)
+            | Value::I64Array(_)
+            | Value::U16Array(_)
+            | Value::U32Array(_)
+            | Value::U64Array(_)
+            | Value::F32Array(_)
+            | Value::F64Array(_) => variant!(14, Array, &ArrayItems(self)),
+        }
+    }
+}
+
+/// Items of an array value, serialized one by one.
+struct ArrayItems<'a>(&'a Value);
+
+impl Serialize for ArrayItems<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let len = self.0.array_len().unwrap_or(0);
+        let mut seq = serializer.serialize_seq(Some(len))?;
+        for item in (0..len).filter_map(|i| self.0.array_item(i)) {
+            seq.serialize_element(&item)?;
+        }
+        seq.end()
+    }
+}
+
 impl Display for Value {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         self.fmt_indented(0, 2, f)
@@ -522,13 +857,13 @@ impl From<Duration> for Value {
 
 impl<T: Into<Value>> From<Vec<T>> for Value {
     fn from(v: Vec<T>) -> Self {
-        Self::Array(v.into_iter().map(Into::into).collect())
+        Self::from_items(v.into_iter().map(Into::into).collect())
     }
 }
 
 impl<T: Into<Value>, const L: usize> From<[T; L]> for Value {
     fn from(v: [T; L]) -> Self {
-        Self::Array(IntoIterator::into_iter(v).map(Into::into).collect())
+        Self::from_items(IntoIterator::into_iter(v).map(Into::into).collect())
     }
 }
 
@@ -654,12 +989,7 @@ impl<T: TryFrom<Value>> TryFrom<Value> for Vec<T> {
     type Error = ();
 
     fn try_from(value: Value) -> Result<Self, Self::Error> {
-        let value = value.try_into_vec().ok_or(())?;
-        value
-            .into_iter()
-            .map(TryInto::try_into)
-            .collect::<Result<Self, _>>()
-            .map_err(|_| ())
+        value.try_into_items()
     }
 }
 
@@ -667,11 +997,10 @@ impl<T: TryFrom<Value>, const L: usize> TryFrom<Value> for [T; L] {
     type Error = ();
 
     fn try_from(value: Value) -> Result<Self, Self::Error> {
-        let value = value.try_into_vec().ok_or(())?;
-        if value.len() != L {
+        if value.array_len() != Some(L) {
             return Err(());
         }
-        array_init::from_iter(value.into_iter().filter_map(|v| v.try_into().ok())).ok_or(())
+        array_init::from_iter(value.try_into_items::<T>()?).ok_or(())
     }
 }
 
@@ -695,6 +1024,6 @@ impl<K: Into<String>, T: Into<Value>> FromIterator<(K, T)> for Value {
 
 impl<T: Into<Value>> FromIterator<T> for Value {
     fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
-        Self::Array(iter.into_iter().map(Into::into).collect())
+        Self::from_items(iter.into_iter().map(Into::into).collect())
     }
 }
@@ -0,0 +1,303 @@
+use crate::{FieldPath, MessageValue, PathComponent, Selection, Value};
+use itertools::Itertools;
+use std::fmt::{self, Display, Formatter};
+
+/// Compares messages field by field, with tolerances for floats and fields to skip.
+///
+/// # Examples
+///
+/// ```
+/// # use ros_message::{Comparator, MessageValue, Value, ValueChange};
+/// #
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut header = MessageValue::new();
+/// header.insert("seq".into(), Value::U32(1));
+/// let mut expected = MessageValue::new();
+/// expected.insert("header".into(), Value::Message(header));
+/// expected.insert("x".into(), Value::F64(1.0));
+/// expected.insert("ranges".into(), vec![1.0f32, 2.0].into());
+///
+/// let mut actual = expected.clone();
+/// actual.insert("x".into(), Value::F64(1.0 + 1e-9));
+/// actual.insert("ranges".into(), vec![1.0f32, 2.5].into());
+/// let mut header = MessageValue::new();
+/// header.insert("seq".into(), Value::U32(2));
+/// actual.insert("header".into(), Value::Message(header));
+///
+/// let comparator = Comparator::new()
+///     .absolute_tolerance(1e-6)
+///     .ignore("header.seq".parse()?);
+/// assert_eq!(
+///     comparator.diff(&expected, &actual),
+///     vec![ValueChange::Changed {
+///         path: "ranges[1]".parse()?,
+///         expected: Value::F32(2.0),
+///         actual: Value::F32(2.5),
+///     }],
+/// );
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Comparator {
+    absolute_tolerance: f64,
+    relative_tolerance: f64,
+    ignored: Vec<FieldPath>,
+}
+
+/// A single difference between two messages.
+///
+/// Paths into arrays select the index of the item, like `poses[1].position.x`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValueChange {
+    /// Values differ, or have different types.
+    Changed {
+        /// Path to the value.
+        path: FieldPath,
+        /// Value in the expected message.
+        expected: Value,
+        /// Value in the actual message.
+        actual: Value,
+    },
+    /// Arrays have a different number of items, with the common items compared separately.
+    LengthChanged {
+        /// Path to the array.
+        path: FieldPath,
+        /// Length in the expected message.
+        expected: usize,
+        /// Length in the actual message.
+        actual: usize,
+    },
+    /// Field is only present in the expected message.
+    FieldRemoved {
+        /// Path to the field.
+        path: FieldPath,
+    },
+    /// Field is only present in the actual message.
+    FieldAdded {
+        /// Path to the field.
+        path: FieldPath,
+    },
+}
+
+impl Comparator {
+    /// Creates a comparator that requires exact equality and checks all fields.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Treats floats as equal if they differ by at most this much.
+    pub fn absolute_tolerance(mut self, tolerance: f64) -> Self {
+        self.absolute_tolerance = tolerance;
+        self
+    }
+
+    /// Treats floats as equal if they differ by at most this fraction of the larger one.
+    pub fn relative_tolerance(mut self, tolerance: f64) -> Self {
+        self.relative_tolerance = tolerance;
+        self
+    }
+
+    /// Skips values at the path, like `header.stamp` or `poses[*].orientation`.
+    pub fn ignore(mut self, path: FieldPath) -> Self {
+        self.ignored.push(path);
+        self
+    }
+
+    /// Returns all differences of the actual message from the expected one.
+    pub fn diff(&self, expected: &MessageValue, actual: &MessageValue) -> Vec<ValueChange> {
+        let mut output = vec![];
+        self.compare_messages(expected, actual, &mut vec![], &mut output);
+        output
+    }
+
+    /// Returns whether the messages have no differences.
+    pub fn matches(&self, expected: &MessageValue, actual: &MessageValue) -> bool {
+        self.diff(expected, actual).is_empty()
+    }
+
+    fn compare_messages<'a>(
+        &self,
+        expected: &'a MessageValue,
+        actual: &'a MessageValue,
+        path: &mut Vec<Segment<'a>>,
+        output: &mut Vec<ValueChange>,
+    ) {
+        for key in expected.keys().chain(actual.keys()).unique().sorted() {
+            path.push(Segment {
+                name: key,
+                index: None,
+            });
+            if !self.is_ignored(path) {
+                match (expected.get(key), actual.get(key)) {
+                    (Some(expected), Some(actual)) => {
+                        self.compare_values(expected, actual, path, output)
+                    }
+                    (Some(_), None) => output.push(ValueChange::FieldRemoved {
+                        path: field_path(path),
+                    }),
+                    (None, Some(_)) => output.push(ValueChange::FieldAdded {
+                        path: field_path(path),
+                    }),
+                    (None, None) => {}
+                }
+            }
+            path.pop();
+        }
+    }
+
+    fn compare_values<'a>(
+        &self,
+        expected: &'a Value,
+        actual: &'a Value,
+        path: &mut Vec<Segment<'a>>,
+        output: &mut Vec<ValueChange>,
+    ) {
+        match (expected, actual) {
+            (Value::Message(a), Value::Message(b)) => self.compare_messages(a, b, path, output),
+            (Value::Array(a), Value::Array(b)) => {
+                self.compare_lengths(a.len(), b.len(), path, output);
+                for (idx, (a, b)) in a.iter().zip(b).enumerate() {
+                    self.set_index(path, idx);
+                    if !self.is_ignored(path) {
+                        self.compare_values(a, b, path, output);
+                    }
+                }
+                self.set_index(path, None);
+            }
+            _ => match (expected.array_len(), actual.array_len()) {
+                (Some(expected_len), Some(actual_len)) => {
+                    self.compare_lengths(expected_len, actual_len, path, output);
+                    // Typed arrays, like images, are often identical, so items are only
+                    // visited if they differ
+                    if expected_len == actual_len
+                        && self.absolute_tolerance == 0.0
+                        && self.relative_tolerance == 0.0
+                        && expected == actual
+                    {
+                        return;
+                    }
+                    for idx in 0..expected_len.min(actual_len) {
+                        self.set_index(path, idx);
+                        if let (Some(a), Some(b)) =
+                            (expected.array_item(idx), actual.array_item(idx))
+                        {
+                            if !self.is_ignored(path) {
+                                self.compare_scalars(&a, &b, path, output);
+                            }
+                        }
+                    }
+                    self.set_index(path, None);
+                }
+                _ => self.compare_scalars(expected, actual, path, output),
+            },
+        }
+    }
+
+    fn compare_scalars(
+        &self,
+        expected: &Value,
+        actual: &Value,
+        path: &[Segment],
+        output: &mut Vec<ValueChange>,
+    ) {
+        let equal = match (expected, actual) {
+            (Value::F32(a), Value::F32(b)) => self.floats_match(f64::from(*a), f64::from(*b)),
+            (Value::F64(a), Value::F64(b)) => self.floats_match(*a, *b),
+            _ => expected == actual,
+        };
+        if !equal {
+            output.push(ValueChange::Changed {
+                path: field_path(path),
+                expected: expected.clone(),
+                actual: actual.clone(),
+            });
+        }
+    }
+
+    fn compare_lengths(
+        &self,
+        expected: usize,
+        actual: usize,
+        path: &[Segment],
+        output: &mut Vec<ValueChange>,
+    ) {
+        if expected != actual {
+            output.push(ValueChange::LengthChanged {
+                path: field_path(path),
+                expected,
+                actual,
+            });
+        }
+    }
+
+    fn set_index(&self, path: &mut [Segment], index: impl Into<Option<usize>>) {
+        if let Some(last) = path.last_mut() {
+            last.index = index.into();
+        }
+    }
+
+    fn floats_match(&self, a: f64, b: f64) -> bool {
+        if a == b || (a.is_nan() && b.is_nan()) {
+            return true;
+        }
+        let difference = (a - b).abs();
+        difference <= self.absolute_tolerance
+            || difference <= self.relative_tolerance * a.abs().max(b.abs())
+    }
+
+    fn is_ignored(&self, path: &[Segment]) -> bool {
+        self.ignored.iter().any(|ignored| {
+            let components = ignored.components();
+            components.len() <= path.len()
+                && components.iter().zip(path).all(|(component, segment)| {
+                    component.name() == segment.name
+                        && match (component.selection(), segment.index) {
+                            (None, _) => true,
+                            (Some(_), None) => false,
+                            (Some(Selection::Index(index)), Some(idx)) => index == idx,
+                            (Some(Selection::Range(start, end)), Some(idx)) => {
+                                start.unwrap_or(0) <= idx && !matches!(end, Some(end) if idx >= end)
+                            }
+                            (Some(Selection::All), Some(_)) => true,
+                        }
+                })
+        })
+    }
+}
+
+/// Field name along a path, with the array index if the field is an array.
+#[derive(Clone, Copy)]
+struct Segment<'a> {
+    name: &'a str,
+    index: Option<usize>,
+}
+
+fn field_path(path: &[Segment]) -> FieldPath {
+    path.iter()
+        .map(|segment| PathComponent::new(segment.name, segment.index.map(Selection::Index)))
+        .collect()
+}
+
+impl Display for ValueChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ValueChange::Changed {
+                path,
+                expected,
+                actual,
+            } => write!(f, "`{}` changed from {} to {}", path, expected, actual),
+            ValueChange::LengthChanged {
+                path,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "`{}` changed length from {} to {}",
+                path, expected, actual
+            ),
+            ValueChange::FieldRemoved { path } => write!(f, "`{}` is missing", path),
+            ValueChange::FieldAdded { path } => write!(f, "`{}` was added", path),
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use std::fmt::Display;
+use std::io;
+
+/// Errors of the ROS1 wire format.
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    /// Reading or writing the underlying data failed.
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    /// The data type can't be represented in the wire format.
+    #[error("{0} are not supported by the ROS1 wire format")]
+    Unsupported(&'static str),
+    /// A string, byte buffer or sequence doesn't fit a `u32` length prefix.
+    #[error("length {0} does not fit into a u32 prefix")]
+    TooLong(usize),
+    /// A sequence was serialized without knowing its length in advance.
+    #[error("sequences need a known length")]
+    UnknownLength,
+    /// The length prefix of a message doesn't match the message body.
+    #[error("message length prefix is {expected}, but the body is {actual} bytes long")]
+    LengthMismatch {
+        /// Length given by the prefix.
+        expected: usize,
+        /// Length of the body that was read.
+        actual: usize,
+    },
+    /// Decoded data doesn't fit the target type.
+    #[error("invalid data: {0}")]
+    InvalidData(String),
+    /// Custom error raised by a `Serialize` or `Deserialize` implementation.
+    #[error("{0}")]
+    Custom(String),
+}
+
+/// Convenience type for results of the wire format.
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl From<Error> for io::Error {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::Io(err) => err,
+            err => io::Error::new(io::ErrorKind::InvalidData, err),
+        }
+    }
+}
+
+impl serde::ser::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error::Custom(msg.to_string())
+    }
+}
+
+impl serde::de::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error::Custom(msg.to_string())
+    }
+}
@@ -0,0 +1,35 @@
+//! Serde data format for the binary encoding that ROS1 uses on the wire.
+//!
+//! Primitives are little endian, `bool` is a single byte, and strings, byte buffers and
+//! sequences are prefixed with their length as a `u32`. Structs and tuples are encoded as
+//! their fields in order, so fixed size arrays map to tuples and Rust arrays.
+//!
+//! The format is not self-describing, and has no representation for options, enums,
+//! maps or `char`, which all result in errors.
+//!
+//! # Examples
+//!
+//! ```
+//! use ros_message::wire;
+//! use serde_derive::{Deserialize, Serialize};
+//!
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! struct Point {
+//!     x: f32,
+//!     label: String,
+//!     values: Vec<u8>,
+//! }
+//!
+//! let point = Point { x: 1.5, label: "a".into(), values: vec![7] };
+//! let data = wire::to_vec(&point).unwrap();
+//! assert_eq!(data, [14, 0, 0, 0, 0, 0, 192, 63, 1, 0, 0, 0, 97, 1, 0, 0, 0, 7]);
+//! assert_eq!(wire::from_slice::<Point>(&data).unwrap(), point);
+//! ```
+
+mod de;
+mod error;
+mod ser;
+
+pub use de::{from_reader, from_slice, Deserializer};
+pub use error::{Error, Result};
+pub use ser::{to_vec, to_writer, Serializer};
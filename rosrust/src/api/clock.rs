@@ -3,8 +3,8 @@ use crossbeam::sync::{Parker, Unparker};
 use ros_message::{Duration, Time};
 use std::cell::Cell;
 use std::cmp;
-use std::collections::BinaryHeap;
-use std::sync::{Arc, Mutex};
+use std::collections::{BinaryHeap, HashMap};
+use std::sync::{Arc, Mutex, Weak};
 use std::thread::sleep;
 use std::time::{Duration as StdDuration, SystemTime, UNIX_EPOCH};
 
@@ -41,10 +41,131 @@ impl Rate {
         }
     }
 
-    pub fn sleep(&self) {
+    /// Sleeps until the end of the current cycle.
+    ///
+    /// If the clock jumps backwards, either while sleeping or since the previous cycle,
+    /// the rate is reset to start counting from the new current time.
+    pub fn sleep(&self) -> SleepResult {
+        if self.clock.now() < self.next.get() {
+            self.reset();
+        }
         let new_time = self.next.get() + self.delay;
         self.next.set(new_time);
-        self.clock.wait_until(new_time);
+        let result = self.clock.wait_until_or_jump(new_time);
+        if let SleepResult::Jumped(_) = result {
+            self.reset();
+        }
+        result
+    }
+
+    /// Starts counting the next cycle from the current time.
+    pub fn reset(&self) {
+        self.next.set(self.clock.now());
+    }
+}
+
+/// Change of the clock's time that did not happen through regular progression.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClockJump {
+    pub previous: Time,
+    pub current: Time,
+}
+
+impl ClockJump {
+    #[inline]
+    pub fn delta(&self) -> Duration {
+        self.current - self.previous
+    }
+
+    #[inline]
+    pub fn is_backward(&self) -> bool {
+        self.current < self.previous
+    }
+}
+
+/// Outcome of waiting on a clock.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SleepResult {
+    Elapsed,
+    Jumped(ClockJump),
+}
+
+/// Minimal jump sizes that trigger a jump callback.
+///
+/// A `None` threshold disables callbacks for jumps in that direction.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct JumpThreshold {
+    pub min_forward: Option<Duration>,
+    pub min_backward: Option<Duration>,
+}
+
+impl JumpThreshold {
+    fn matches(&self, jump: &ClockJump) -> bool {
+        let delta = jump.delta();
+        if jump.is_backward() {
+            matches!(self.min_backward, Some(min) if -delta >= min)
+        } else {
+            matches!(self.min_forward, Some(min) if delta >= min)
+        }
+    }
+}
+
+pub type JumpCallback = Box<dyn Fn(&ClockJump) + Send + Sync>;
+type SharedJumpCallback = Arc<dyn Fn(&ClockJump) + Send + Sync>;
+
+#[derive(Default)]
+struct JumpHandlers {
+    next_id: usize,
+    handlers: HashMap<usize, (JumpThreshold, SharedJumpCallback)>,
+}
+
+impl JumpHandlers {
+    fn add(
+        handlers: &Arc<Mutex<Self>>,
+        threshold: JumpThreshold,
+        callback: JumpCallback,
+    ) -> JumpHandle {
+        let mut data = handlers.lock().expect(FAILED_TO_LOCK);
+        let id = data.next_id;
+        data.next_id += 1;
+        data.handlers.insert(id, (threshold, callback.into()));
+        JumpHandle {
+            handlers: Arc::downgrade(handlers),
+            id,
+        }
+    }
+
+    fn notify(handlers: &Mutex<Self>, jump: &ClockJump) {
+        let callbacks = handlers
+            .lock()
+            .expect(FAILED_TO_LOCK)
+            .handlers
+            .values()
+            .filter(|(threshold, _)| threshold.matches(jump))
+            .map(|(_, callback)| Arc::clone(callback))
+            .collect::<Vec<_>>();
+        for callback in callbacks {
+            callback(jump);
+        }
+    }
+}
+
+/// Keeps a jump callback registered until dropped.
+#[derive(Default)]
+pub struct JumpHandle {
+    handlers: Weak<Mutex<JumpHandlers>>,
+    id: usize,
+}
+
+impl Drop for JumpHandle {
+    fn drop(&mut self) {
+        if let Some(handlers) = self.handlers.upgrade() {
+            handlers
+                .lock()
+                .expect(FAILED_TO_LOCK)
+                .handlers
+                .remove(&self.id);
+        }
     }
 }
 
@@ -53,6 +174,19 @@ pub trait Clock: Send + Sync {
     fn sleep(&self, d: Duration);
     fn wait_until(&self, t: Time);
     fn await_init(&self) {}
+
+    /// Waits like `wait_until`, but reports if the wait was cut short by a time jump.
+    fn wait_until_or_jump(&self, t: Time) -> SleepResult {
+        self.wait_until(t);
+        SleepResult::Elapsed
+    }
+
+    /// Registers a callback for time jumps exceeding the given threshold.
+    ///
+    /// Clocks that cannot jump never call the callback.
+    fn on_jump(&self, _threshold: JumpThreshold, _callback: JumpCallback) -> JumpHandle {
+        JumpHandle::default()
+    }
 }
 
 #[derive(Clone, Default)]
@@ -87,6 +221,7 @@ impl Clock for RealClock {
 struct Timeout {
     timestamp: Time,
     unparker: Unparker,
+    jump: Arc<Mutex<Option<ClockJump>>>,
 }
 
 impl Drop for Timeout {
@@ -126,23 +261,54 @@ pub struct SimData {
 #[derive(Default)]
 pub struct SimulatedClock {
     pub data: Mutex<SimData>,
+    jump_handlers: Arc<Mutex<JumpHandlers>>,
 }
 
 impl SimulatedClock {
     pub fn trigger(&self, time: Time) {
-        let mut data = self.data.lock().expect(FAILED_TO_LOCK);
-        data.current = time;
-        loop {
-            match data.timeouts.peek() {
-                None => break,
-                Some(next) if next.timestamp > data.current => break,
-                _ => {}
+        let jump = {
+            let mut data = self.data.lock().expect(FAILED_TO_LOCK);
+            let previous = data.current;
+            data.current = time;
+            let jump = ClockJump {
+                previous,
+                current: time,
+            };
+            if jump.is_backward() {
+                // Pending waits would otherwise block until the clock catches up again
+                for timeout in data.timeouts.drain() {
+                    *timeout.jump.lock().expect(FAILED_TO_LOCK) = Some(jump);
+                }
+            } else {
+                loop {
+                    match data.timeouts.peek() {
+                        None => break,
+                        Some(next) if next.timestamp > data.current => break,
+                        _ => {}
+                    }
+                    data.timeouts.pop();
+                }
+            }
+            // The first received time only initializes the clock
+            if previous == Time::default() || previous == time {
+                None
+            } else {
+                Some(jump)
             }
-            data.timeouts.pop();
+        };
+        if let Some(jump) = jump {
+            JumpHandlers::notify(&self.jump_handlers, &jump);
         }
     }
 }
 
+impl SimulatedClock {
+    #[inline]
+    pub(crate) fn sleeper_count(&self) -> usize {
+        self.data.lock().expect(FAILED_TO_LOCK).timeouts.len()
+    }
+}
+
 impl Clock for SimulatedClock {
     #[inline]
     fn now(&self) -> Time {
@@ -160,19 +326,34 @@ impl Clock for SimulatedClock {
 
     #[inline]
     fn wait_until(&self, timestamp: Time) {
+        self.wait_until_or_jump(timestamp);
+    }
+
+    fn wait_until_or_jump(&self, timestamp: Time) -> SleepResult {
         let parker = Parker::new();
         let unparker = parker.unparker().clone();
+        let jump = Arc::new(Mutex::new(None));
         {
-            self.data
-                .lock()
-                .expect(FAILED_TO_LOCK)
-                .timeouts
-                .push(Timeout {
-                    timestamp,
-                    unparker,
-                });
+            let mut data = self.data.lock().expect(FAILED_TO_LOCK);
+            if timestamp <= data.current {
+                return SleepResult::Elapsed;
+            }
+            data.timeouts.push(Timeout {
+                timestamp,
+                unparker,
+                jump: Arc::clone(&jump),
+            });
+        }
+        parker.park();
+        let jump = *jump.lock().expect(FAILED_TO_LOCK);
+        match jump {
+            Some(jump) => SleepResult::Jumped(jump),
+            None => SleepResult::Elapsed,
         }
-        parker.park()
+    }
+
+    fn on_jump(&self, threshold: JumpThreshold, callback: JumpCallback) -> JumpHandle {
+        JumpHandlers::add(&self.jump_handlers, threshold, callback)
     }
 
     fn await_init(&self) {
@@ -181,3 +362,231 @@ impl Clock for SimulatedClock {
         }
     }
 }
+
+/// Clock that only moves when told to, meant for deterministic tests.
+///
+/// Sleepers, including `Rate`s, are woken as soon as an advance passes their deadline.
+#[derive(Default)]
+pub struct ManualClock {
+    inner: SimulatedClock,
+}
+
+impl ManualClock {
+    pub fn new(start: Time) -> Self {
+        let clock = Self::default();
+        clock.set_now(start);
+        clock
+    }
+
+    #[inline]
+    pub fn set_now(&self, time: Time) {
+        self.inner.trigger(time);
+    }
+
+    pub fn advance(&self, d: Duration) {
+        let now = self.now();
+        self.set_now(now + d);
+    }
+
+    /// Number of threads currently waiting on this clock.
+    #[inline]
+    pub fn sleeper_count(&self) -> usize {
+        self.inner.sleeper_count()
+    }
+
+    /// Blocks until at least `count` threads are waiting on this clock.
+    pub fn wait_for_sleepers(&self, count: usize) {
+        while self.sleeper_count() < count {
+            std::thread::yield_now();
+        }
+    }
+}
+
+impl Clock for ManualClock {
+    #[inline]
+    fn now(&self) -> Time {
+        self.inner.now()
+    }
+
+    #[inline]
+    fn sleep(&self, d: Duration) {
+        self.inner.sleep(d)
+    }
+
+    #[inline]
+    fn wait_until(&self, t: Time) {
+        self.inner.wait_until(t)
+    }
+
+    #[inline]
+    fn wait_until_or_jump(&self, t: Time) -> SleepResult {
+        self.inner.wait_until_or_jump(t)
+    }
+
+    #[inline]
+    fn on_jump(&self, threshold: JumpThreshold, callback: JumpCallback) -> JumpHandle {
+        self.inner.on_jump(threshold, callback)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+
+    fn wait_for_sleepers(clock: &SimulatedClock, count: usize) {
+        while clock.sleeper_count() < count {
+            thread::yield_now();
+        }
+    }
+
+    #[test]
+    fn forward_time_wakes_elapsed_sleepers() {
+        let clock = Arc::new(SimulatedClock::default());
+        clock.trigger(Time::from_seconds(10));
+        let sleeper = {
+            let clock = Arc::clone(&clock);
+            thread::spawn(move || clock.wait_until_or_jump(Time::from_seconds(12)))
+        };
+        wait_for_sleepers(&clock, 1);
+        clock.trigger(Time::from_seconds(11));
+        assert_eq!(1, clock.data.lock().unwrap().timeouts.len());
+        clock.trigger(Time::from_seconds(12));
+        assert_eq!(SleepResult::Elapsed, sleeper.join().unwrap());
+    }
+
+    #[test]
+    fn backward_jump_wakes_all_sleepers() {
+        let clock = Arc::new(SimulatedClock::default());
+        clock.trigger(Time::from_seconds(100));
+        let sleepers = (0..3)
+            .map(|i| {
+                let clock = Arc::clone(&clock);
+                thread::spawn(move || clock.wait_until_or_jump(Time::from_seconds(101 + i)))
+            })
+            .collect::<Vec<_>>();
+        wait_for_sleepers(&clock, 3);
+        clock.trigger(Time::from_seconds(5));
+        let jump = ClockJump {
+            previous: Time::from_seconds(100),
+            current: Time::from_seconds(5),
+        };
+        for sleeper in sleepers {
+            assert_eq!(SleepResult::Jumped(jump), sleeper.join().unwrap());
+        }
+    }
+
+    #[test]
+    fn jump_callbacks_respect_thresholds() {
+        let clock = SimulatedClock::default();
+        let forward = Arc::new(AtomicUsize::new(0));
+        let backward = Arc::new(AtomicUsize::new(0));
+        let _forward_handle = clock.on_jump(
+            JumpThreshold {
+                min_forward: Some(Duration::from_seconds(5)),
+                min_backward: None,
+            },
+            Box::new({
+                let forward = Arc::clone(&forward);
+                move |_| {
+                    forward.fetch_add(1, Ordering::SeqCst);
+                }
+            }),
+        );
+        let backward_handle = clock.on_jump(
+            JumpThreshold {
+                min_forward: None,
+                min_backward: Some(Duration::from_nanos(1)),
+            },
+            Box::new({
+                let backward = Arc::clone(&backward);
+                move |jump| {
+                    assert!(jump.is_backward());
+                    backward.fetch_add(1, Ordering::SeqCst);
+                }
+            }),
+        );
+
+        clock.trigger(Time::from_seconds(100));
+        clock.trigger(Time::from_seconds(101));
+        clock.trigger(Time::from_seconds(110));
+        clock.trigger(Time::from_seconds(3));
+        assert_eq!(1, forward.load(Ordering::SeqCst));
+        assert_eq!(1, backward.load(Ordering::SeqCst));
+
+        drop(backward_handle);
+        clock.trigger(Time::from_seconds(1));
+        assert_eq!(1, backward.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn rate_resets_after_backward_jump() {
+        let clock = Arc::new(SimulatedClock::default());
+        clock.trigger(Time::from_seconds(100));
+        let rate = Rate::new(
+            Arc::clone(&clock) as Arc<dyn Clock>,
+            Duration::from_seconds(1),
+        );
+        let sleeper = {
+            let clock = Arc::clone(&clock);
+            thread::spawn(move || {
+                wait_for_sleepers(&clock, 1);
+                clock.trigger(Time::from_seconds(10));
+                wait_for_sleepers(&clock, 1);
+                clock.trigger(Time::from_seconds(11));
+            })
+        };
+        assert!(matches!(rate.sleep(), SleepResult::Jumped(_)));
+        assert_eq!(SleepResult::Elapsed, rate.sleep());
+        sleeper.join().unwrap();
+    }
+
+    #[test]
+    fn manual_clock_starts_at_given_time() {
+        let clock = ManualClock::new(Time::from_seconds(42));
+        assert_eq!(Time::from_seconds(42), clock.now());
+        clock.advance(Duration::from_nanos(1_500_000_000));
+        assert_eq!(Time::from_nanos(43_500_000_000), clock.now());
+    }
+
+    #[test]
+    fn manual_clock_does_not_block_on_past_deadlines() {
+        let clock = ManualClock::new(Time::from_seconds(10));
+        assert_eq!(
+            SleepResult::Elapsed,
+            clock.wait_until_or_jump(Time::from_seconds(3))
+        );
+        clock.sleep(Duration::from_seconds(0));
+        assert_eq!(0, clock.sleeper_count());
+    }
+
+    #[test]
+    fn manual_clock_drives_rate() {
+        let clock = Arc::new(ManualClock::new(Time::from_seconds(1)));
+        let rate = Rate::new(
+            Arc::clone(&clock) as Arc<dyn Clock>,
+            Duration::from_seconds(2),
+        );
+        let ticks = Arc::new(AtomicUsize::new(0));
+        let sleeper = {
+            let ticks = Arc::clone(&ticks);
+            thread::spawn(move || {
+                for _ in 0..3 {
+                    rate.sleep();
+                    ticks.fetch_add(1, Ordering::SeqCst);
+                }
+            })
+        };
+        clock.wait_for_sleepers(1);
+        clock.advance(Duration::from_seconds(1));
+        assert_eq!(0, ticks.load(Ordering::SeqCst));
+        assert_eq!(1, clock.sleeper_count());
+        clock.advance(Duration::from_seconds(1));
+        clock.wait_for_sleepers(1);
+        assert_eq!(1, ticks.load(Ordering::SeqCst));
+        clock.advance(Duration::from_seconds(10));
+        sleeper.join().unwrap();
+        assert_eq!(3, ticks.load(Ordering::SeqCst));
+    }
+}
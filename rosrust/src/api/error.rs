@@ -9,6 +9,7 @@ error_chain::error_chain! {
         FromUTF8(::std::string::FromUtf8Error);
         Response(ResponseError);
         SigintOverride(::ctrlc::Error);
+        SetLogger(::log::SetLoggerError);
     }
     links {
         XmlRpc(rosxmlrpc::Error, rosxmlrpc::ErrorKind);
@@ -41,6 +42,10 @@ error_chain::error_chain! {
             description("Failure in communication with ROS API")
             display("Failure in communication with ROS API: {}", details)
         }
+        BadLoggerConfig(details: String) {
+            description("Bad logger configuration")
+            display("Bad logger configuration: {}", details)
+        }
     }
 }
 
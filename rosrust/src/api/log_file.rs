@@ -0,0 +1,237 @@
+use crate::msg::rosgraph_msgs::Log;
+use ros_message::Time;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+static DEFAULT_FORMAT: &str = "[${severity}] [${time}]: ${message}";
+
+/// Settings of the node's log file.
+#[derive(Clone, Debug)]
+pub struct LogFileOptions {
+    /// Size in bytes after which the file gets rotated.
+    pub max_size: u64,
+    /// Number of rotated files that are kept, as `<file>.1` up to `<file>.<max_backups>`.
+    pub max_backups: usize,
+    /// Format in the style of `ROSCONSOLE_FORMAT`, which is used when not set.
+    pub format: Option<String>,
+}
+
+impl Default for LogFileOptions {
+    fn default() -> Self {
+        Self {
+            max_size: 50 * 1024 * 1024,
+            max_backups: 4,
+            format: None,
+        }
+    }
+}
+
+pub struct LogRecord<'a> {
+    pub level: i8,
+    pub logger: &'a str,
+    pub node: &'a str,
+    pub msg: &'a str,
+    pub file: &'a str,
+    pub line: u32,
+    pub time: Time,
+    pub walltime: Time,
+}
+
+pub struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size: u64,
+    max_backups: usize,
+    format: String,
+}
+
+impl LogFile {
+    pub fn open(path: PathBuf, options: &LogFileOptions) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = open_for_append(&path)?;
+        let size = file.metadata()?.len();
+        let format = options
+            .format
+            .clone()
+            .or_else(|| std::env::var("ROSCONSOLE_FORMAT").ok())
+            .unwrap_or_else(|| DEFAULT_FORMAT.into());
+        Ok(Self {
+            path,
+            file,
+            size,
+            max_size: options.max_size,
+            max_backups: options.max_backups,
+            format,
+        })
+    }
+
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn write(&mut self, record: &LogRecord) -> io::Result<()> {
+        let mut line = format_record(&self.format, record);
+        line.push('\n');
+        if self.size > 0 && self.size + line.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+        self.file.write_all(line.as_bytes())?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        let backup = |idx: usize| {
+            let mut name = self.path.clone().into_os_string();
+            name.push(format!(".{}", idx));
+            PathBuf::from(name)
+        };
+        if self.max_backups == 0 {
+            self.file = File::create(&self.path)?;
+        } else {
+            for idx in (1..self.max_backups).rev() {
+                let source = backup(idx);
+                if source.exists() {
+                    fs::rename(source, backup(idx + 1))?;
+                }
+            }
+            fs::rename(&self.path, backup(1))?;
+            self.file = open_for_append(&self.path)?;
+        }
+        self.size = 0;
+        Ok(())
+    }
+}
+
+fn open_for_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// Name of the node's log file, with the namespace separators flattened.
+pub fn file_name(node: &str) -> String {
+    format!("{}.log", node.trim_start_matches('/').replace('/', "_"))
+}
+
+fn severity(level: i8) -> &'static str {
+    match level {
+        Log::DEBUG => "DEBUG",
+        Log::INFO => "INFO",
+        Log::WARN => "WARN",
+        Log::ERROR => "ERROR",
+        Log::FATAL => "FATAL",
+        _ => "UNKNOWN",
+    }
+}
+
+fn format_time(time: Time) -> String {
+    format!("{}.{:09}", time.sec, time.nsec)
+}
+
+/// Replaces `${token}` entries of the format, leaving unknown tokens untouched.
+pub fn format_record(format: &str, record: &LogRecord) -> String {
+    let mut output = String::with_capacity(format.len() + record.msg.len());
+    let mut rest = format;
+    while let Some(start) = rest.find("${") {
+        output.push_str(&rest[..start]);
+        let token = &rest[start..];
+        let end = match token.find('}') {
+            Some(end) => end,
+            None => {
+                rest = token;
+                break;
+            }
+        };
+        match &token[2..end] {
+            "severity" => output.push_str(severity(record.level)),
+            "message" => output.push_str(record.msg),
+            "time" => output.push_str(&format_time(record.time)),
+            "walltime" => output.push_str(&format_time(record.walltime)),
+            "node" => output.push_str(record.node),
+            "logger" => output.push_str(record.logger),
+            "file" => output.push_str(record.file),
+            "line" => output.push_str(&record.line.to_string()),
+            "function" => {}
+            "thread" => output.push_str(&format!("{:?}", std::thread::current().id())),
+            _ => output.push_str(&token[..=end]),
+        }
+        rest = &token[end + 1..];
+    }
+    output.push_str(rest);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(msg: &str) -> LogRecord<'_> {
+        LogRecord {
+            level: Log::WARN,
+            logger: "ros.planner",
+            node: "/robot/planner",
+            msg,
+            file: "src/main.rs",
+            line: 42,
+            time: Time { sec: 12, nsec: 34 },
+            walltime: Time::from_seconds(1_600_000_000),
+        }
+    }
+
+    #[test]
+    fn formats_with_default_format() {
+        assert_eq!(
+            "[WARN] [12.000000034]: Hello",
+            format_record(DEFAULT_FORMAT, &record("Hello"))
+        );
+    }
+
+    #[test]
+    fn formats_all_tokens() {
+        assert_eq!(
+            "/robot/planner ros.planner src/main.rs:42 1600000000.000000000 ${unknown} ${",
+            format_record(
+                "${node} ${logger} ${file}:${line} ${walltime} ${unknown} ${",
+                &record("Hello")
+            )
+        );
+    }
+
+    #[test]
+    fn flattens_node_names() {
+        assert_eq!("robot_planner.log", file_name("/robot/planner"));
+    }
+
+    #[test]
+    fn rotates_files_by_size() {
+        let dir = tempfile::tempdir().unwrap();
+        // Parent folders get created when opening
+        let path = dir.path().join("nested").join("node.log");
+        let options = LogFileOptions {
+            max_size: 25,
+            max_backups: 2,
+            format: Some("${message}".into()),
+        };
+        let mut log_file = LogFile::open(path.clone(), &options).unwrap();
+        for msg in &[
+            "first line of text",
+            "second line of text",
+            "third",
+            "fourth line of text",
+        ] {
+            log_file.write(&record(msg)).unwrap();
+        }
+        let read = |suffix: &str| {
+            let mut name = path.clone().into_os_string();
+            name.push(suffix);
+            fs::read_to_string(name).unwrap()
+        };
+        assert_eq!("fourth line of text\n", read(""));
+        assert_eq!("third\n", read(".1"));
+        assert_eq!("second line of text\n", read(".2"));
+    }
+}
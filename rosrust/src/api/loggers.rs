@@ -0,0 +1,220 @@
+use crate::error::{ErrorKind, Result};
+use crate::msg::rosgraph_msgs::Log;
+use crate::util::FAILED_TO_LOCK;
+use error_chain::bail;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Mutex;
+
+pub static ROOT_LOGGER: &str = "ros";
+
+/// Levels of rosconsole-style named loggers, organized in a dot separated hierarchy.
+///
+/// Loggers without an explicitly set level inherit it from their closest ancestor.
+/// Everything is enabled unless configured otherwise.
+#[derive(Default)]
+pub struct Loggers {
+    data: Mutex<LoggersData>,
+}
+
+#[derive(Default)]
+struct LoggersData {
+    known: BTreeSet<String>,
+    levels: HashMap<String, i8>,
+}
+
+impl LoggersData {
+    fn effective_level(&self, name: &str) -> i8 {
+        let mut name = name;
+        loop {
+            if let Some(level) = self.levels.get(name) {
+                return *level;
+            }
+            match name.rfind('.') {
+                Some(idx) => name = &name[..idx],
+                None => return Log::DEBUG,
+            }
+        }
+    }
+}
+
+impl Loggers {
+    /// Prefixes the name with the root logger's name, unless it is already there.
+    pub fn full_name(name: &str) -> String {
+        if name.is_empty() || name == ROOT_LOGGER {
+            ROOT_LOGGER.into()
+        } else if name.starts_with(ROOT_LOGGER) && name[ROOT_LOGGER.len()..].starts_with('.') {
+            name.into()
+        } else {
+            format!("{}.{}", ROOT_LOGGER, name)
+        }
+    }
+
+    pub fn is_enabled(&self, name: &str, level: i8) -> bool {
+        level >= self.level(name)
+    }
+
+    pub fn level(&self, name: &str) -> i8 {
+        let name = Self::full_name(name);
+        let mut data = self.data.lock().expect(FAILED_TO_LOCK);
+        let level = data.effective_level(&name);
+        data.known.insert(name);
+        level
+    }
+
+    pub fn set_level(&self, name: &str, level: i8) {
+        let name = Self::full_name(name);
+        let mut data = self.data.lock().expect(FAILED_TO_LOCK);
+        data.known.insert(name.clone());
+        data.levels.insert(name, level);
+    }
+
+    /// All loggers that were used or configured, with their effective levels.
+    pub fn list(&self) -> Vec<(String, i8)> {
+        let data = self.data.lock().expect(FAILED_TO_LOCK);
+        data.known
+            .iter()
+            .map(|name| (name.clone(), data.effective_level(name)))
+            .collect()
+    }
+
+    /// Applies `log4j.logger.<name>=<LEVEL>` lines of a rosconsole config file.
+    ///
+    /// All valid lines are applied, and all invalid ones are reported in the error.
+    pub fn load_config(&self, config: &str) -> Result<()> {
+        let mut errors = vec![];
+        for (idx, line) in config.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = match line.split_once('=') {
+                Some(v) => v,
+                None => {
+                    errors.push(format!("line {}: expected key=value", idx + 1));
+                    continue;
+                }
+            };
+            let name = match key.trim().strip_prefix("log4j.logger.") {
+                Some(name) => name,
+                None => continue,
+            };
+            let level_name = value.split(',').next().unwrap_or_default().trim();
+            match parse_level(level_name) {
+                Some(level) => self.set_level(name, level),
+                None => errors.push(format!("line {}: unknown level '{}'", idx + 1, level_name)),
+            }
+        }
+        if !errors.is_empty() {
+            bail!(ErrorKind::BadLoggerConfig(errors.join(", ")));
+        }
+        Ok(())
+    }
+
+    pub fn load_config_file(&self, path: &str) -> Result<()> {
+        let config = std::fs::read_to_string(path)?;
+        self.load_config(&config)
+    }
+}
+
+pub fn level_name(level: i8) -> &'static str {
+    match level {
+        Log::DEBUG => "debug",
+        Log::INFO => "info",
+        Log::WARN => "warn",
+        Log::ERROR => "error",
+        Log::FATAL => "fatal",
+        _ => "unknown",
+    }
+}
+
+pub fn parse_level(name: &str) -> Option<i8> {
+    Some(match name.to_lowercase().as_str() {
+        "debug" => Log::DEBUG,
+        "info" => Log::INFO,
+        "warn" | "warning" => Log::WARN,
+        "error" => Log::ERROR,
+        "fatal" => Log::FATAL,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn everything_is_enabled_by_default() {
+        let loggers = Loggers::default();
+        assert!(loggers.is_enabled("", Log::DEBUG));
+        assert!(loggers.is_enabled("planner", Log::DEBUG));
+    }
+
+    #[test]
+    fn names_get_root_prefix() {
+        assert_eq!("ros", Loggers::full_name(""));
+        assert_eq!("ros", Loggers::full_name("ros"));
+        assert_eq!("ros.planner", Loggers::full_name("planner"));
+        assert_eq!("ros.planner", Loggers::full_name("ros.planner"));
+        assert_eq!("ros.rosie", Loggers::full_name("rosie"));
+    }
+
+    #[test]
+    fn levels_are_inherited() {
+        let loggers = Loggers::default();
+        loggers.set_level("", Log::INFO);
+        loggers.set_level("planner", Log::DEBUG);
+        loggers.set_level("planner.astar", Log::ERROR);
+        assert!(!loggers.is_enabled("", Log::DEBUG));
+        assert!(!loggers.is_enabled("controller", Log::DEBUG));
+        assert!(loggers.is_enabled("controller", Log::INFO));
+        assert!(loggers.is_enabled("planner", Log::DEBUG));
+        assert!(loggers.is_enabled("planner.graph", Log::DEBUG));
+        assert!(!loggers.is_enabled("planner.astar", Log::WARN));
+        assert_eq!(
+            vec![
+                ("ros".to_owned(), Log::INFO),
+                ("ros.controller".to_owned(), Log::INFO),
+                ("ros.planner".to_owned(), Log::DEBUG),
+                ("ros.planner.astar".to_owned(), Log::ERROR),
+                ("ros.planner.graph".to_owned(), Log::DEBUG),
+            ],
+            loggers.list()
+        );
+    }
+
+    #[test]
+    fn parses_rosconsole_config() {
+        let loggers = Loggers::default();
+        loggers
+            .load_config(
+                "# Comment\n\
+                 log4j.logger.ros=WARN\n\
+                 log4j.logger.ros.planner=DEBUG, stdout\n\
+                 log4j.appender.stdout=org.apache.log4j.ConsoleAppender\n",
+            )
+            .unwrap();
+        assert_eq!(Log::WARN, loggers.level("controller"));
+        assert_eq!(Log::DEBUG, loggers.level("planner"));
+    }
+
+    #[test]
+    fn reports_all_bad_config_lines() {
+        let loggers = Loggers::default();
+        let err = loggers
+            .load_config("log4j.logger.ros=LOUD\nlog4j.logger.ros.a=INFO\nnonsense\n")
+            .unwrap_err();
+        assert_eq!(
+            "Bad logger configuration: line 1: unknown level 'LOUD', line 3: expected key=value",
+            err.to_string()
+        );
+        assert_eq!(Log::INFO, loggers.level("a"));
+    }
+
+    #[test]
+    fn parses_level_names() {
+        assert_eq!(Some(Log::WARN), parse_level("WARN"));
+        assert_eq!(Some(Log::FATAL), parse_level("Fatal"));
+        assert_eq!(None, parse_level("verbose"));
+        assert_eq!("error", level_name(Log::ERROR));
+    }
+}
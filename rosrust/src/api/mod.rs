@@ -1,4 +1,9 @@
-pub use self::clock::{Clock, Delay, Rate};
+pub use self::clock::{
+    Clock, ClockJump, Delay, JumpCallback, JumpHandle, JumpThreshold, ManualClock, Rate,
+    SleepResult,
+};
+pub use self::log_file::LogFileOptions;
+pub use self::loggers::ROOT_LOGGER;
 pub use self::master::{Master, SystemState, Topic};
 pub use self::ros::{Parameter, Ros};
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -6,6 +11,8 @@ use std::sync::atomic::{AtomicBool, Ordering};
 mod clock;
 pub mod error;
 pub mod handlers;
+mod log_file;
+mod loggers;
 mod master;
 mod naming;
 pub mod raii;
@@ -1,3 +1,4 @@
+use std::path::{Path, PathBuf};
 use std::{self, env};
 
 pub fn master() -> String {
@@ -30,6 +31,27 @@ pub fn namespace() -> String {
     env::var("ROS_NAMESPACE").unwrap_or_default()
 }
 
+/// Explicit log file path, as passed in by roslaunch.
+pub fn log_file() -> Option<String> {
+    find_with_prefix("__log:=")
+}
+
+pub fn log_dir() -> Option<PathBuf> {
+    if let Ok(v) = env::var("ROS_LOG_DIR") {
+        return Some(PathBuf::from(v));
+    }
+    if let Ok(v) = env::var("ROS_HOME") {
+        return Some(Path::new(&v).join("log"));
+    }
+    env::var("HOME")
+        .ok()
+        .map(|v| Path::new(&v).join(".ros").join("log"))
+}
+
+pub fn rosconsole_config_file() -> Option<String> {
+    env::var("ROSCONSOLE_CONFIG_FILE").ok()
+}
+
 pub fn name(default: &str) -> String {
     find_with_prefix("__name:=").unwrap_or_else(|| String::from(default))
 }
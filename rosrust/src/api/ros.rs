@@ -1,6 +1,8 @@
 use super::super::rosxmlrpc::Response;
-use super::clock::{Clock, Rate, RealClock, SimulatedClock};
+use super::clock::{Clock, ClockJump, JumpHandle, JumpThreshold, Rate, RealClock, SimulatedClock};
 use super::error::{Error, ErrorKind, Result, ResultExt};
+use super::log_file::{self, LogFile, LogFileOptions, LogRecord};
+use super::loggers::{level_name, parse_level, Loggers};
 use super::master::{self, Master, Topic};
 use super::naming::{self, Resolver};
 use super::raii::{Publisher, Service, Subscriber};
@@ -10,8 +12,10 @@ use crate::api::clock::Delay;
 use crate::api::handlers::CallbackSubscriptionHandler;
 use crate::api::slave::ParamCache;
 use crate::api::ShutdownManager;
+use crate::msg::roscpp::{GetLoggers, GetLoggersRes, Logger, SetLoggerLevel, SetLoggerLevelRes};
 use crate::msg::rosgraph_msgs::{Clock as ClockMsg, Log};
 use crate::msg::std_msgs::Header;
+use crate::rosmsg::DecodeLimits;
 use crate::rosxmlrpc::client::bad_response_structure;
 use crate::tcpros::{Client, Message, ServicePair, ServiceResult};
 use crate::util::FAILED_TO_LOCK;
@@ -22,7 +26,8 @@ use log::error;
 use ros_message::{Duration, Time};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
-use std::ops::Deref;
+use std::io;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::thread::sleep;
@@ -40,12 +45,31 @@ pub struct Ros {
     name: String,
     clock: Arc<dyn Clock>,
     static_subs: Vec<Subscriber>,
+    static_services: Vec<Service>,
+    loggers: Arc<Loggers>,
+    log_file: Mutex<Option<LogFile>>,
     logger: Arc<Mutex<Option<Publisher<Log>>>>,
     shutdown_manager: Arc<ShutdownManager>,
 }
 
 impl Ros {
+    #[inline]
     pub fn new(name: &str) -> Result<Ros> {
+        Self::new_with_clock(name, None)
+    }
+
+    /// Creates a node that uses the provided clock instead of picking one based on `/use_sim_time`.
+    #[inline]
+    pub fn new_with_clock(name: &str, clock: Option<Arc<dyn Clock>>) -> Result<Ros> {
+        Self::new_with_decode_limits(name, clock, DecodeLimits::default())
+    }
+
+    /// Creates a node whose own topics and services, like `/rosout`, already use the limits.
+    pub fn new_with_decode_limits(
+        name: &str,
+        clock: Option<Arc<dyn Clock>>,
+        decode_limits: DecodeLimits,
+    ) -> Result<Ros> {
         let mut namespace = resolve::namespace();
         if !namespace.starts_with('/') {
             namespace = format!("/{}", namespace);
@@ -54,6 +78,7 @@ impl Ros {
         let hostname = resolve::hostname();
         let name = resolve::name(name);
         let mut ros = Ros::new_raw(&master_uri, &hostname, &namespace, &name)?;
+        ros.set_decode_limits(decode_limits);
         for (src, dest) in resolve::mappings() {
             ros.map(&src, &dest)?;
         }
@@ -67,7 +92,9 @@ impl Ros {
             param.set_raw(yaml_to_xmlrpc(data)?)?;
         }
 
-        if ros
+        if let Some(clock) = clock {
+            ros.clock = clock;
+        } else if ros
             .param("/use_sim_time")
             .and_then(|v| v.get().ok())
             .unwrap_or(false)
@@ -83,6 +110,13 @@ impl Ros {
             ros.clock = ros_clock;
         }
 
+        if let Some(path) = resolve::rosconsole_config_file() {
+            if let Err(err) = ros.loggers.load_config_file(&path) {
+                error!("Failed to load logger config '{}': {}", path, err);
+            }
+        }
+        ros.add_logger_services()?;
+
         *ros.logger.lock().unwrap() = Some(ros.publish("/rosout", 100)?);
 
         Ok(ros)
@@ -136,11 +170,40 @@ impl Ros {
             name,
             clock: Arc::new(RealClock::default()),
             static_subs: Vec::new(),
+            static_services: Vec::new(),
+            loggers: Arc::new(Loggers::default()),
+            log_file: Mutex::new(None),
             logger,
             shutdown_manager,
         })
     }
 
+    fn add_logger_services(&mut self) -> Result<()> {
+        let loggers = Arc::clone(&self.loggers);
+        let get_loggers = self.service::<GetLoggers, _>("~get_loggers", move |_| {
+            let loggers = loggers
+                .list()
+                .into_iter()
+                .map(|(name, level)| Logger {
+                    name,
+                    level: level_name(level).into(),
+                })
+                .collect();
+            Ok(GetLoggersRes { loggers })
+        })?;
+        let loggers = Arc::clone(&self.loggers);
+        let set_logger_level =
+            self.service::<SetLoggerLevel, _>("~set_logger_level", move |req| {
+                let level = parse_level(&req.level)
+                    .ok_or_else(|| format!("Unknown logger level '{}'", req.level))?;
+                loggers.set_level(&req.logger, level);
+                Ok(SetLoggerLevelRes {})
+            })?;
+        self.static_services.push(get_loggers);
+        self.static_services.push(set_logger_level);
+        Ok(())
+    }
+
     fn map(&mut self, source: &str, destination: &str) -> Result<()> {
         self.resolver.map(source, destination).map_err(Into::into)
     }
@@ -187,6 +250,14 @@ impl Ros {
         Rate::new(Arc::clone(&self.clock), Duration::from_nanos(nanos as i64))
     }
 
+    /// Registers a callback for jumps of the node's clock, like a looping bag's `/clock`.
+    pub fn on_clock_jump<F>(&self, threshold: JumpThreshold, callback: F) -> JumpHandle
+    where
+        F: Fn(&ClockJump) + Send + Sync + 'static,
+    {
+        self.clock.on_jump(threshold, Box::new(callback))
+    }
+
     #[inline]
     pub fn is_ok(&self) -> bool {
         !self.shutdown_manager.awaiting_shutdown()
@@ -223,7 +294,13 @@ impl Ros {
 
     pub fn client<T: ServicePair>(&self, service: &str) -> Result<Client<T>> {
         let name = self.resolver.translate(service)?;
-        Ok(Client::new(Arc::clone(&self.master), &self.name, &name))
+        let limits = self.slave.decode_limits(&name);
+        Ok(Client::new(
+            Arc::clone(&self.master),
+            &self.name,
+            &name,
+            limits,
+        ))
     }
 
     pub fn wait_for_service(
@@ -400,8 +477,107 @@ impl Ros {
         }
     }
 
+    /// Checks if the named logger would output at the given level.
+    ///
+    /// The root logger is referred to by an empty name.
+    #[inline]
+    pub fn log_enabled(&self, level: i8, name: &str) -> bool {
+        self.loggers.is_enabled(name, level)
+    }
+
+    #[inline]
+    pub fn set_logger_level(&self, name: &str, level: i8) {
+        self.loggers.set_level(name, level)
+    }
+
+    /// Limits decoding of data from other nodes on all topics and services without their own.
+    ///
+    /// Limits are picked up when creating publishers, subscribers, services and clients,
+    /// so existing ones keep theirs. Data that breaks a limit, or has lengths that run past
+    /// the end of its message, drops the connection it came from, and the logged error
+    /// names the limit.
+    #[inline]
+    pub fn set_decode_limits(&self, limits: DecodeLimits) {
+        self.slave.set_decode_limits(limits)
+    }
+
+    /// Limits decoding of data from other nodes on a single topic or service.
+    pub fn set_topic_decode_limits(&self, name: &str, limits: DecodeLimits) -> Result<()> {
+        let name = self.resolver.translate(name)?;
+        self.slave.set_name_decode_limits(&name, limits);
+        Ok(())
+    }
+
+    #[inline]
     pub fn log(&self, level: i8, msg: String, file: &str, line: u32) {
+        self.log_named(level, "", msg, file, line)
+    }
+
+    /// Starts writing logs to a file, or stops if no options are provided.
+    ///
+    /// The file is placed at `<ROS_LOG_DIR>/<run_id>/<node>.log`, unless roslaunch
+    /// provided a path through `__log:=`.
+    pub fn set_log_file(&self, options: Option<LogFileOptions>) -> Result<()> {
+        let log_file = match options {
+            Some(options) => Some(LogFile::open(self.log_file_path()?, &options)?),
+            None => None,
+        };
+        *self.log_file.lock().expect(FAILED_TO_LOCK) = log_file;
+        Ok(())
+    }
+
+    fn log_file_path(&self) -> Result<PathBuf> {
+        if let Some(path) = resolve::log_file() {
+            return Ok(path.into());
+        }
+        let mut path = resolve::log_dir().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "Cannot resolve the ROS log directory",
+            )
+        })?;
+        if let Some(run_id) = self.param("/run_id").and_then(|v| v.get::<String>().ok()) {
+            path.push(run_id);
+        }
+        path.push(log_file::file_name(&self.name));
+        Ok(path)
+    }
+
+    fn log_to_file(&self, level: i8, name: &str, msg: &str, file: &str, line: u32) {
+        // Errors are reported after releasing the lock, as they might get logged back here
+        let failure = {
+            let mut maybe_log_file = self.log_file.lock().expect(FAILED_TO_LOCK);
+            let log_file = match maybe_log_file.as_mut() {
+                Some(log_file) => log_file,
+                None => return,
+            };
+            let logger = Loggers::full_name(name);
+            let record = LogRecord {
+                level,
+                logger: &logger,
+                node: &self.name,
+                msg,
+                file,
+                line,
+                time: self.now(),
+                walltime: crate::wall_time::now(),
+            };
+            log_file
+                .write(&record)
+                .err()
+                .map(|err| (log_file.path().to_owned(), err))
+        };
+        if let Some((path, err)) = failure {
+            error!("Failed to write to log file {}: {}", path.display(), err);
+        }
+    }
+
+    pub fn log_named(&self, level: i8, name: &str, msg: String, file: &str, line: u32) {
+        if !self.log_enabled(level, name) {
+            return;
+        }
         self.log_to_terminal(level, &msg, file, line);
+        self.log_to_file(level, name, &msg, file, line);
         let topics = self.slave.publications.get_topic_names();
         let message = Log {
             header: Header::default(),
@@ -413,8 +589,8 @@ impl Ros {
             function: String::default(),
             topics,
         };
-        let maybe_logger = self.logger.lock().unwrap();
-        if let Some(logger) = maybe_logger.deref() {
+        let maybe_logger = self.logger.lock().unwrap().clone();
+        if let Some(logger) = maybe_logger {
             if let Err(err) = logger.send(message) {
                 error!("Logging error: {}", err);
             }
@@ -6,6 +6,7 @@ pub use self::handler::ParamCache;
 use self::handler::SlaveHandler;
 use super::error::{self, ErrorKind, Result};
 use crate::api::ShutdownManager;
+use crate::rosmsg::DecodeLimits;
 use crate::tcpros::{Message, PublisherStream, Service, ServicePair, ServiceResult};
 use crate::util::{kill, FAILED_TO_LOCK};
 use crate::{RawMessageDescription, SubscriptionHandler};
@@ -23,6 +24,14 @@ pub struct Slave {
     pub subscriptions: subscriptions::SubscriptionsTracker,
     pub services: Arc<Mutex<HashMap<String, Service>>>,
     pub shutdown_tx: kill::Sender,
+    decode_limits: Mutex<DecodeLimitsConfig>,
+}
+
+/// Decode limits of the node, with overrides for individual topics and services.
+#[derive(Default)]
+struct DecodeLimitsConfig {
+    node: DecodeLimits,
+    names: HashMap<String, DecodeLimits>,
 }
 
 type SerdeResult<T> = Result<T>;
@@ -77,6 +86,7 @@ impl Slave {
             subscriptions,
             services,
             shutdown_tx,
+            decode_limits: Mutex::new(DecodeLimitsConfig::default()),
         })
     }
 
@@ -85,6 +95,25 @@ impl Slave {
         &self.uri
     }
 
+    /// Sets the limits of topics and services without their own, once they get created.
+    pub fn set_decode_limits(&self, limits: DecodeLimits) {
+        self.decode_limits.lock().expect(FAILED_TO_LOCK).node = limits;
+    }
+
+    /// Sets the limits of a single topic or service, once it gets created.
+    pub fn set_name_decode_limits(&self, name: &str, limits: DecodeLimits) {
+        self.decode_limits
+            .lock()
+            .expect(FAILED_TO_LOCK)
+            .names
+            .insert(String::from(name), limits);
+    }
+
+    pub fn decode_limits(&self, name: &str) -> DecodeLimits {
+        let config = self.decode_limits.lock().expect(FAILED_TO_LOCK);
+        config.names.get(name).copied().unwrap_or(config.node)
+    }
+
     pub fn add_publishers_to_subscription<T>(&self, topic: &str, publishers: T) -> SerdeResult<()>
     where
         T: Iterator<Item = String>,
@@ -116,8 +145,15 @@ impl Slave {
                 Err(ErrorKind::Duplicate("service".into()).into())
             }
             Entry::Vacant(entry) => {
-                let service =
-                    Service::new::<T, _>(hostname, bind_address, 0, service, &self.name, handler)?;
+                let service = Service::new::<T, _>(
+                    hostname,
+                    bind_address,
+                    0,
+                    service,
+                    &self.name,
+                    self.decode_limits(service),
+                    handler,
+                )?;
                 let api = service.api.clone();
                 entry.insert(service);
                 Ok(api)
@@ -141,8 +177,14 @@ impl Slave {
     where
         T: Message,
     {
-        self.publications
-            .add(hostname, topic, queue_size, &self.name, message_description)
+        self.publications.add(
+            hostname,
+            topic,
+            queue_size,
+            &self.name,
+            message_description,
+            self.decode_limits(topic),
+        )
     }
 
     #[inline]
@@ -161,8 +203,13 @@ impl Slave {
         T: Message,
         H: SubscriptionHandler<T>,
     {
-        self.subscriptions
-            .add(&self.name, topic, queue_size, handler)
+        self.subscriptions.add(
+            &self.name,
+            topic,
+            queue_size,
+            self.decode_limits(topic),
+            handler,
+        )
     }
 
     #[inline]
@@ -1,4 +1,5 @@
 use crate::api::error;
+use crate::rosmsg::DecodeLimits;
 use crate::tcpros::{Publisher, PublisherStream, Topic};
 use crate::util::FAILED_TO_LOCK;
 use crate::{Message, RawMessageDescription};
@@ -49,6 +50,7 @@ impl PublicationsTracker {
         queue_size: usize,
         caller_id: &str,
         message_description: RawMessageDescription,
+        limits: DecodeLimits,
     ) -> error::tcpros::Result<PublisherStream<T>> {
         use std::collections::hash_map::Entry;
         match self
@@ -67,6 +69,7 @@ impl PublicationsTracker {
                     queue_size,
                     caller_id,
                     message_description.clone(),
+                    limits,
                 )?;
                 entry
                     .insert(publisher)
@@ -1,4 +1,5 @@
 use crate::api::error::{self, ErrorKind, Result};
+use crate::rosmsg::DecodeLimits;
 use crate::tcpros::{SubscriberRosConnection, Topic};
 use crate::util::FAILED_TO_LOCK;
 use crate::{Message, SubscriptionHandler};
@@ -51,7 +52,14 @@ impl SubscriptionsTracker {
             .collect()
     }
 
-    pub fn add<T, H>(&self, name: &str, topic: &str, queue_size: usize, handler: H) -> Result<usize>
+    pub fn add<T, H>(
+        &self,
+        name: &str,
+        topic: &str,
+        queue_size: usize,
+        limits: DecodeLimits,
+        handler: H,
+    ) -> Result<usize>
     where
         T: Message,
         H: SubscriptionHandler<T>,
@@ -67,6 +75,7 @@ impl SubscriptionsTracker {
                 msg_definition,
                 msg_type.clone(),
                 md5sum.clone(),
+                limits,
             )
         });
         let connection_topic = connection.get_topic();
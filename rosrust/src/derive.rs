@@ -0,0 +1,391 @@
+//! Support code for `#[derive(RosMessage)]`.
+
+use crate::rosmsg::{self, RosMsg};
+use crate::{Message, Primitive, SharedSlice};
+use ros_message::{Duration, MessagePath, Msg, Time};
+use std::collections::{BTreeSet, HashMap};
+use std::convert::{TryFrom, TryInto};
+use std::io;
+
+const SEPARATOR: &str =
+    "================================================================================";
+
+/// Message that a derived message depends on.
+pub struct Dependency {
+    pub msg_type: String,
+    pub md5sum: String,
+    pub msg_definition: String,
+}
+
+/// Types that can be fields, or elements of array fields, in derived messages.
+pub trait MessageElement: RosMsg {
+    fn datatype() -> String;
+
+    #[inline]
+    fn dependency() -> Option<Dependency> {
+        None
+    }
+
+    #[inline]
+    fn encode_variable<W: io::Write>(data: &[Self], w: W) -> io::Result<()> {
+        rosmsg::encode_variable_slice(data, w)
+    }
+
+    #[inline]
+    fn decode_variable<R: io::Read>(r: R) -> io::Result<Vec<Self>> {
+        rosmsg::decode_variable_vec(r)
+    }
+}
+
+/// Types of fields in derived messages, which are elements or arrays of elements.
+pub trait MessageField: Sized {
+    fn datatype() -> String;
+    fn dependency() -> Option<Dependency>;
+    fn encode_field<W: io::Write>(&self, w: W) -> io::Result<()>;
+    fn decode_field<R: io::Read>(r: R) -> io::Result<Self>;
+    fn encoded_len_field(&self) -> usize;
+}
+
+macro_rules! impl_element {
+    ($t:ty, $name:expr) => {
+        impl MessageElement for $t {
+            #[inline]
+            fn datatype() -> String {
+                $name.into()
+            }
+        }
+    };
+    ($t:ty, $name:expr, primitive) => {
+        impl MessageElement for $t {
+            #[inline]
+            fn datatype() -> String {
+                $name.into()
+            }
+
+            #[inline]
+            fn encode_variable<W: io::Write>(data: &[Self], w: W) -> io::Result<()> {
+                rosmsg::encode_variable_primitive_slice(data, w)
+            }
+
+            #[inline]
+            fn decode_variable<R: io::Read>(r: R) -> io::Result<Vec<Self>> {
+                rosmsg::decode_variable_primitive_vec(r)
+            }
+        }
+    };
+}
+
+impl_element!(bool, "bool");
+impl_element!(i8, "int8", primitive);
+impl_element!(i16, "int16", primitive);
+impl_element!(i32, "int32", primitive);
+impl_element!(i64, "int64", primitive);
+impl_element!(u8, "uint8", primitive);
+impl_element!(u16, "uint16", primitive);
+impl_element!(u32, "uint32", primitive);
+impl_element!(u64, "uint64", primitive);
+impl_element!(f32, "float32", primitive);
+impl_element!(f64, "float64", primitive);
+impl_element!(String, "string");
+impl_element!(Time, "time");
+impl_element!(Duration, "duration");
+
+impl<T: Message> MessageElement for T {
+    #[inline]
+    fn datatype() -> String {
+        T::msg_type()
+    }
+
+    #[inline]
+    fn dependency() -> Option<Dependency> {
+        Some(Dependency {
+            msg_type: T::msg_type(),
+            md5sum: T::md5sum(),
+            msg_definition: T::msg_definition(),
+        })
+    }
+}
+
+impl<T: MessageElement> MessageField for T {
+    #[inline]
+    fn datatype() -> String {
+        <T as MessageElement>::datatype()
+    }
+
+    #[inline]
+    fn dependency() -> Option<Dependency> {
+        <T as MessageElement>::dependency()
+    }
+
+    #[inline]
+    fn encode_field<W: io::Write>(&self, w: W) -> io::Result<()> {
+        self.encode(w)
+    }
+
+    #[inline]
+    fn decode_field<R: io::Read>(r: R) -> io::Result<Self> {
+        T::decode(r)
+    }
+
+    #[inline]
+    fn encoded_len_field(&self) -> usize {
+        self.encoded_len()
+    }
+}
+
+impl<T: MessageElement> MessageField for Vec<T> {
+    #[inline]
+    fn datatype() -> String {
+        format!("{}[]", <T as MessageElement>::datatype())
+    }
+
+    #[inline]
+    fn dependency() -> Option<Dependency> {
+        <T as MessageElement>::dependency()
+    }
+
+    #[inline]
+    fn encode_field<W: io::Write>(&self, w: W) -> io::Result<()> {
+        T::encode_variable(self, w)
+    }
+
+    #[inline]
+    fn decode_field<R: io::Read>(r: R) -> io::Result<Self> {
+        T::decode_variable(r)
+    }
+
+    #[inline]
+    fn encoded_len_field(&self) -> usize {
+        rosmsg::encoded_len_variable_slice(self)
+    }
+}
+
+impl<T: MessageElement + Primitive> MessageField for SharedSlice<T> {
+    #[inline]
+    fn datatype() -> String {
+        format!("{}[]", <T as MessageElement>::datatype())
+    }
+
+    #[inline]
+    fn dependency() -> Option<Dependency> {
+        None
+    }
+
+    #[inline]
+    fn encode_field<W: io::Write>(&self, w: W) -> io::Result<()> {
+        self.encode(w)
+    }
+
+    #[inline]
+    fn decode_field<R: io::Read>(r: R) -> io::Result<Self> {
+        Self::decode(r)
+    }
+
+    #[inline]
+    fn encoded_len_field(&self) -> usize {
+        self.encoded_len()
+    }
+}
+
+impl<T: MessageElement, const N: usize> MessageField for [T; N] {
+    #[inline]
+    fn datatype() -> String {
+        format!("{}[{}]", <T as MessageElement>::datatype(), N)
+    }
+
+    #[inline]
+    fn dependency() -> Option<Dependency> {
+        <T as MessageElement>::dependency()
+    }
+
+    #[inline]
+    fn encode_field<W: io::Write>(&self, w: W) -> io::Result<()> {
+        rosmsg::encode_fixed_slice(self, w)
+    }
+
+    #[inline]
+    fn decode_field<R: io::Read>(r: R) -> io::Result<Self> {
+        let items = rosmsg::decode_fixed_vec(N as u32, r)?;
+        match <[T; N]>::try_from(items) {
+            Ok(items) => Ok(items),
+            Err(_) => unreachable!("Decoded exactly {} items", N),
+        }
+    }
+
+    #[inline]
+    fn encoded_len_field(&self) -> usize {
+        rosmsg::encoded_len_fixed_slice(self)
+    }
+}
+
+/// Field of a derived message, given as its ROS name and datatype.
+pub fn field_line<T: MessageField>(name: &str) -> String {
+    format!("{} {}", T::datatype(), name)
+}
+
+/// Message definition made of the message source and all its dependencies.
+pub fn msg_definition(source: &str, dependencies: &[Option<Dependency>]) -> String {
+    let mut handled = BTreeSet::new();
+    let mut result = source.to_owned();
+    for dependency in dependencies.iter().flatten() {
+        let mut sections = dependency.msg_definition.split(SEPARATOR);
+        let own_source = sections.next().unwrap_or_default();
+        let sections = sections.filter_map(|section| {
+            let section = section.trim_start_matches('\n');
+            let section = section.strip_prefix("MSG: ")?;
+            let mut parts = section.splitn(2, '\n');
+            Some((parts.next()?, parts.next().unwrap_or_default()))
+        });
+        for (msg_type, source) in Some((dependency.msg_type.as_str(), own_source))
+            .into_iter()
+            .chain(sections)
+        {
+            if !handled.insert(msg_type.to_owned()) {
+                continue;
+            }
+            result += &format!(
+                "\n\n{}\nMSG: {}\n{}",
+                SEPARATOR,
+                msg_type,
+                source.trim_end()
+            );
+        }
+    }
+    result += "\n";
+    result
+}
+
+/// MD5 sum of a derived message, matching the sum of an equivalent `.msg` file.
+pub fn md5sum(msg_type: &str, source: &str, dependencies: &[Option<Dependency>]) -> String {
+    let path: MessagePath = msg_type
+        .try_into()
+        .unwrap_or_else(|err| panic!("Bad message type {}: {}", msg_type, err));
+    let hashes = dependencies
+        .iter()
+        .flatten()
+        .filter_map(|dependency| {
+            let path = dependency.msg_type.as_str().try_into().ok()?;
+            Some((path, dependency.md5sum.clone()))
+        })
+        .collect::<HashMap<MessagePath, String>>();
+    Msg::new(path, source)
+        .and_then(|message| message.calculate_md5(&hashes))
+        .unwrap_or_else(|err| panic!("Bad message {}: {}", msg_type, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Debug, Default, PartialEq)]
+    struct Point {
+        x: f64,
+        y: f64,
+        z: f64,
+    }
+
+    impl RosMsg for Point {
+        fn encode<W: io::Write>(&self, mut w: W) -> io::Result<()> {
+            self.x.encode_field(w.by_ref())?;
+            self.y.encode_field(w.by_ref())?;
+            self.z.encode_field(w.by_ref())
+        }
+
+        fn decode<R: io::Read>(mut r: R) -> io::Result<Self> {
+            Ok(Self {
+                x: MessageField::decode_field(r.by_ref())?,
+                y: MessageField::decode_field(r.by_ref())?,
+                z: MessageField::decode_field(r.by_ref())?,
+            })
+        }
+    }
+
+    impl Message for Point {
+        fn msg_definition() -> String {
+            msg_definition(&Self::source(), &[])
+        }
+
+        fn md5sum() -> String {
+            md5sum("geometry_msgs/Point", &Self::source(), &[])
+        }
+
+        fn msg_type() -> String {
+            "geometry_msgs/Point".into()
+        }
+    }
+
+    impl Point {
+        fn source() -> String {
+            [
+                field_line::<f64>("x"),
+                field_line::<f64>("y"),
+                field_line::<f64>("z"),
+            ]
+            .join("\n")
+        }
+    }
+
+    #[test]
+    fn md5sum_matches_msg_files() {
+        assert_eq!(Point::md5sum(), "4a842b65f413084dc2b10fb484ea7f17");
+        let source = [
+            field_line::<Vec<Point>>("points"),
+            field_line::<[u8; 4]>("data"),
+        ]
+        .join("\n");
+        let dependencies = [
+            <Vec<Point> as MessageField>::dependency(),
+            <[u8; 4] as MessageField>::dependency(),
+        ];
+        assert_eq!(source, "geometry_msgs/Point[] points\nuint8[4] data");
+        // MD5 sum of "4a842b65f413084dc2b10fb484ea7f17 points\nuint8[4] data"
+        assert_eq!(
+            md5sum("foo/Polygon", &source, &dependencies),
+            "1ec2a69249efd1a1c53c700a231f40d3",
+        );
+    }
+
+    #[test]
+    fn msg_definition_flattens_dependencies() {
+        let dependencies = [
+            Some(Dependency {
+                msg_type: "foo/A".into(),
+                md5sum: String::new(),
+                msg_definition: format!("foo/B b\n\n{}\nMSG: foo/B\nint32 x\n", SEPARATOR),
+            }),
+            Some(Dependency {
+                msg_type: "foo/B".into(),
+                md5sum: String::new(),
+                msg_definition: "int32 x\n".into(),
+            }),
+        ];
+        assert_eq!(
+            msg_definition("foo/A a\nfoo/B b", &dependencies),
+            format!(
+                "foo/A a\nfoo/B b\n\n{0}\nMSG: foo/A\nfoo/B b\n\n{0}\nMSG: foo/B\nint32 x\n",
+                SEPARATOR,
+            ),
+        );
+    }
+
+    #[test]
+    fn encodes_arrays() {
+        let data = ([1u8, 2], vec![3u16], vec![Point::default()]);
+        let mut buffer = vec![];
+        data.0.encode_field(&mut buffer).unwrap();
+        data.1.encode_field(&mut buffer).unwrap();
+        data.2.encode_field(&mut buffer).unwrap();
+        assert_eq!(buffer.len(), 2 + 6 + 4 + 24);
+        assert_eq!(
+            data.0.encoded_len_field() + data.1.encoded_len_field() + data.2.encoded_len_field(),
+            buffer.len(),
+        );
+        let mut reader = io::Cursor::new(buffer);
+        let decoded: ([u8; 2], Vec<u16>, Vec<Point>) = (
+            MessageField::decode_field(&mut reader).unwrap(),
+            MessageField::decode_field(&mut reader).unwrap(),
+            MessageField::decode_field(&mut reader).unwrap(),
+        );
+        assert_eq!(decoded, data);
+    }
+}
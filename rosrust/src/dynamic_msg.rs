@@ -1,8 +1,11 @@
 use crate::error::{Result, ResultExt};
+use crate::message_diff::MessageDiff;
+use crate::rosmsg;
 use crate::{Duration, RosMsg, Time};
 use lazy_static::lazy_static;
 use regex::RegexBuilder;
-use ros_message::{DataType, FieldCase, FieldInfo, MessagePath, MessageValue, Msg, Value};
+use ros_message::{wire, DataType, FieldCase, FieldInfo, MessagePath, MessageValue, Msg, Value};
+use serde::ser::{Serialize, SerializeTuple, Serializer};
 use std::collections::HashMap;
 use std::convert::TryInto;
 use std::io;
@@ -55,6 +58,15 @@ impl DynamicMsg {
         self.dependencies.get(path)
     }
 
+    pub fn dependencies(&self) -> &HashMap<MessagePath, Msg> {
+        &self.dependencies
+    }
+
+    /// Compares the structure of both messages, describing how `other` differs from `self`.
+    pub fn diff(&self, other: &DynamicMsg) -> MessageDiff {
+        MessageDiff::new(self, other)
+    }
+
     pub fn from_headers(headers: HashMap<String, String>) -> Result<Self> {
         let message_type = headers.get("type").chain_err(|| "Missing header `type`")?;
         let message_definition = headers
@@ -91,13 +103,14 @@ impl DynamicMsg {
 
     pub fn encode(&self, value: &MessageValue, mut w: impl io::Write) -> io::Result<()> {
         self.encode_message(&self.msg, value, &mut w)
+            .map_err(|err| self.explain_encode_error(value, err))
     }
 
     pub fn decode(&self, mut r: impl io::Read) -> io::Result<MessageValue> {
         self.decode_message(&self.msg, &mut r)
     }
 
-    fn get_dependency(&self, path: &MessagePath) -> io::Result<&Msg> {
+    pub(crate) fn get_dependency(&self, path: &MessagePath) -> io::Result<&Msg> {
         self.dependencies.get(path).ok_or_else(|| {
             io::Error::new(
                 io::ErrorKind::Other,
@@ -140,20 +153,20 @@ impl DynamicMsg {
         w: &mut impl std::io::Write,
     ) -> io::Result<()> {
         match (field.datatype(), value) {
-            (DataType::Bool, Value::Bool(v)) => v.encode(w),
-            (DataType::I8(_), Value::I8(v)) => v.encode(w),
-            (DataType::I16, Value::I16(v)) => v.encode(w),
-            (DataType::I32, Value::I32(v)) => v.encode(w),
-            (DataType::I64, Value::I64(v)) => v.encode(w),
-            (DataType::U8(_), Value::U8(v)) => v.encode(w),
-            (DataType::U16, Value::U16(v)) => v.encode(w),
-            (DataType::U32, Value::U32(v)) => v.encode(w),
-            (DataType::U64, Value::U64(v)) => v.encode(w),
-            (DataType::F32, Value::F32(v)) => v.encode(w),
-            (DataType::F64, Value::F64(v)) => v.encode(w),
-            (DataType::String, Value::String(v)) => v.encode(w),
-            (DataType::Time, Value::Time(time)) => time.encode(w),
-            (DataType::Duration, Value::Duration(duration)) => duration.encode(w),
+            (DataType::Bool, Value::Bool(v)) => encode_wire(v, w),
+            (DataType::I8(_), Value::I8(v)) => encode_wire(v, w),
+            (DataType::I16, Value::I16(v)) => encode_wire(v, w),
+            (DataType::I32, Value::I32(v)) => encode_wire(v, w),
+            (DataType::I64, Value::I64(v)) => encode_wire(v, w),
+            (DataType::U8(_), Value::U8(v)) => encode_wire(v, w),
+            (DataType::U16, Value::U16(v)) => encode_wire(v, w),
+            (DataType::U32, Value::U32(v)) => encode_wire(v, w),
+            (DataType::U64, Value::U64(v)) => encode_wire(v, w),
+            (DataType::F32, Value::F32(v)) => encode_wire(v, w),
+            (DataType::F64, Value::F64(v)) => encode_wire(v, w),
+            (DataType::String, Value::String(v)) => encode_wire(v, w),
+            (DataType::Time, Value::Time(time)) => encode_wire(time, w),
+            (DataType::Duration, Value::Duration(duration)) => encode_wire(duration, w),
             (DataType::LocalMessage(name), Value::Message(v)) => {
                 let path = parent.peer(name);
                 let dependency = self.get_dependency(&path)?;
@@ -178,46 +191,36 @@ impl DynamicMsg {
         array_length: Option<usize>,
         w: &mut impl std::io::Write,
     ) -> io::Result<()> {
-        let value = match value {
-            Value::Array(v) => v,
-            Value::Bool(_)
-            | Value::I8(_)
-            | Value::I16(_)
-            | Value::I32(_)
-            | Value::I64(_)
-            | Value::U8(_)
-            | Value::U16(_)
-            | Value::U32(_)
-            | Value::U64(_)
-            | Value::F32(_)
-            | Value::F64(_)
-            | Value::String(_)
-            | Value::Time { .. }
-            | Value::Duration { .. }
-            | Value::Message(_) => {
-                return Err(io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    "Passed in dynamic message field is not an array",
-                ));
-            }
-        };
-        match array_length {
-            Some(array_length) => {
-                if array_length != value.len() {
-                    return Err(io::Error::new(
-                        io::ErrorKind::InvalidData,
-                        "Passed in dynamic message array field has wrong length",
-                    ));
+        match (field.datatype(), value) {
+            (_, Value::Array(v)) => {
+                encode_array_length(v.len(), array_length, w.by_ref())?;
+                for value in v {
+                    self.encode_field(parent, field, value, w.by_ref())?;
                 }
+                Ok(())
             }
-            None => {
-                (value.len() as u32).encode(w.by_ref())?;
-            }
-        }
-        for value in value {
-            self.encode_field(parent, field, value, w.by_ref())?;
+            (DataType::U8(_), Value::Bytes(v)) => match array_length {
+                Some(_) => encode_primitive_array(v, array_length, w),
+                None => encode_wire(&Bytes(v), w),
+            },
+            (DataType::I8(_), Value::I8Array(v)) => encode_primitive_array(v, array_length, w),
+            (DataType::I16, Value::I16Array(v)) => encode_primitive_array(v, array_length, w),
+            (DataType::I32, Value::I32Array(v)) => encode_primitive_array(v, array_length, w),
+            (DataType::I64, Value::I64Array(v)) => encode_primitive_array(v, array_length, w),
+            (DataType::U16, Value::U16Array(v)) => encode_primitive_array(v, array_length, w),
+            (DataType::U32, Value::U32Array(v)) => encode_primitive_array(v, array_length, w),
+            (DataType::U64, Value::U64Array(v)) => encode_primitive_array(v, array_length, w),
+            (DataType::F32, Value::F32Array(v)) => encode_primitive_array(v, array_length, w),
+            (DataType::F64, Value::F64Array(v)) => encode_primitive_array(v, array_length, w),
+            (_, value) if value.array_len().is_some() => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Passed in dynamic data value does not match message format",
+            )),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Passed in dynamic message field is not an array",
+            )),
         }
-        Ok(())
     }
 
     fn decode_message(&self, msg: &Msg, r: &mut impl io::Read) -> io::Result<MessageValue> {
@@ -234,7 +237,7 @@ impl DynamicMsg {
         Ok(output)
     }
 
-    fn decode_field(
+    pub(crate) fn decode_field(
         &self,
         parent: &MessagePath,
         field: &FieldInfo,
@@ -267,20 +270,106 @@ impl DynamicMsg {
         })
     }
 
-    fn decode_field_array(
+    pub(crate) fn decode_field_array(
         &self,
         parent: &MessagePath,
         field: &FieldInfo,
         array_length: Option<usize>,
         r: &mut impl io::Read,
     ) -> io::Result<Value> {
-        let array_length = match array_length {
-            Some(v) => v,
-            None => u32::decode(r.by_ref())? as usize,
-        };
-        // TODO: optimize by checking data type only once
-        (0..array_length)
-            .map(|_| self.decode_field(parent, field, r))
-            .collect()
+        Ok(match field.datatype() {
+            DataType::U8(_) => Value::Bytes(decode_primitive_array(array_length, r)?),
+            DataType::I8(_) => Value::I8Array(decode_primitive_array(array_length, r)?),
+            DataType::I16 => Value::I16Array(decode_primitive_array(array_length, r)?),
+            DataType::I32 => Value::I32Array(decode_primitive_array(array_length, r)?),
+            DataType::I64 => Value::I64Array(decode_primitive_array(array_length, r)?),
+            DataType::U16 => Value::U16Array(decode_primitive_array(array_length, r)?),
+            DataType::U32 => Value::U32Array(decode_primitive_array(array_length, r)?),
+            DataType::U64 => Value::U64Array(decode_primitive_array(array_length, r)?),
+            DataType::F32 => Value::F32Array(decode_primitive_array(array_length, r)?),
+            DataType::F64 => Value::F64Array(decode_primitive_array(array_length, r)?),
+            DataType::Bool
+            | DataType::String
+            | DataType::Time
+            | DataType::Duration
+            | DataType::LocalMessage(_)
+            | DataType::GlobalMessage(_) => {
+                let array_length = match array_length {
+                    Some(v) => v,
+                    None => rosmsg::decode_array_length(r.by_ref())?,
+                };
+                Value::Array(
+                    (0..array_length)
+                        .map(|_| self.decode_field(parent, field, r))
+                        .collect::<io::Result<_>>()?,
+                )
+            }
+        })
+    }
+}
+
+/// Encodes values in the ROS1 wire format shared with `ros_message::wire`.
+fn encode_wire<T: Serialize + ?Sized>(value: &T, w: impl io::Write) -> io::Result<()> {
+    Ok(wire::to_writer(w, value)?)
+}
+
+fn encode_array_length(
+    len: usize,
+    array_length: Option<usize>,
+    w: impl io::Write,
+) -> io::Result<()> {
+    match array_length {
+        Some(array_length) if array_length != len => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Passed in dynamic message array field has wrong length",
+        )),
+        Some(_) => Ok(()),
+        None => encode_wire(&(len as u32), w),
+    }
+}
+
+fn encode_primitive_array<T: Serialize>(
+    data: &[T],
+    array_length: Option<usize>,
+    w: &mut impl io::Write,
+) -> io::Result<()> {
+    match array_length {
+        Some(_) => {
+            encode_array_length(data.len(), array_length, w.by_ref())?;
+            encode_wire(&FixedItems(data), w)
+        }
+        None => encode_wire(data, w),
+    }
+}
+
+/// Items of a fixed length array, which have no length prefix.
+struct FixedItems<'a, T>(&'a [T]);
+
+impl<T: Serialize> Serialize for FixedItems<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut tuple = serializer.serialize_tuple(self.0.len())?;
+        for item in self.0 {
+            tuple.serialize_element(item)?;
+        }
+        tuple.end()
+    }
+}
+
+/// Contents of a `uint8[]` array, written in one go instead of byte by byte.
+struct Bytes<'a>(&'a [u8]);
+
+impl Serialize for Bytes<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+fn decode_primitive_array<T: RosMsg>(
+    array_length: Option<usize>,
+    r: &mut impl io::Read,
+) -> io::Result<Vec<T>> {
+    match array_length {
+        Some(len) => rosmsg::decode_fixed_vec(len as u32, r),
+        None => rosmsg::decode_variable_primitive_vec(r),
     }
 }
@@ -0,0 +1,294 @@
+use crate::error::{Result, ResultExt};
+use crate::{rosmsg, DynamicMsg, RosMsg};
+use error_chain::bail;
+use ros_message::{
+    DataType, FieldCase, FieldInfo, FieldPath, MessagePath, MessageValue, Msg, PathComponent,
+    Selection, Value,
+};
+use std::borrow::Cow;
+use std::io;
+
+/// Field path compiled against a message definition, like `pose.position.x` or `status[*].name`.
+///
+/// Compiling checks that all fields exist and that only arrays get indexed, so typos fail
+/// early instead of silently selecting nothing. Values can then be selected from decoded
+/// messages, or extracted directly from encoded data, skipping over all other fields.
+#[derive(Clone, Debug)]
+pub struct FieldQuery {
+    message: DynamicMsg,
+    path: FieldPath,
+    datatype: String,
+}
+
+impl FieldQuery {
+    pub fn new(message: &DynamicMsg, path: &str) -> Result<Self> {
+        let path = FieldPath::new(path).chain_err(|| format!("Bad field path `{}`", path))?;
+        let datatype = compile(message, &path)
+            .chain_err(|| format!("Field path `{}` does not match the message", path))?;
+        Ok(Self {
+            message: message.clone(),
+            path,
+            datatype,
+        })
+    }
+
+    #[inline]
+    pub fn path(&self) -> &FieldPath {
+        &self.path
+    }
+
+    /// Type of the selected values, like `float64`, `geometry_msgs/Point` or `uint8[]`.
+    #[inline]
+    pub fn datatype(&self) -> &str {
+        &self.datatype
+    }
+
+    /// Returns whether the query selects at most one value, without ranges or `[*]`.
+    #[inline]
+    pub fn is_single(&self) -> bool {
+        self.path.is_single()
+    }
+
+    /// Returns the selected value of a single query from a decoded message.
+    #[inline]
+    pub fn get<'a>(&self, message: &'a MessageValue) -> Option<Cow<'a, Value>> {
+        self.path.get(message)
+    }
+
+    /// Returns all selected values from a decoded message.
+    #[inline]
+    pub fn select<'a>(&self, message: &'a MessageValue) -> Vec<Cow<'a, Value>> {
+        self.path.select(message)
+    }
+
+    /// Decodes only the selected values from an encoded message, without its length prefix.
+    ///
+    /// Reading stops right after the last selected value.
+    pub fn extract(&self, mut r: impl io::Read) -> io::Result<Vec<Value>> {
+        let mut output = vec![];
+        self.extract_message(
+            self.message.msg(),
+            self.path.components(),
+            &mut r,
+            &mut output,
+            false,
+        )?;
+        Ok(output)
+    }
+
+    fn extract_message(
+        &self,
+        msg: &Msg,
+        components: &[PathComponent],
+        r: &mut impl io::Read,
+        output: &mut Vec<Value>,
+        consume_rest: bool,
+    ) -> io::Result<()> {
+        let (component, rest) = match components.split_first() {
+            Some(v) => v,
+            None => return Ok(()),
+        };
+        let mut fields = msg.fields().iter().filter(|v| !v.is_constant());
+        for field in &mut fields {
+            if field.name() != component.name() {
+                self.skip_field(msg.path(), field, r)?;
+                continue;
+            }
+            let len = match (field.case(), component.selection()) {
+                (FieldCase::Vector, None) => {
+                    output.push(
+                        self.message
+                            .decode_field_array(msg.path(), field, None, r)?,
+                    );
+                    break;
+                }
+                (FieldCase::Array(l), None) => {
+                    output.push(
+                        self.message
+                            .decode_field_array(msg.path(), field, Some(*l), r)?,
+                    );
+                    break;
+                }
+                (FieldCase::Unit, _) | (FieldCase::Const(_), _) => {
+                    self.extract_value(msg.path(), field, rest, r, output, consume_rest)?;
+                    break;
+                }
+                (FieldCase::Vector, Some(_)) => rosmsg::decode_array_length(&mut *r)?,
+                (FieldCase::Array(l), Some(_)) => *l,
+            };
+            let range = component
+                .selection()
+                .map_or(0..len, |selection| selection.range(len));
+            for idx in 0..len {
+                if !range.contains(&idx) {
+                    self.skip_value(msg.path(), field.datatype(), r)?;
+                    continue;
+                }
+                if idx + 1 == range.end && !consume_rest {
+                    // Nothing after the last selected item is needed
+                    return self.extract_value(msg.path(), field, rest, r, output, false);
+                }
+                self.extract_value(msg.path(), field, rest, r, output, true)?;
+            }
+            break;
+        }
+        if consume_rest {
+            for field in fields {
+                self.skip_field(msg.path(), field, r)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn extract_value(
+        &self,
+        parent: &MessagePath,
+        field: &FieldInfo,
+        rest: &[PathComponent],
+        r: &mut impl io::Read,
+        output: &mut Vec<Value>,
+        consume_rest: bool,
+    ) -> io::Result<()> {
+        if rest.is_empty() {
+            output.push(self.message.decode_field(parent, field, r)?);
+            return Ok(());
+        }
+        let path = field.datatype().message_path(parent).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, "Queried field is not a message")
+        })?;
+        let msg = self.message.get_dependency(&path)?;
+        self.extract_message(msg, rest, r, output, consume_rest)
+    }
+
+    fn skip_field(
+        &self,
+        parent: &MessagePath,
+        field: &FieldInfo,
+        r: &mut impl io::Read,
+    ) -> io::Result<()> {
+        let len = match field.case() {
+            FieldCase::Const(_) => return Ok(()),
+            FieldCase::Unit => return self.skip_value(parent, field.datatype(), r),
+            FieldCase::Vector => rosmsg::decode_array_length(&mut *r)?,
+            FieldCase::Array(l) => *l,
+        };
+        if let Some(size) = fixed_size(field.datatype()) {
+            let num_bytes = len.checked_mul(size).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "Array length is too large")
+            })?;
+            return skip_bytes(r, num_bytes);
+        }
+        for _ in 0..len {
+            self.skip_value(parent, field.datatype(), r)?;
+        }
+        Ok(())
+    }
+
+    fn skip_value(
+        &self,
+        parent: &MessagePath,
+        datatype: &DataType,
+        r: &mut impl io::Read,
+    ) -> io::Result<()> {
+        if let Some(size) = fixed_size(datatype) {
+            return skip_bytes(r, size);
+        }
+        if let DataType::String = datatype {
+            let len = u32::decode(&mut *r)? as usize;
+            return skip_bytes(r, len);
+        }
+        let path = datatype.message_path(parent).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, "Skipped field has unknown size")
+        })?;
+        let msg = self.message.get_dependency(&path)?;
+        for field in msg.fields() {
+            self.skip_field(msg.path(), field, r)?;
+        }
+        Ok(())
+    }
+}
+
+impl DynamicMsg {
+    /// Compiles a field path like `pose.position.x` against the message.
+    #[inline]
+    pub fn query(&self, path: &str) -> Result<FieldQuery> {
+        FieldQuery::new(self, path)
+    }
+}
+
+/// Checks the path against the message, returning the type of selected values.
+fn compile(message: &DynamicMsg, path: &FieldPath) -> Result<String> {
+    let mut msg = message.msg();
+    let components = path.components();
+    for (idx, component) in components.iter().enumerate() {
+        let field = match msg
+            .fields()
+            .iter()
+            .find(|v| !v.is_constant() && v.name() == component.name())
+        {
+            Some(field) => field,
+            None => bail!("{} has no field `{}`", msg.path(), component.name()),
+        };
+        let is_last = idx + 1 == components.len();
+        match (field.case(), component.selection()) {
+            (FieldCase::Unit, Some(_)) => {
+                bail!("Field `{}` of {} is not an array", field.name(), msg.path())
+            }
+            (FieldCase::Array(l), Some(Selection::Index(index))) if index >= *l => bail!(
+                "Index {} is out of bounds for field `{}` of {} with length {}",
+                index,
+                field.name(),
+                msg.path(),
+                l,
+            ),
+            (FieldCase::Vector, None) | (FieldCase::Array(_), None) if !is_last => bail!(
+                "Field `{}` of {} is an array, and needs a selection like `[0]` or `[*]`",
+                field.name(),
+                msg.path(),
+            ),
+            _ => {}
+        }
+        if is_last {
+            return Ok(match component.selection() {
+                Some(_) => field.datatype().qualified_name(msg.path()),
+                None => field.qualified_type(msg.path()),
+            });
+        }
+        msg = match field.datatype().message_path(msg.path()) {
+            Some(path) => match message.dependency(&path) {
+                Some(msg) => msg,
+                None => bail!("Definition of {} is missing", path),
+            },
+            None => bail!(
+                "Field `{}` of {} is not a message",
+                field.name(),
+                msg.path()
+            ),
+        };
+    }
+    bail!("Field path is empty")
+}
+
+fn fixed_size(datatype: &DataType) -> Option<usize> {
+    Some(match datatype {
+        DataType::Bool | DataType::I8(_) | DataType::U8(_) => 1,
+        DataType::I16 | DataType::U16 => 2,
+        DataType::I32 | DataType::U32 | DataType::F32 => 4,
+        DataType::I64 | DataType::U64 | DataType::F64 | DataType::Time | DataType::Duration => 8,
+        DataType::String | DataType::LocalMessage(_) | DataType::GlobalMessage(_) => return None,
+    })
+}
+
+fn skip_bytes(r: &mut impl io::Read, num_bytes: usize) -> io::Result<()> {
+    let skipped = io::copy(
+        &mut io::Read::take(&mut *r, num_bytes as u64),
+        &mut io::sink(),
+    )?;
+    if skipped != num_bytes as u64 {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "Message ended before the queried field",
+        ));
+    }
+    Ok(())
+}
@@ -2,27 +2,53 @@
 
 pub use crate::api::raii::{Publisher, Service, Subscriber};
 pub use crate::api::handlers::{SubscriptionHandler};
-pub use crate::api::{error, Clock, Parameter};
+pub use crate::api::{error, Clock, ManualClock, Parameter};
+pub use crate::logger::RosLogger;
 pub use crate::raw_message::{RawMessage, RawMessageDescription};
+pub use crate::rosmsg::DecodeLimits;
 #[doc(hidden)]
 pub use crate::rosmsg::RosMsg;
+pub use crate::shared_slice::{Primitive, SharedSlice};
 pub use crate::singleton::*;
 pub use crate::tcpros::{Client, ClientResponse, Message, ServicePair};
 pub use dynamic_msg::DynamicMsg;
+pub use field_query::FieldQuery;
+#[doc(hidden)]
+pub use lazy_static;
+pub use message_diff::{MessageChange, MessageDiff};
+pub use message_registry::{MessageRegistry, RegisteredType};
+pub use message_schema::MessageSchema;
+pub use message_validation::ValueMismatch;
 pub use ros_message::{Duration, MessageValue as MsgMessage, Time, Value as MsgValue};
+pub use rosrust_macros::RosMessage;
+#[doc(hidden)]
+pub use rosrust_macros::*;
+#[doc(hidden)]
+pub use serde;
 #[doc(hidden)]
-pub use rosrust_codegen::*;
+pub use serde_derive;
 pub mod wall_time;
 
 pub mod api;
+#[doc(hidden)]
+pub mod derive;
 mod dynamic_msg;
+mod field_query;
 mod log_macros;
+mod logger;
+mod message_diff;
+mod message_registry;
+mod message_schema;
+mod message_validation;
 #[doc(hidden)]
 pub mod msg;
 mod raw_message;
 #[doc(hidden)]
 pub mod rosmsg;
 mod rosxmlrpc;
+#[doc(hidden)]
+pub mod serde_helpers;
+mod shared_slice;
 pub mod singleton;
 mod tcpros;
 mod util;
@@ -1,8 +1,10 @@
 #[macro_export]
 macro_rules! ros_log {
     ($level:expr, $($arg:tt)+) => {
-        let msg = format!($($arg)*);
-        $crate::log($level, msg, file!(), line!());
+        if $crate::log_enabled($level, "") {
+            let msg = format!($($arg)*);
+            $crate::log($level, msg, file!(), line!());
+        }
     }
 }
 
@@ -41,11 +43,58 @@ macro_rules! ros_fatal {
     }
 }
 
+#[macro_export]
+macro_rules! ros_log_named {
+    ($level:expr, $name:expr, $($arg:tt)+) => {
+        if $crate::log_enabled($level, $name) {
+            let msg = format!($($arg)*);
+            $crate::log_named($level, $name, msg, file!(), line!());
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! ros_debug_named {
+    ($name:expr, $($arg:tt)*) => {
+        $crate::ros_log_named!($crate::msg::rosgraph_msgs::Log::DEBUG, $name, $($arg)*);
+    }
+}
+
+#[macro_export]
+macro_rules! ros_info_named {
+    ($name:expr, $($arg:tt)*) => {
+        $crate::ros_log_named!($crate::msg::rosgraph_msgs::Log::INFO, $name, $($arg)*);
+    }
+}
+
+#[macro_export]
+macro_rules! ros_warn_named {
+    ($name:expr, $($arg:tt)*) => {
+        $crate::ros_log_named!($crate::msg::rosgraph_msgs::Log::WARN, $name, $($arg)*);
+    }
+}
+
+#[macro_export]
+macro_rules! ros_err_named {
+    ($name:expr, $($arg:tt)*) => {
+        $crate::ros_log_named!($crate::msg::rosgraph_msgs::Log::ERROR, $name, $($arg)*);
+    }
+}
+
+#[macro_export]
+macro_rules! ros_fatal_named {
+    ($name:expr, $($arg:tt)*) => {
+        $crate::ros_log_named!($crate::msg::rosgraph_msgs::Log::FATAL, $name, $($arg)*);
+    }
+}
+
 #[macro_export]
 macro_rules! ros_log_once {
     ($level:expr, $($arg:tt)+) => {
-        let msg = format!($($arg)*);
-        $crate::log_once($level, msg, file!(), line!());
+        if $crate::log_enabled($level, "") {
+            let msg = format!($($arg)*);
+            $crate::log_once($level, msg, file!(), line!());
+        }
     }
 }
 
@@ -87,8 +136,10 @@ macro_rules! ros_fatal_once {
 #[macro_export]
 macro_rules! ros_log_throttle {
     ($period:expr, $level:expr, $($arg:tt)+) => {
-        let msg = format!($($arg)*);
-        $crate::log_throttle($period, $level, msg, file!(), line!());
+        if $crate::log_enabled($level, "") {
+            let msg = format!($($arg)*);
+            $crate::log_throttle($period, $level, msg, file!(), line!());
+        }
     }
 }
 
@@ -129,8 +180,10 @@ macro_rules! ros_fatal_throttle {
 #[macro_export]
 macro_rules! ros_log_throttle_identical {
     ($period:expr, $level:expr, $($arg:tt)+) => {
-        let msg = format!($($arg)*);
-        $crate::log_throttle_identical($period, $level, msg, file!(), line!());
+        if $crate::log_enabled($level, "") {
+            let msg = format!($($arg)*);
+            $crate::log_throttle_identical($period, $level, msg, file!(), line!());
+        }
     }
 }
 
@@ -0,0 +1,96 @@
+use crate::msg::rosgraph_msgs::Log;
+use crate::singleton::with_initialized;
+use log::{Level, LevelFilter, Metadata, Record, SetLoggerError};
+use std::cell::Cell;
+
+thread_local! {
+    static FORWARDING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Forwards records from the `log` crate facade to `/rosout`.
+///
+/// Records emitted before the node is initialized, or while it is being initialized,
+/// are printed to the terminal instead.
+pub struct RosLogger {
+    level: LevelFilter,
+    throttle_identical: Option<f64>,
+}
+
+impl Default for RosLogger {
+    fn default() -> Self {
+        Self {
+            level: LevelFilter::Info,
+            throttle_identical: None,
+        }
+    }
+}
+
+impl RosLogger {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the most verbose level that gets forwarded.
+    pub fn with_level(mut self, level: LevelFilter) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Drops identical records from the same source location for the given period in seconds.
+    pub fn with_throttle_identical(mut self, period: f64) -> Self {
+        self.throttle_identical = Some(period);
+        self
+    }
+
+    /// Installs the logger as the global `log` implementation.
+    pub fn install(self) -> Result<(), SetLoggerError> {
+        let level = self.level;
+        log::set_boxed_logger(Box::new(self))?;
+        log::set_max_level(level);
+        Ok(())
+    }
+}
+
+pub(crate) fn level_to_ros(level: Level) -> i8 {
+    match level {
+        Level::Error => Log::ERROR,
+        Level::Warn => Log::WARN,
+        Level::Info => Log::INFO,
+        Level::Debug | Level::Trace => Log::DEBUG,
+    }
+}
+
+impl log::Log for RosLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        // Logging inside of rosrust's own publishing would otherwise recurse forever
+        if FORWARDING.with(|forwarding| forwarding.replace(true)) {
+            return;
+        }
+        let level = level_to_ros(record.level());
+        let msg = if record.target().is_empty() {
+            record.args().to_string()
+        } else {
+            format!("[{}] {}", record.target(), record.args())
+        };
+        let file = record.file().unwrap_or("<unknown>");
+        let line = record.line().unwrap_or(0);
+        let forwarded = with_initialized(|ros| match self.throttle_identical {
+            Some(period) => ros.log_throttle_identical(period, level, msg.clone(), file, line),
+            None => ros.log(level, msg.clone(), file, line),
+        });
+        if forwarded.is_none() {
+            eprintln!("[{} @ {}:{}]: {}", record.level(), file, line, msg);
+        }
+        FORWARDING.with(|forwarding| forwarding.set(false));
+    }
+
+    fn flush(&self) {}
+}
@@ -0,0 +1,269 @@
+use crate::DynamicMsg;
+use ros_message::{FieldCase, FieldInfo, FieldPath, Msg, Selection};
+use std::fmt;
+
+/// Structural differences between two definitions of a message.
+///
+/// Changes describe how the other definition differs from the local one, so an added field
+/// only exists in the other definition.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MessageDiff {
+    changes: Vec<MessageChange>,
+}
+
+/// A single difference between two message definitions.
+///
+/// Paths into arrays of messages select all items, like `poses[*].position.x`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MessageChange {
+    /// A field of the given type only exists in the other definition.
+    FieldAdded {
+        path: FieldPath,
+        datatype: String,
+    },
+    /// A field of the given type only exists in the local definition.
+    FieldRemoved {
+        path: FieldPath,
+        datatype: String,
+    },
+    /// A field has the `expected` type locally and the `actual` type in the other definition.
+    FieldRetyped {
+        path: FieldPath,
+        expected: String,
+        actual: String,
+    },
+    /// Fields of the message at the path appear in a different order.
+    FieldsReordered {
+        path: FieldPath,
+    },
+    /// A constant, shown as its definition line, only exists in the other definition.
+    ConstantAdded {
+        path: FieldPath,
+        constant: String,
+    },
+    /// A constant, shown as its definition line, only exists in the local definition.
+    ConstantRemoved {
+        path: FieldPath,
+        constant: String,
+    },
+    /// A constant has a different type or value, shown as the `expected` local definition line
+    /// and the `actual` one in the other definition.
+    ConstantChanged {
+        path: FieldPath,
+        expected: String,
+        actual: String,
+    },
+}
+
+impl MessageDiff {
+    pub(crate) fn new(local: &DynamicMsg, remote: &DynamicMsg) -> Self {
+        let mut diff = Self::default();
+        diff.compare(
+            Side {
+                message: local,
+                msg: local.msg(),
+            },
+            Side {
+                message: remote,
+                msg: remote.msg(),
+            },
+            &FieldPath::default(),
+        );
+        diff
+    }
+
+    #[inline]
+    pub fn changes(&self) -> &[MessageChange] {
+        &self.changes
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    fn compare(&mut self, local: Side, remote: Side, prefix: &FieldPath) {
+        let path = |name: &str| prefix.field(name);
+
+        for constant in local.constants() {
+            match remote.constant(constant.name()) {
+                None => self.changes.push(MessageChange::ConstantRemoved {
+                    path: path(constant.name()),
+                    constant: constant.to_string(),
+                }),
+                Some(other)
+                    if other.datatype() != constant.datatype()
+                        || other.const_value() != constant.const_value() =>
+                {
+                    self.changes.push(MessageChange::ConstantChanged {
+                        path: path(constant.name()),
+                        expected: constant.to_string(),
+                        actual: other.to_string(),
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+        for constant in remote.constants() {
+            if local.constant(constant.name()).is_none() {
+                self.changes.push(MessageChange::ConstantAdded {
+                    path: path(constant.name()),
+                    constant: constant.to_string(),
+                });
+            }
+        }
+
+        for field in local.fields() {
+            let other = match remote.field(field.name()) {
+                Some(other) => other,
+                None => {
+                    self.changes.push(MessageChange::FieldRemoved {
+                        path: path(field.name()),
+                        datatype: field.qualified_type(local.msg.path()),
+                    });
+                    continue;
+                }
+            };
+            let (expected, actual) = (
+                field.qualified_type(local.msg.path()),
+                other.qualified_type(remote.msg.path()),
+            );
+            if expected != actual {
+                self.changes.push(MessageChange::FieldRetyped {
+                    path: path(field.name()),
+                    expected,
+                    actual,
+                });
+                continue;
+            }
+            // Nested messages can only be compared if both definitions include them
+            if let (Some(local_msg), Some(remote_msg)) = (local.nested(field), remote.nested(other))
+            {
+                let nested_prefix = match field.case() {
+                    FieldCase::Unit => path(field.name()),
+                    _ => path(field.name()).with_selection(Selection::All),
+                };
+                self.compare(
+                    Side {
+                        message: local.message,
+                        msg: local_msg,
+                    },
+                    Side {
+                        message: remote.message,
+                        msg: remote_msg,
+                    },
+                    &nested_prefix,
+                );
+            }
+        }
+        for field in remote.fields() {
+            if local.field(field.name()).is_none() {
+                self.changes.push(MessageChange::FieldAdded {
+                    path: path(field.name()),
+                    datatype: field.qualified_type(remote.msg.path()),
+                });
+            }
+        }
+
+        let local_order = local
+            .fields()
+            .filter(|v| remote.field(v.name()).is_some())
+            .map(FieldInfo::name);
+        let remote_order = remote
+            .fields()
+            .filter(|v| local.field(v.name()).is_some())
+            .map(FieldInfo::name);
+        if !local_order.eq(remote_order) {
+            self.changes.push(MessageChange::FieldsReordered {
+                path: prefix.clone(),
+            });
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Side<'a> {
+    message: &'a DynamicMsg,
+    msg: &'a Msg,
+}
+
+impl<'a> Side<'a> {
+    fn fields(self) -> impl Iterator<Item = &'a FieldInfo> {
+        self.msg.fields().iter().filter(|v| !v.is_constant())
+    }
+
+    fn constants(self) -> impl Iterator<Item = &'a FieldInfo> {
+        self.msg.fields().iter().filter(|v| v.is_constant())
+    }
+
+    fn field(self, name: &str) -> Option<&'a FieldInfo> {
+        self.fields().find(|v| v.name() == name)
+    }
+
+    fn constant(self, name: &str) -> Option<&'a FieldInfo> {
+        self.constants().find(|v| v.name() == name)
+    }
+
+    fn nested(self, field: &FieldInfo) -> Option<&'a Msg> {
+        let path = field.datatype().message_path(self.msg.path())?;
+        self.message.dependency(&path)
+    }
+}
+
+impl fmt::Display for MessageChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MessageChange::FieldAdded { path, datatype } => {
+                write!(f, "added field `{}` of type {}", path, datatype)
+            }
+            MessageChange::FieldRemoved { path, datatype } => {
+                write!(f, "removed field `{}` of type {}", path, datatype)
+            }
+            MessageChange::FieldRetyped {
+                path,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "field `{}` changed type from {} to {}",
+                path, expected, actual
+            ),
+            MessageChange::FieldsReordered { path } if path.components().is_empty() => {
+                write!(f, "fields are in a different order")
+            }
+            MessageChange::FieldsReordered { path } => {
+                write!(f, "fields of `{}` are in a different order", path)
+            }
+            MessageChange::ConstantAdded { path, constant } => {
+                write!(f, "added constant `{}`: {}", path, constant)
+            }
+            MessageChange::ConstantRemoved { path, constant } => {
+                write!(f, "removed constant `{}`: {}", path, constant)
+            }
+            MessageChange::ConstantChanged {
+                path,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "constant `{}` changed from `{}` to `{}`",
+                path, expected, actual
+            ),
+        }
+    }
+}
+
+impl fmt::Display for MessageDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.changes.is_empty() {
+            return write!(f, "no structural differences");
+        }
+        for (idx, change) in self.changes.iter().enumerate() {
+            if idx > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "  - {}", change)?;
+        }
+        Ok(())
+    }
+}
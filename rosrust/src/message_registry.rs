@@ -0,0 +1,181 @@
+use crate::{DynamicMsg, MessageSchema, RawMessage};
+use ros_message::MessageValue;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+
+/// Compiled message types, looked up by the type name and MD5 sum found in connection headers.
+///
+/// This lets relays and loggers that receive `RawMessage`s work with the compiled type
+/// of a message, instead of parsing its definition into a `DynamicMsg`.
+#[derive(Clone, Debug, Default)]
+pub struct MessageRegistry {
+    types: HashMap<(String, String), RegisteredType>,
+}
+
+impl MessageRegistry {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the type, replacing any type registered with the same name and MD5 sum.
+    pub fn register<T: MessageSchema>(&mut self) {
+        let registered = RegisteredType::new::<T>();
+        let key = (registered.msg_type.clone(), registered.md5sum.clone());
+        self.types.insert(key, registered);
+    }
+
+    /// Finds the type with the given name and MD5 sum, where an MD5 sum of `*` matches any.
+    ///
+    /// Since `*` cannot tell apart types registered under several MD5 sums, it finds nothing
+    /// unless exactly one MD5 sum is registered for the name.
+    pub fn get(&self, msg_type: &str, md5sum: &str) -> Option<&RegisteredType> {
+        if md5sum == "*" {
+            let mut found = self.types.values().filter(|v| v.msg_type == msg_type);
+            return match (found.next(), found.next()) {
+                (Some(registered), None) => Some(registered),
+                _ => None,
+            };
+        }
+        self.types.get(&(msg_type.into(), md5sum.into()))
+    }
+
+    /// Finds the type announced in the headers of a connection.
+    pub fn from_headers(&self, headers: &HashMap<String, String>) -> Option<&RegisteredType> {
+        self.get(headers.get("type")?, headers.get("md5sum")?)
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.types.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &RegisteredType> {
+        self.types.values()
+    }
+}
+
+/// Operations on a compiled message type, which work without naming its Rust type.
+///
+/// Encoded messages are passed without their length prefix, like in `RawMessage`.
+/// Decoded messages are boxed as `dyn Any`, and can be downcast to the compiled type.
+#[derive(Clone)]
+pub struct RegisteredType {
+    msg_type: String,
+    md5sum: String,
+    type_id: TypeId,
+    dynamic_msg: fn() -> &'static DynamicMsg,
+    decode: fn(&[u8]) -> io::Result<Box<dyn Any + Send + Sync>>,
+    decode_value: fn(&[u8]) -> io::Result<MessageValue>,
+    encode: fn(&dyn Any) -> io::Result<Vec<u8>>,
+    fmt_debug: fn(&dyn Any, &mut fmt::Formatter<'_>) -> fmt::Result,
+}
+
+impl RegisteredType {
+    fn new<T: MessageSchema>() -> Self {
+        Self {
+            msg_type: T::msg_type(),
+            md5sum: T::md5sum(),
+            type_id: TypeId::of::<T>(),
+            dynamic_msg: T::dynamic_msg,
+            decode: |bytes| Ok(Box::new(T::decode_from_slice(bytes)?)),
+            decode_value: |bytes| T::decode_from_slice(bytes).map(Into::into),
+            encode: |value| {
+                let value = downcast::<T>(value)?;
+                let mut output = Vec::with_capacity(value.encoded_len());
+                value.encode(&mut output)?;
+                Ok(output)
+            },
+            fmt_debug: |value, f| match value.downcast_ref::<T>() {
+                Some(value) => fmt::Debug::fmt(value, f),
+                None => Err(fmt::Error),
+            },
+        }
+    }
+
+    #[inline]
+    pub fn msg_type(&self) -> &str {
+        &self.msg_type
+    }
+
+    #[inline]
+    pub fn md5sum(&self) -> &str {
+        &self.md5sum
+    }
+
+    /// Whether the value is of this type.
+    #[inline]
+    pub fn matches(&self, value: &dyn Any) -> bool {
+        value.type_id() == self.type_id
+    }
+
+    /// Parsed definition of the type.
+    #[inline]
+    pub fn dynamic_msg(&self) -> &'static DynamicMsg {
+        (self.dynamic_msg)()
+    }
+
+    #[inline]
+    pub fn decode(&self, bytes: &[u8]) -> io::Result<Box<dyn Any + Send + Sync>> {
+        (self.decode)(bytes)
+    }
+
+    /// Decodes the message into the compiled type, and converts it to a value from there.
+    #[inline]
+    pub fn decode_value(&self, bytes: &[u8]) -> io::Result<MessageValue> {
+        (self.decode_value)(bytes)
+    }
+
+    /// Encodes a value of this type, which fails for values of any other type.
+    #[inline]
+    pub fn encode(&self, value: &dyn Any) -> io::Result<RawMessage> {
+        (self.encode)(value).map(RawMessage)
+    }
+
+    /// Formats a value of this type with its `Debug` implementation.
+    pub fn debug<'a>(&self, value: &'a dyn Any) -> Option<impl fmt::Debug + 'a> {
+        if !self.matches(value) {
+            return None;
+        }
+        Some(DebugAny {
+            value,
+            fmt_debug: self.fmt_debug,
+        })
+    }
+}
+
+impl fmt::Debug for RegisteredType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RegisteredType")
+            .field("msg_type", &self.msg_type)
+            .field("md5sum", &self.md5sum)
+            .finish()
+    }
+}
+
+fn downcast<T: Any>(value: &dyn Any) -> io::Result<&T> {
+    value.downcast_ref::<T>().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Value is not of type {}", std::any::type_name::<T>()),
+        )
+    })
+}
+
+struct DebugAny<'a> {
+    value: &'a dyn Any,
+    fmt_debug: fn(&dyn Any, &mut fmt::Formatter<'_>) -> fmt::Result,
+}
+
+impl fmt::Debug for DebugAny<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (self.fmt_debug)(self.value, f)
+    }
+}
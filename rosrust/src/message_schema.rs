@@ -0,0 +1,33 @@
+use crate::{DynamicMsg, Message};
+use ros_message::{MessagePath, MessageValue, Msg};
+use std::collections::HashMap;
+use std::convert::{TryFrom, TryInto};
+
+/// Parsed definition of a message type, for tools that work with any message at runtime.
+///
+/// Messages generated with the `SCHEMA` or `REGISTRY` flag parse their definition the
+/// first time it is needed and keep it for the rest of the program.
+pub trait MessageSchema: Message + Into<MessageValue> + TryFrom<MessageValue> {
+    /// Definition of the message together with its dependencies.
+    fn dynamic_msg() -> &'static DynamicMsg;
+
+    #[inline]
+    fn schema() -> &'static Msg {
+        Self::dynamic_msg().msg()
+    }
+
+    /// Definitions of all messages nested in this one, directly or not.
+    #[inline]
+    fn dependency_schemas() -> &'static HashMap<MessagePath, Msg> {
+        Self::dynamic_msg().dependencies()
+    }
+
+    /// Converts the message field by field, without encoding it.
+    fn to_value(&self) -> MessageValue;
+
+    /// Builds the message field by field, failing if any field is missing or has the wrong type.
+    #[inline]
+    fn from_value(value: MessageValue) -> Option<Self> {
+        value.try_into().ok()
+    }
+}
@@ -0,0 +1,392 @@
+use crate::DynamicMsg;
+use ros_message::{
+    DataType, Duration, FieldCase, FieldInfo, FieldPath, MessagePath, MessageValue, Msg, Selection,
+    Time, Value,
+};
+use std::fmt;
+use std::io;
+
+/// A way in which a `MessageValue` does not fit a message definition.
+///
+/// Paths into arrays select the index of the item, like `poses[1].position.x`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValueMismatch {
+    /// The value lacks a field of the `expected` type, which `fill_defaults` can add.
+    MissingField {
+        path: FieldPath,
+        expected: String,
+    },
+    /// The value has a field that the definition does not, which `encode` ignores.
+    UnknownField {
+        path: FieldPath,
+    },
+    /// The value holds an `actual` kind of value where the definition has the `expected` type.
+    WrongType {
+        path: FieldPath,
+        expected: String,
+        actual: String,
+    },
+    /// A fixed length array has a different number of items than the definition.
+    WrongLength {
+        path: FieldPath,
+        expected: usize,
+        actual: usize,
+    },
+    /// Definition of a nested message is not part of the `DynamicMsg`.
+    MissingDefinition {
+        path: FieldPath,
+        message: String,
+    },
+}
+
+impl ValueMismatch {
+    pub fn path(&self) -> &FieldPath {
+        match self {
+            ValueMismatch::MissingField { path, .. }
+            | ValueMismatch::UnknownField { path }
+            | ValueMismatch::WrongType { path, .. }
+            | ValueMismatch::WrongLength { path, .. }
+            | ValueMismatch::MissingDefinition { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for ValueMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValueMismatch::MissingField { path, expected } => {
+                write!(f, "{}: missing field of type {}", path, expected)
+            }
+            ValueMismatch::UnknownField { path } => {
+                write!(f, "{}: field is not part of the message", path)
+            }
+            ValueMismatch::WrongType {
+                path,
+                expected,
+                actual,
+            } => write!(f, "{}: expected {}, got {}", path, expected, actual),
+            ValueMismatch::WrongLength {
+                path,
+                expected,
+                actual,
+            } => write!(f, "{}: expected {} items, got {}", path, expected, actual),
+            ValueMismatch::MissingDefinition { path, message } => {
+                write!(f, "{}: definition of {} is missing", path, message)
+            }
+        }
+    }
+}
+
+impl DynamicMsg {
+    /// Builds a value with all fields set to zero, `false`, empty strings or empty arrays,
+    /// and fixed length arrays filled with as many such items as they need.
+    pub fn default_value(&self) -> io::Result<MessageValue> {
+        self.default_message(self.msg())
+    }
+
+    /// Adds default values for all fields missing from the value, including nested messages.
+    ///
+    /// This allows publishing values that only set some of the fields.
+    pub fn fill_defaults(&self, value: &mut MessageValue) -> io::Result<()> {
+        self.fill_message(self.msg(), value)
+    }
+
+    /// Checks the value against the message definition, returning every mismatch.
+    pub fn validate(&self, value: &MessageValue) -> Vec<ValueMismatch> {
+        let mut validator = Validator {
+            message: self,
+            mismatches: vec![],
+        };
+        validator.check_message(self.msg(), value, &FieldPath::default());
+        validator.mismatches
+    }
+
+    /// Replaces an encoding error with the list of problems in the value, if there are any.
+    pub(crate) fn explain_encode_error(&self, value: &MessageValue, err: io::Error) -> io::Error {
+        let mismatches = self
+            .validate(value)
+            .into_iter()
+            .filter(|v| !matches!(v, ValueMismatch::UnknownField { .. }))
+            .map(|v| format!("\n  - {}", v))
+            .collect::<String>();
+        if mismatches.is_empty() {
+            return err;
+        }
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Passed in dynamic data value does not match {}:{}",
+                self.msg().path(),
+                mismatches
+            ),
+        )
+    }
+
+    fn default_message(&self, msg: &Msg) -> io::Result<MessageValue> {
+        let mut output = MessageValue::new();
+        for field in msg.fields().iter().filter(|v| !v.is_constant()) {
+            output.insert(field.name().into(), self.default_field(msg.path(), field)?);
+        }
+        Ok(output)
+    }
+
+    fn default_field(&self, parent: &MessagePath, field: &FieldInfo) -> io::Result<Value> {
+        let len = match field.case() {
+            FieldCase::Unit | FieldCase::Const(_) => {
+                return self.default_item(parent, field.datatype())
+            }
+            FieldCase::Vector => 0,
+            FieldCase::Array(l) => *l,
+        };
+        Ok(match field.datatype() {
+            DataType::U8(_) => Value::Bytes(vec![0; len]),
+            DataType::I8(_) => Value::I8Array(vec![0; len]),
+            DataType::I16 => Value::I16Array(vec![0; len]),
+            DataType::I32 => Value::I32Array(vec![0; len]),
+            DataType::I64 => Value::I64Array(vec![0; len]),
+            DataType::U16 => Value::U16Array(vec![0; len]),
+            DataType::U32 => Value::U32Array(vec![0; len]),
+            DataType::U64 => Value::U64Array(vec![0; len]),
+            DataType::F32 => Value::F32Array(vec![0.0; len]),
+            DataType::F64 => Value::F64Array(vec![0.0; len]),
+            DataType::Bool
+            | DataType::String
+            | DataType::Time
+            | DataType::Duration
+            | DataType::LocalMessage(_)
+            | DataType::GlobalMessage(_) => Value::Array(
+                (0..len)
+                    .map(|_| self.default_item(parent, field.datatype()))
+                    .collect::<io::Result<_>>()?,
+            ),
+        })
+    }
+
+    fn default_item(&self, parent: &MessagePath, datatype: &DataType) -> io::Result<Value> {
+        Ok(match datatype {
+            DataType::Bool => false.into(),
+            DataType::I8(_) => 0i8.into(),
+            DataType::I16 => 0i16.into(),
+            DataType::I32 => 0i32.into(),
+            DataType::I64 => 0i64.into(),
+            DataType::U8(_) => 0u8.into(),
+            DataType::U16 => 0u16.into(),
+            DataType::U32 => 0u32.into(),
+            DataType::U64 => 0u64.into(),
+            DataType::F32 => 0f32.into(),
+            DataType::F64 => 0f64.into(),
+            DataType::String => String::new().into(),
+            DataType::Time => Time::default().into(),
+            DataType::Duration => Duration::default().into(),
+            DataType::LocalMessage(name) => {
+                let dependency = self.get_dependency(&parent.peer(name))?;
+                self.default_message(dependency)?.into()
+            }
+            DataType::GlobalMessage(path) => {
+                let dependency = self.get_dependency(path)?;
+                self.default_message(dependency)?.into()
+            }
+        })
+    }
+
+    fn fill_message(&self, msg: &Msg, value: &mut MessageValue) -> io::Result<()> {
+        for field in msg.fields().iter().filter(|v| !v.is_constant()) {
+            let item = match value.get_mut(field.name()) {
+                Some(item) => item,
+                None => {
+                    let item = self.default_field(msg.path(), field)?;
+                    value.insert(field.name().into(), item);
+                    continue;
+                }
+            };
+            let dependency = match field.datatype().message_path(msg.path()) {
+                Some(path) => self.get_dependency(&path)?,
+                None => continue,
+            };
+            match item {
+                Value::Message(v) => self.fill_message(dependency, v)?,
+                Value::Array(items) => {
+                    for item in items {
+                        if let Value::Message(v) = item {
+                            self.fill_message(dependency, v)?;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Validator<'a> {
+    message: &'a DynamicMsg,
+    mismatches: Vec<ValueMismatch>,
+}
+
+impl<'a> Validator<'a> {
+    fn check_message(&mut self, msg: &Msg, value: &MessageValue, path: &FieldPath) {
+        let fields = msg.fields().iter().filter(|v| !v.is_constant());
+        for field in fields.clone() {
+            match value.get(field.name()) {
+                Some(item) => self.check_field(msg.path(), field, item, path.field(field.name())),
+                None => self.mismatches.push(ValueMismatch::MissingField {
+                    path: path.field(field.name()),
+                    expected: field.qualified_type(msg.path()),
+                }),
+            }
+        }
+        let mut unknown = value
+            .keys()
+            .filter(|name| !fields.clone().any(|v| v.name() == name.as_str()))
+            .collect::<Vec<_>>();
+        unknown.sort();
+        for name in unknown {
+            self.mismatches.push(ValueMismatch::UnknownField {
+                path: path.field(name.as_str()),
+            });
+        }
+    }
+
+    fn check_field(
+        &mut self,
+        parent: &MessagePath,
+        field: &FieldInfo,
+        value: &Value,
+        path: FieldPath,
+    ) {
+        let array_length = match field.case() {
+            FieldCase::Unit | FieldCase::Const(_) => {
+                return self.check_item(parent, field.datatype(), value, path)
+            }
+            FieldCase::Vector => None,
+            FieldCase::Array(l) => Some(*l),
+        };
+        let len = match value.array_len() {
+            Some(len) => len,
+            None => {
+                self.mismatches.push(ValueMismatch::WrongType {
+                    path,
+                    expected: field.qualified_type(parent),
+                    actual: value_type(value),
+                });
+                return;
+            }
+        };
+        if let Some(expected) = array_length.filter(|&v| v != len) {
+            self.mismatches.push(ValueMismatch::WrongLength {
+                path: path.clone(),
+                expected,
+                actual: len,
+            });
+        }
+        match value {
+            Value::Array(items) => {
+                for (idx, item) in items.iter().enumerate() {
+                    let item_path = path.with_selection(Selection::Index(idx));
+                    self.check_item(parent, field.datatype(), item, item_path);
+                }
+            }
+            _ if typed_array_matches(field.datatype(), value) => {}
+            _ => self.mismatches.push(ValueMismatch::WrongType {
+                path,
+                expected: field.qualified_type(parent),
+                actual: value_type(value),
+            }),
+        }
+    }
+
+    fn check_item(
+        &mut self,
+        parent: &MessagePath,
+        datatype: &DataType,
+        value: &Value,
+        path: FieldPath,
+    ) {
+        let nested = match (datatype, value) {
+            (DataType::Bool, Value::Bool(_))
+            | (DataType::I8(_), Value::I8(_))
+            | (DataType::I16, Value::I16(_))
+            | (DataType::I32, Value::I32(_))
+            | (DataType::I64, Value::I64(_))
+            | (DataType::U8(_), Value::U8(_))
+            | (DataType::U16, Value::U16(_))
+            | (DataType::U32, Value::U32(_))
+            | (DataType::U64, Value::U64(_))
+            | (DataType::F32, Value::F32(_))
+            | (DataType::F64, Value::F64(_))
+            | (DataType::String, Value::String(_))
+            | (DataType::Time, Value::Time(_))
+            | (DataType::Duration, Value::Duration(_)) => return,
+            (DataType::LocalMessage(_), Value::Message(v))
+            | (DataType::GlobalMessage(_), Value::Message(v)) => v,
+            _ => {
+                self.mismatches.push(ValueMismatch::WrongType {
+                    path,
+                    expected: datatype.qualified_name(parent),
+                    actual: value_type(value),
+                });
+                return;
+            }
+        };
+        let message_path = match datatype.message_path(parent) {
+            Some(v) => v,
+            None => return,
+        };
+        match self.message.dependency(&message_path) {
+            Some(msg) => self.check_message(msg, nested, &path),
+            None => self.mismatches.push(ValueMismatch::MissingDefinition {
+                path,
+                message: message_path.to_string(),
+            }),
+        }
+    }
+}
+
+fn typed_array_matches(datatype: &DataType, value: &Value) -> bool {
+    matches!(
+        (datatype, value),
+        (DataType::U8(_), Value::Bytes(_))
+            | (DataType::I8(_), Value::I8Array(_))
+            | (DataType::I16, Value::I16Array(_))
+            | (DataType::I32, Value::I32Array(_))
+            | (DataType::I64, Value::I64Array(_))
+            | (DataType::U16, Value::U16Array(_))
+            | (DataType::U32, Value::U32Array(_))
+            | (DataType::U64, Value::U64Array(_))
+            | (DataType::F32, Value::F32Array(_))
+            | (DataType::F64, Value::F64Array(_))
+    )
+}
+
+fn value_type(value: &Value) -> String {
+    match value {
+        Value::Bool(_) => "bool",
+        Value::I8(_) => "int8",
+        Value::I16(_) => "int16",
+        Value::I32(_) => "int32",
+        Value::I64(_) => "int64",
+        Value::U8(_) => "uint8",
+        Value::U16(_) => "uint16",
+        Value::U32(_) => "uint32",
+        Value::U64(_) => "uint64",
+        Value::F32(_) => "float32",
+        Value::F64(_) => "float64",
+        Value::String(_) => "string",
+        Value::Time(_) => "time",
+        Value::Duration(_) => "duration",
+        Value::Array(_) => "array",
+        Value::Message(_) => "message",
+        Value::Bytes(_) => "uint8[]",
+        Value::I8Array(_) => "int8[]",
+        Value::I16Array(_) => "int16[]",
+        Value::I32Array(_) => "int32[]",
+        Value::I64Array(_) => "int64[]",
+        Value::U16Array(_) => "uint16[]",
+        Value::U32Array(_) => "uint32[]",
+        Value::U64Array(_) => "uint64[]",
+        Value::F32Array(_) => "float32[]",
+        Value::F64Array(_) => "float64[]",
+    }
+    .into()
+}
@@ -1,36 +1,406 @@
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use ros_message::{Duration, Time};
+use std::cell::Cell;
 use std::collections::HashMap;
 use std::io;
+use std::sync::Arc;
+
+/// Largest sizes accepted when decoding data received from other nodes.
+///
+/// Length prefixes are checked against these, and against the data left in the message,
+/// before anything gets allocated, so corrupt or malicious data fails to decode instead of
+/// exhausting memory. Received messages are only allocated as their data arrives.
+///
+/// By default messages can be up to 256 MiB, strings up to 64 MiB, and arrays up to
+/// 2^28 items long.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeLimits {
+    /// Largest message in bytes, which also applies to connection headers and service calls.
+    pub max_message_size: usize,
+    /// Longest string in bytes.
+    pub max_string_length: usize,
+    /// Most items in a variable length array.
+    pub max_array_length: usize,
+}
+
+const DEFAULT_DECODE_LIMITS: DecodeLimits = DecodeLimits {
+    max_message_size: 1 << 28,
+    max_string_length: 1 << 26,
+    max_array_length: 1 << 28,
+};
+
+thread_local! {
+    static DECODE_LIMITS: Cell<DecodeLimits> = const { Cell::new(DEFAULT_DECODE_LIMITS) };
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        DEFAULT_DECODE_LIMITS
+    }
+}
+
+impl DecodeLimits {
+    /// Limits applied to decoding on the current thread.
+    #[inline]
+    pub fn current() -> Self {
+        DECODE_LIMITS.with(Cell::get)
+    }
+
+    /// Applies the limits to all decoding on the current thread while the closure runs.
+    pub fn apply<T>(self, f: impl FnOnce() -> T) -> T {
+        struct Restore(DecodeLimits);
+
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                DECODE_LIMITS.with(|limits| limits.set(self.0));
+            }
+        }
+
+        let _restore = Restore(DECODE_LIMITS.with(|limits| limits.replace(self)));
+        f()
+    }
+
+    /// Fails if a message of the given size in bytes exceeds the limit.
+    pub fn check_message_size(&self, size: usize) -> io::Result<()> {
+        check_limit("Message", size, self.max_message_size, "bytes")
+    }
+}
+
+/// Length prefix that exceeds a decode limit or the data left in the message.
+#[derive(Debug)]
+struct LimitExceeded(String);
+
+impl std::fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
+
+/// Whether decoding failed because a length prefix exceeded a limit or the rest of the message.
+///
+/// Such data is corrupt or malicious, so connections that send it get dropped.
+pub fn is_limit_error(err: &io::Error) -> bool {
+    err.get_ref()
+        .is_some_and(|inner| inner.is::<LimitExceeded>())
+}
+
+fn limit_error(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, LimitExceeded(message))
+}
+
+fn check_limit(kind: &str, length: usize, limit: usize, unit: &str) -> io::Result<()> {
+    if length <= limit {
+        return Ok(());
+    }
+    Err(limit_error(format!(
+        "{} of {} {} exceeds the limit of {} {}",
+        kind, length, unit, limit, unit
+    )))
+}
+
+/// Fails if the data announced by a length prefix is longer than the rest of the message.
+fn check_remaining(
+    remaining: usize,
+    kind: &str,
+    length: usize,
+    item_size: usize,
+) -> io::Result<usize> {
+    match length.checked_mul(item_size) {
+        Some(num_bytes) if num_bytes <= remaining => Ok(num_bytes),
+        _ => Err(limit_error(format!(
+            "{} of length {} needs more than the {} bytes left in the message",
+            kind, length, remaining
+        ))),
+    }
+}
+
+/// Reads the length prefix of a variable length array, checked against the current limits.
+#[inline]
+pub fn decode_array_length<R: io::Read>(r: R) -> io::Result<usize> {
+    let len = u32::decode(r)? as usize;
+    let limit = DecodeLimits::current().max_array_length;
+    check_limit("Array", len, limit, "items")?;
+    Ok(len)
+}
+
+#[inline]
+fn decode_string_length<R: io::Read>(r: R) -> io::Result<usize> {
+    let len = u32::decode(r)? as usize;
+    let limit = DecodeLimits::current().max_string_length;
+    check_limit("String", len, limit, "bytes")?;
+    Ok(len)
+}
 
 pub trait RosMsg: std::marker::Sized {
     fn encode<W: io::Write>(&self, w: W) -> io::Result<()>;
     fn decode<R: io::Read>(r: R) -> io::Result<Self>;
 
+    /// Number of bytes that `encode` writes.
     #[inline]
-    fn encode_vec(&self) -> io::Result<Vec<u8>> {
-        let mut writer = io::Cursor::new(Vec::with_capacity(128));
-        // skip the first 4 bytes that will contain the message length
-        writer.set_position(4);
+    fn encoded_len(&self) -> usize {
+        let mut counter = ByteCounter(0);
+        match self.encode(&mut counter) {
+            Ok(()) => counter.0,
+            Err(_) => 0,
+        }
+    }
+
+    /// Encodes into the start of the buffer, returning the number of written bytes.
+    ///
+    /// Fails if the buffer is shorter than `encoded_len`.
+    #[inline]
+    fn encode_to_slice(&self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut w = SliceWriter::new(buf);
+        self.encode_into(&mut w)?;
+        Ok(w.position())
+    }
 
-        self.encode(&mut writer)?;
+    /// Decodes from the start of the buffer, which has no length prefix.
+    #[inline]
+    fn decode_from_slice(bytes: &[u8]) -> io::Result<Self> {
+        Self::decode_from(&mut SliceReader::new(bytes))
+    }
 
-        // write the message length to the start of the header
-        let message_length = (writer.position() - 4) as u32;
-        writer.set_position(0);
-        message_length.encode(&mut writer)?;
-        Ok(writer.into_inner())
+    /// Encodes at the position of the slice writer, which generated messages do
+    /// field by field without going through `encode`.
+    #[inline]
+    fn encode_into(&self, w: &mut SliceWriter) -> io::Result<()> {
+        self.encode(w)
+    }
+
+    /// Decodes at the position of the slice reader, which generated messages do
+    /// field by field without going through `decode`.
+    #[inline]
+    fn decode_from(r: &mut SliceReader) -> io::Result<Self> {
+        Self::decode(r)
+    }
+
+    #[inline]
+    fn encode_vec(&self) -> io::Result<Vec<u8>> {
+        // The buffer is sized exactly, so it never gets reallocated while encoding
+        let message_length = self.encoded_len();
+        let mut output = Vec::with_capacity(message_length + 4);
+        (message_length as u32).encode(&mut output)?;
+        self.encode(&mut output)?;
+        if output.len() != message_length + 4 {
+            let message_length = (output.len() - 4) as u32;
+            output[..4].copy_from_slice(&message_length.to_le_bytes());
+        }
+        Ok(output)
     }
 
     #[inline]
     fn decode_slice(bytes: &[u8]) -> io::Result<Self> {
-        let mut reader = io::Cursor::new(bytes);
+        // skip the first 4 bytes that contain the message length
+        Self::decode_from_slice(bytes.get(4..).unwrap_or_default())
+    }
+
+    /// Decodes from a received frame, which primitive arrays can share instead of copying.
+    #[inline]
+    fn decode_frame(r: &mut FrameReader) -> io::Result<Self> {
+        Self::decode(r)
+    }
+
+    #[inline]
+    fn decode_shared(frame: &Arc<Vec<u8>>) -> io::Result<Self> {
+        let mut reader = FrameReader::new(Arc::clone(frame));
         // skip the first 4 bytes that contain the message length
         reader.set_position(4);
-        Self::decode(&mut reader)
+        Self::decode_frame(&mut reader)
+    }
+}
+
+/// Writer that only counts the bytes written to it.
+struct ByteCounter(usize);
+
+impl io::Write for ByteCounter {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Reader over a complete received message, shared by all values decoded from it.
+pub struct FrameReader {
+    frame: Arc<Vec<u8>>,
+    position: usize,
+}
+
+impl FrameReader {
+    #[inline]
+    pub fn new(frame: Arc<Vec<u8>>) -> Self {
+        Self { frame, position: 0 }
+    }
+
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    #[inline]
+    pub fn set_position(&mut self, position: usize) {
+        self.position = position;
+    }
+
+    /// Number of bytes left after the current position.
+    #[inline]
+    pub fn remaining(&self) -> usize {
+        self.frame.len().saturating_sub(self.position)
+    }
+
+    /// Skips the given number of bytes, returning the frame and the offset they start at.
+    pub fn take_shared(&mut self, num_bytes: usize) -> io::Result<(Arc<Vec<u8>>, usize)> {
+        let end = self
+            .position
+            .checked_add(num_bytes)
+            .filter(|&end| end <= self.frame.len())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "Frame is shorter than the encoded data",
+                )
+            })?;
+        let offset = self.position;
+        self.position = end;
+        Ok((Arc::clone(&self.frame), offset))
+    }
+}
+
+impl io::Read for FrameReader {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.frame.get(self.position..).unwrap_or_default();
+        let count = remaining.len().min(buf.len());
+        buf[..count].copy_from_slice(&remaining[..count]);
+        self.position += count;
+        Ok(count)
+    }
+}
+
+/// Writer into a byte slice, which fails instead of writing past its end.
+pub struct SliceWriter<'a> {
+    buf: &'a mut [u8],
+    position: usize,
+}
+
+impl<'a> SliceWriter<'a> {
+    #[inline]
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        Self { buf, position: 0 }
+    }
+
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Copies the bytes to the current position.
+    #[inline]
+    pub fn put(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let end = self.position + bytes.len();
+        let target = self.buf.get_mut(self.position..end).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::WriteZero,
+                "Buffer is shorter than the encoded data",
+            )
+        })?;
+        target.copy_from_slice(bytes);
+        self.position = end;
+        Ok(())
+    }
+}
+
+impl<'a> io::Write for SliceWriter<'a> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let count = buf.len().min(self.buf.len() - self.position);
+        self.put(&buf[..count])?;
+        Ok(count)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
     }
 }
 
+/// Reader over a byte slice, which hands out the bytes it skips without copying them.
+pub struct SliceReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> SliceReader<'a> {
+    #[inline]
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Number of bytes left after the current position.
+    #[inline]
+    pub fn remaining(&self) -> usize {
+        self.bytes.len() - self.position
+    }
+
+    /// Skips the given number of bytes, returning them.
+    #[inline]
+    pub fn take(&mut self, num_bytes: usize) -> io::Result<&'a [u8]> {
+        if num_bytes > self.remaining() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Buffer is shorter than the encoded data",
+            ));
+        }
+        let bytes = &self.bytes[self.position..self.position + num_bytes];
+        self.position += num_bytes;
+        Ok(bytes)
+    }
+
+    #[inline]
+    fn take_array<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        let mut output = [0; N];
+        output.copy_from_slice(self.take(N)?);
+        Ok(output)
+    }
+}
+
+impl<'a> io::Read for SliceReader<'a> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = self.remaining().min(buf.len());
+        buf[..count].copy_from_slice(self.take(count)?);
+        Ok(count)
+    }
+}
+
+/// Encodes and decodes numbers in slices by copying their little endian bytes.
+macro_rules! slice_number_methods {
+    () => {
+        #[inline]
+        fn encode_into(&self, w: &mut SliceWriter) -> io::Result<()> {
+            w.put(&self.to_le_bytes())
+        }
+
+        #[inline]
+        fn decode_from(r: &mut SliceReader) -> io::Result<Self> {
+            r.take_array().map(Self::from_le_bytes)
+        }
+    };
+}
+
 impl RosMsg for bool {
     #[inline]
     fn encode<W: io::Write>(&self, mut w: W) -> io::Result<()> {
@@ -41,6 +411,21 @@ impl RosMsg for bool {
     fn decode<R: io::Read>(mut r: R) -> io::Result<Self> {
         r.read_u8().map(|u| u > 0)
     }
+
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        1
+    }
+
+    #[inline]
+    fn encode_into(&self, w: &mut SliceWriter) -> io::Result<()> {
+        w.put(&[*self as u8])
+    }
+
+    #[inline]
+    fn decode_from(r: &mut SliceReader) -> io::Result<Self> {
+        r.take_array::<1>().map(|[u]| u > 0)
+    }
 }
 
 impl RosMsg for u8 {
@@ -53,6 +438,13 @@ impl RosMsg for u8 {
     fn decode<R: io::Read>(mut r: R) -> io::Result<Self> {
         r.read_u8()
     }
+
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        1
+    }
+
+    slice_number_methods!();
 }
 
 impl RosMsg for i8 {
@@ -65,6 +457,13 @@ impl RosMsg for i8 {
     fn decode<R: io::Read>(mut r: R) -> io::Result<Self> {
         r.read_i8()
     }
+
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        1
+    }
+
+    slice_number_methods!();
 }
 
 impl RosMsg for u16 {
@@ -77,6 +476,13 @@ impl RosMsg for u16 {
     fn decode<R: io::Read>(mut r: R) -> io::Result<Self> {
         r.read_u16::<LittleEndian>()
     }
+
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        2
+    }
+
+    slice_number_methods!();
 }
 
 impl RosMsg for i16 {
@@ -89,6 +495,13 @@ impl RosMsg for i16 {
     fn decode<R: io::Read>(mut r: R) -> io::Result<Self> {
         r.read_i16::<LittleEndian>()
     }
+
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        2
+    }
+
+    slice_number_methods!();
 }
 
 impl RosMsg for u32 {
@@ -101,6 +514,13 @@ impl RosMsg for u32 {
     fn decode<R: io::Read>(mut r: R) -> io::Result<Self> {
         r.read_u32::<LittleEndian>()
     }
+
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        4
+    }
+
+    slice_number_methods!();
 }
 
 impl RosMsg for i32 {
@@ -113,6 +533,13 @@ impl RosMsg for i32 {
     fn decode<R: io::Read>(mut r: R) -> io::Result<Self> {
         r.read_i32::<LittleEndian>()
     }
+
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        4
+    }
+
+    slice_number_methods!();
 }
 
 impl RosMsg for u64 {
@@ -125,6 +552,13 @@ impl RosMsg for u64 {
     fn decode<R: io::Read>(mut r: R) -> io::Result<Self> {
         r.read_u64::<LittleEndian>()
     }
+
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        8
+    }
+
+    slice_number_methods!();
 }
 
 impl RosMsg for i64 {
@@ -137,6 +571,13 @@ impl RosMsg for i64 {
     fn decode<R: io::Read>(mut r: R) -> io::Result<Self> {
         r.read_i64::<LittleEndian>()
     }
+
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        8
+    }
+
+    slice_number_methods!();
 }
 
 impl RosMsg for f32 {
@@ -149,6 +590,13 @@ impl RosMsg for f32 {
     fn decode<R: io::Read>(mut r: R) -> io::Result<Self> {
         r.read_f32::<LittleEndian>()
     }
+
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        4
+    }
+
+    slice_number_methods!();
 }
 
 impl RosMsg for f64 {
@@ -161,6 +609,13 @@ impl RosMsg for f64 {
     fn decode<R: io::Read>(mut r: R) -> io::Result<Self> {
         r.read_f64::<LittleEndian>()
     }
+
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        8
+    }
+
+    slice_number_methods!();
 }
 
 #[inline]
@@ -173,6 +628,41 @@ pub fn decode_fixed_vec<R: io::Read, T: RosMsg>(len: u32, mut r: R) -> io::Resul
     (0..len).map(move |_| T::decode(r.by_ref())).collect()
 }
 
+#[inline]
+pub fn encoded_len_fixed_slice<T: RosMsg>(data: &[T]) -> usize {
+    data.iter().map(RosMsg::encoded_len).sum()
+}
+
+#[inline]
+pub fn encoded_len_variable_slice<T: RosMsg>(data: &[T]) -> usize {
+    4 + encoded_len_fixed_slice(data)
+}
+
+/// Decodes an array of strings or messages from a frame.
+///
+/// The items grow the vector as they get decoded, so its memory never exceeds what the
+/// frame actually holds.
+#[inline]
+pub fn decode_frame_vec<T: RosMsg>(r: &mut FrameReader) -> io::Result<Vec<T>> {
+    let len = decode_array_length(&mut *r)?;
+    (0..len).map(|_| T::decode_frame(r)).collect()
+}
+
+/// Decodes an array of numbers from a frame, checking its length against the rest of the frame.
+#[inline]
+pub fn decode_frame_primitive_vec<T: RosMsg>(r: &mut FrameReader) -> io::Result<Vec<T>> {
+    let len = decode_frame_array_length(r, std::mem::size_of::<T>())?;
+    decode_primitive_items(len, r)
+}
+
+/// Reads the length prefix of an array in a frame, which has to fit into the rest of the frame.
+#[inline]
+pub fn decode_frame_array_length(r: &mut FrameReader, item_size: usize) -> io::Result<usize> {
+    let len = decode_array_length(&mut *r)?;
+    check_remaining(r.remaining(), "Array", len, item_size)?;
+    Ok(len)
+}
+
 #[inline]
 pub fn encode_variable_slice<W: io::Write, T: RosMsg>(data: &[T], mut w: W) -> io::Result<()> {
     (data.len() as u32).encode(w.by_ref())?;
@@ -181,7 +671,59 @@ pub fn encode_variable_slice<W: io::Write, T: RosMsg>(data: &[T], mut w: W) -> i
 
 #[inline]
 pub fn decode_variable_vec<R: io::Read, T: RosMsg>(mut r: R) -> io::Result<Vec<T>> {
-    decode_fixed_vec(u32::decode(r.by_ref())?, r)
+    let len = decode_array_length(r.by_ref())?;
+    decode_fixed_vec(len as u32, r)
+}
+
+#[inline]
+pub fn encode_fixed_slice_into<T: RosMsg>(data: &[T], w: &mut SliceWriter) -> io::Result<()> {
+    data.iter().try_for_each(|v| v.encode_into(w))
+}
+
+#[inline]
+pub fn encode_variable_slice_into<T: RosMsg>(data: &[T], w: &mut SliceWriter) -> io::Result<()> {
+    (data.len() as u32).encode_into(w)?;
+    encode_fixed_slice_into(data, w)
+}
+
+/// Encodes an array of numbers into a slice, which copies the data directly when platform
+/// endiannes matches wire endiannes (little).
+#[inline]
+pub fn encode_variable_primitive_slice_into<T: RosMsg>(
+    data: &[T],
+    w: &mut SliceWriter,
+) -> io::Result<()> {
+    if cfg!(target_endian = "big") {
+        return encode_variable_slice_into(data, w);
+    }
+    (data.len() as u32).encode_into(w)?;
+    let ptr = data.as_ptr() as *const u8;
+    w.put(unsafe { std::slice::from_raw_parts(ptr, std::mem::size_of_val(data)) })
+}
+
+/// Decodes an array of strings or messages from a slice.
+#[inline]
+pub fn decode_slice_vec<T: RosMsg>(r: &mut SliceReader) -> io::Result<Vec<T>> {
+    let len = decode_array_length(&mut *r)?;
+    (0..len).map(|_| T::decode_from(r)).collect()
+}
+
+/// Decodes an array of numbers from a slice, checking its length against the rest of the slice.
+#[inline]
+pub fn decode_slice_primitive_vec<T: RosMsg>(r: &mut SliceReader) -> io::Result<Vec<T>> {
+    let len = decode_array_length(&mut *r)?;
+    let num_bytes = check_remaining(r.remaining(), "Array", len, std::mem::size_of::<T>())?;
+    if cfg!(target_endian = "big") {
+        return (0..len).map(|_| T::decode_from(r)).collect();
+    }
+    let bytes = r.take(num_bytes)?;
+    let mut buf = Vec::<T>::with_capacity(len);
+    // Because both wire and system are little endian, the data gets copied directly
+    unsafe {
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), buf.as_mut_ptr() as *mut u8, num_bytes);
+        buf.set_len(len);
+    }
+    Ok(buf)
 }
 
 /// Fast vector encoding when platform endiannes matches wire
@@ -197,8 +739,8 @@ pub fn encode_variable_primitive_slice<W: io::Write, T: RosMsg>(
 
     // Because both wire and system are little endian, we simply copy
     // the in-memory slice to the buffer directly.
-    w.write(unsafe { std::slice::from_raw_parts(ptr, data.len() * std::mem::size_of::<T>()) })
-        .map(|_| ())
+    // A partial write, as into a short slice, has to fail instead of truncating the data.
+    w.write_all(unsafe { std::slice::from_raw_parts(ptr, data.len() * std::mem::size_of::<T>()) })
 }
 
 #[inline]
@@ -210,35 +752,50 @@ pub fn encode_variable_primitive_slice<W: io::Write, T: RosMsg>(
     encode_variable_slice(data, w)
 }
 
-/// Fast vector decoding when platform endiannes matches wire
-/// endiannes (little).
+/// Fast vector decoding, which copies the data directly when platform endiannes
+/// matches wire endiannes (little).
 #[inline]
-#[cfg(target_endian = "little")]
 pub fn decode_variable_primitive_vec<R: io::Read, T: RosMsg>(mut r: R) -> io::Result<Vec<T>> {
-    let num_elements = u32::decode(r.by_ref())? as usize;
-    let num_bytes = num_elements * std::mem::size_of::<T>();
-
-    // Allocate the memory w/o initializing because we will later fill
-    // all the memory.
-    let mut buf = Vec::<T>::with_capacity(num_elements);
-
-    let buf_ptr = buf.as_mut_ptr();
-
-    // Fill the Vec to full capacity with the stream data.
-    let read_buf = unsafe { std::slice::from_raw_parts_mut(buf_ptr as *mut u8, num_bytes) };
-    r.read_exact(read_buf)?;
+    let num_elements = decode_array_length(r.by_ref())?;
+    decode_primitive_items(num_elements, r)
+}
 
-    // Do not drop the memory
-    std::mem::forget(buf);
+/// Number of bytes allocated before any data arrives, which doubles with every read chunk.
+///
+/// This keeps a length prefix that is larger than the actual data from allocating
+/// more than twice the memory that was received.
+const INITIAL_CHUNK_BYTES: usize = 64 * 1024;
 
-    // Return a new, completely full Vec using the now initialized memory.
-    Ok(unsafe { Vec::from_raw_parts(buf_ptr, num_elements, num_elements) })
+#[cfg(target_endian = "little")]
+fn decode_primitive_items<R: io::Read, T: RosMsg>(
+    num_elements: usize,
+    mut r: R,
+) -> io::Result<Vec<T>> {
+    let item_size = std::mem::size_of::<T>();
+    let mut buf = Vec::<T>::new();
+    while buf.len() < num_elements {
+        let chunk = (INITIAL_CHUNK_BYTES / item_size.max(1))
+            .max(buf.len())
+            .min(num_elements - buf.len());
+        buf.reserve_exact(chunk);
+
+        // Because both wire and system are little endian, the stream data gets copied
+        // into the memory after the initialized items. That memory gets zeroed first,
+        // since readers may look at the buffer they are given.
+        let read_buf = unsafe {
+            let start = buf.as_mut_ptr().add(buf.len()) as *mut u8;
+            std::ptr::write_bytes(start, 0, chunk * item_size);
+            std::slice::from_raw_parts_mut(start, chunk * item_size)
+        };
+        r.read_exact(read_buf)?;
+        unsafe { buf.set_len(buf.len() + chunk) };
+    }
+    Ok(buf)
 }
 
-#[inline]
 #[cfg(target_endian = "big")]
-pub fn decode_variable_primitive_vec<R: io::Read, T: RosMsg>(mut r: R) -> io::Result<Vec<T>> {
-    decode_variable_vec(r)
+fn decode_primitive_items<R: io::Read, T: RosMsg>(num_elements: usize, r: R) -> io::Result<Vec<T>> {
+    decode_fixed_vec(num_elements as u32, r)
 }
 
 #[inline]
@@ -253,13 +810,44 @@ impl RosMsg for String {
     }
 
     #[inline]
-    fn decode<R: io::Read>(r: R) -> io::Result<Self> {
-        decode_variable_vec::<R, u8>(r).and_then(|v| {
-            String::from_utf8(v).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
-        })
+    fn decode<R: io::Read>(mut r: R) -> io::Result<Self> {
+        let len = decode_string_length(r.by_ref())?;
+        decode_primitive_items::<R, u8>(len, r).and_then(string_from_utf8)
+    }
+
+    /// Checks the length against the rest of the frame before copying the string out of it.
+    #[inline]
+    fn decode_frame(r: &mut FrameReader) -> io::Result<Self> {
+        let len = decode_string_length(&mut *r)?;
+        check_remaining(r.remaining(), "String", len, 1)?;
+        let (frame, offset) = r.take_shared(len)?;
+        string_from_utf8(frame[offset..offset + len].to_vec())
+    }
+
+    #[inline]
+    fn encode_into(&self, w: &mut SliceWriter) -> io::Result<()> {
+        (self.len() as u32).encode_into(w)?;
+        w.put(self.as_bytes())
+    }
+
+    #[inline]
+    fn decode_from(r: &mut SliceReader) -> io::Result<Self> {
+        let len = decode_string_length(&mut *r)?;
+        check_remaining(r.remaining(), "String", len, 1)?;
+        string_from_utf8(r.take(len)?.to_vec())
+    }
+
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        4 + self.len()
     }
 }
 
+#[inline]
+fn string_from_utf8(data: Vec<u8>) -> io::Result<String> {
+    String::from_utf8(data).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
 impl<Hasher> RosMsg for HashMap<String, String, Hasher>
 where
     Hasher: std::hash::BuildHasher,
@@ -279,8 +867,9 @@ where
 
     #[inline]
     fn decode<R: io::Read>(mut r: R) -> io::Result<Self> {
-        let data_size = u64::from(read_data_size(r.by_ref())?);
-        let mut limited_r = r.take(data_size);
+        let data_size = read_data_size(r.by_ref())?;
+        DecodeLimits::current().check_message_size(data_size as usize)?;
+        let mut limited_r = r.take(u64::from(data_size));
         let mut output = HashMap::<String, String, Hasher>::default();
         // TODO: ensure we break only on EOF
         while let Ok(item) = String::decode(&mut limited_r) {
@@ -297,6 +886,15 @@ where
         }
         Ok(output)
     }
+
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        let data_size: usize = self
+            .iter()
+            .map(|(key, value)| 4 + key.len() + 1 + value.len())
+            .sum();
+        4 + data_size
+    }
 }
 
 impl RosMsg for Time {
@@ -314,6 +912,25 @@ impl RosMsg for Time {
             nsec: RosMsg::decode(r)?,
         })
     }
+
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        8
+    }
+
+    #[inline]
+    fn encode_into(&self, w: &mut SliceWriter) -> io::Result<()> {
+        self.sec.encode_into(w)?;
+        self.nsec.encode_into(w)
+    }
+
+    #[inline]
+    fn decode_from(r: &mut SliceReader) -> io::Result<Self> {
+        Ok(Self {
+            sec: RosMsg::decode_from(r)?,
+            nsec: RosMsg::decode_from(r)?,
+        })
+    }
 }
 
 impl RosMsg for Duration {
@@ -331,6 +948,25 @@ impl RosMsg for Duration {
             nsec: RosMsg::decode(r)?,
         })
     }
+
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        8
+    }
+
+    #[inline]
+    fn encode_into(&self, w: &mut SliceWriter) -> io::Result<()> {
+        self.sec.encode_into(w)?;
+        self.nsec.encode_into(w)
+    }
+
+    #[inline]
+    fn decode_from(r: &mut SliceReader) -> io::Result<Self> {
+        Ok(Self {
+            sec: RosMsg::decode_from(r)?,
+            nsec: RosMsg::decode_from(r)?,
+        })
+    }
 }
 
 #[inline]
@@ -0,0 +1,321 @@
+//! Primitive arrays that share the memory of received messages.
+
+use crate::rosmsg::{self, FrameReader, RosMsg};
+use ros_message::Value;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::convert::TryFrom;
+use std::fmt;
+use std::io;
+use std::ops::{Bound, Deref, RangeBounds};
+use std::sync::Arc;
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// Primitive types that can be read directly from the little endian wire format.
+///
+/// Every bit pattern of these types is valid, so they can be viewed in place.
+pub trait Primitive: private::Sealed + RosMsg + Copy + Send + Sync + 'static {}
+
+macro_rules! impl_primitive {
+    ($($t:ty),*) => {
+        $(
+            impl private::Sealed for $t {}
+            impl Primitive for $t {}
+        )*
+    };
+}
+
+impl_primitive!(u8, i8, u16, i16, u32, i32, u64, i64, f32, f64);
+
+/// Immutable array of primitives, like `bytes::Bytes`.
+///
+/// When decoded from a received message, the array points into the message's buffer
+/// instead of copying it, as long as the buffer has suitable alignment for `T`.
+/// Clones and slices share the same memory.
+pub struct SharedSlice<T: Primitive> {
+    storage: Storage<T>,
+    offset: usize,
+    len: usize,
+}
+
+enum Storage<T> {
+    /// Offset is counted in bytes, and points at correctly aligned data.
+    Frame(Arc<Vec<u8>>),
+    /// Offset is counted in elements.
+    Owned(Arc<Vec<T>>),
+}
+
+impl<T: Primitive> SharedSlice<T> {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn as_slice(&self) -> &[T] {
+        match &self.storage {
+            // Construction guarantees that the range is in bounds and aligned
+            Storage::Frame(frame) => unsafe {
+                std::slice::from_raw_parts(frame.as_ptr().add(self.offset) as *const T, self.len)
+            },
+            Storage::Owned(items) => &items[self.offset..self.offset + self.len],
+        }
+    }
+
+    /// Returns whether the data is borrowed from a received message buffer.
+    #[inline]
+    pub fn is_shared_with_frame(&self) -> bool {
+        matches!(self.storage, Storage::Frame(_))
+    }
+
+    /// Returns a part of the array, sharing the same memory.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn slice(&self, range: impl RangeBounds<usize>) -> Self {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end + 1,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.len,
+        };
+        assert!(
+            start <= end && end <= self.len,
+            "Range {}..{} out of bounds for length {}",
+            start,
+            end,
+            self.len,
+        );
+        let (storage, offset) = match &self.storage {
+            Storage::Frame(frame) => (
+                Storage::Frame(Arc::clone(frame)),
+                self.offset + start * std::mem::size_of::<T>(),
+            ),
+            Storage::Owned(items) => (Storage::Owned(Arc::clone(items)), self.offset + start),
+        };
+        Self {
+            storage,
+            offset,
+            len: end - start,
+        }
+    }
+
+    /// Reads the array from a frame, sharing the frame's memory if possible.
+    fn decode_from_frame(r: &mut FrameReader) -> io::Result<Self> {
+        let len = rosmsg::decode_frame_array_length(r, std::mem::size_of::<T>())?;
+        let num_bytes = len * std::mem::size_of::<T>();
+        let start = r.position();
+        let (frame, offset) = r.take_shared(num_bytes)?;
+        let data = frame[offset..].as_ptr() as *const T;
+        let aligned = data.align_offset(std::mem::align_of::<T>()) == 0;
+        if len > 0 && aligned && cfg!(target_endian = "little") {
+            return Ok(Self {
+                storage: Storage::Frame(frame),
+                offset,
+                len,
+            });
+        }
+        // Misaligned data has to be copied before it can be viewed as a slice of T
+        r.set_position(start);
+        let items = rosmsg::decode_fixed_vec(len as u32, &mut *r)?;
+        Ok(items.into())
+    }
+}
+
+impl<T: Primitive> Default for SharedSlice<T> {
+    #[inline]
+    fn default() -> Self {
+        Vec::new().into()
+    }
+}
+
+impl<T: Primitive> Clone for SharedSlice<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        let storage = match &self.storage {
+            Storage::Frame(frame) => Storage::Frame(Arc::clone(frame)),
+            Storage::Owned(items) => Storage::Owned(Arc::clone(items)),
+        };
+        Self {
+            storage,
+            offset: self.offset,
+            len: self.len,
+        }
+    }
+}
+
+impl<T: Primitive> Deref for SharedSlice<T> {
+    type Target = [T];
+
+    #[inline]
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T: Primitive> AsRef<[T]> for SharedSlice<T> {
+    #[inline]
+    fn as_ref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T: Primitive + PartialEq> PartialEq for SharedSlice<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<T: Primitive + fmt::Debug> fmt::Debug for SharedSlice<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_slice().fmt(f)
+    }
+}
+
+impl<T: Primitive> From<Vec<T>> for SharedSlice<T> {
+    #[inline]
+    fn from(items: Vec<T>) -> Self {
+        Self {
+            len: items.len(),
+            offset: 0,
+            storage: Storage::Owned(Arc::new(items)),
+        }
+    }
+}
+
+impl<T: Primitive> From<&[T]> for SharedSlice<T> {
+    #[inline]
+    fn from(items: &[T]) -> Self {
+        items.to_vec().into()
+    }
+}
+
+impl<T: Primitive> From<SharedSlice<T>> for Vec<T> {
+    #[inline]
+    fn from(src: SharedSlice<T>) -> Self {
+        src.as_slice().to_vec()
+    }
+}
+
+impl<T: Primitive> std::iter::FromIterator<T> for SharedSlice<T> {
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        iter.into_iter().collect::<Vec<T>>().into()
+    }
+}
+
+impl<T: Primitive> RosMsg for SharedSlice<T> {
+    #[inline]
+    fn encode<W: io::Write>(&self, w: W) -> io::Result<()> {
+        rosmsg::encode_variable_primitive_slice(self, w)
+    }
+
+    #[inline]
+    fn decode<R: io::Read>(r: R) -> io::Result<Self> {
+        rosmsg::decode_variable_primitive_vec(r).map(Into::into)
+    }
+
+    #[inline]
+    fn decode_frame(r: &mut FrameReader) -> io::Result<Self> {
+        Self::decode_from_frame(r)
+    }
+
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        4 + self.len * std::mem::size_of::<T>()
+    }
+}
+
+impl<T: Primitive + Into<Value>> From<SharedSlice<T>> for Value {
+    #[inline]
+    fn from(src: SharedSlice<T>) -> Self {
+        src.iter().copied().collect()
+    }
+}
+
+impl<T: Primitive + TryFrom<Value>> TryFrom<Value> for SharedSlice<T> {
+    type Error = ();
+
+    #[inline]
+    fn try_from(value: Value) -> Result<Self, ()> {
+        Vec::<T>::try_from(value).map(Into::into)
+    }
+}
+
+impl<T: Primitive + Serialize> Serialize for SharedSlice<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_slice().serialize(serializer)
+    }
+}
+
+impl<'de, T: Primitive + Deserialize<'de>> Deserialize<'de> for SharedSlice<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<T>::deserialize(deserializer).map(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame_reader(data: Vec<u8>, position: usize) -> FrameReader {
+        let mut reader = FrameReader::new(Arc::new(data));
+        reader.set_position(position);
+        reader
+    }
+
+    #[test]
+    fn bytes_share_the_frame() {
+        let mut reader = frame_reader(vec![3, 0, 0, 0, 7, 8, 9, 1], 0);
+        let data = SharedSlice::<u8>::decode_frame(&mut reader).unwrap();
+        assert!(data.is_shared_with_frame());
+        assert_eq!(&data[..], &[7, 8, 9]);
+        assert_eq!(reader.position(), 7);
+        assert_eq!(&data.slice(1..)[..], &[8, 9]);
+        assert!(data.slice(1..).is_shared_with_frame());
+    }
+
+    #[test]
+    fn misaligned_data_gets_copied() {
+        for position in 0..4 {
+            let mut data = vec![0; position];
+            data.extend_from_slice(&[2, 0, 0, 0]);
+            data.extend_from_slice(&1.5f32.to_le_bytes());
+            data.extend_from_slice(&(-2f32).to_le_bytes());
+            let aligned = data[position + 4..].as_ptr().align_offset(4) == 0;
+            let mut reader = frame_reader(data, position);
+            let values = SharedSlice::<f32>::decode_frame(&mut reader).unwrap();
+            assert_eq!(&values[..], &[1.5, -2.0]);
+            assert_eq!(reader.position(), position + 12);
+            assert_eq!(values.is_shared_with_frame(), aligned);
+        }
+    }
+
+    #[test]
+    fn decoding_fails_on_short_frames() {
+        let mut reader = frame_reader(vec![5, 0, 0, 0, 1, 2], 0);
+        SharedSlice::<u8>::decode_frame(&mut reader).unwrap_err();
+        let mut reader = frame_reader(vec![255, 255, 255, 255, 1, 2], 0);
+        SharedSlice::<u64>::decode_frame(&mut reader).unwrap_err();
+    }
+
+    #[test]
+    fn encodes_like_vectors() {
+        let data = SharedSlice::from(vec![1u16, 2, 3]);
+        assert_eq!(
+            data.encode_vec().unwrap(),
+            vec![10, 0, 0, 0, 3, 0, 0, 0, 1, 0, 2, 0, 3, 0]
+        );
+        let decoded = SharedSlice::<u16>::decode_slice(&data.encode_vec().unwrap()).unwrap();
+        assert_eq!(decoded, data);
+        assert!(!decoded.is_shared_with_frame());
+    }
+}
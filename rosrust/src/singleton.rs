@@ -1,7 +1,12 @@
 use crate::api::raii::{Publisher, Service, Subscriber};
 use crate::api::resolve::get_unused_args;
-use crate::api::{Delay, Parameter, Rate, Ros, SystemState, Topic};
+use crate::api::{
+    Clock, ClockJump, Delay, JumpHandle, JumpThreshold, LogFileOptions, Parameter, Rate, Ros,
+    SystemState, Topic,
+};
 use crate::error::{ErrorKind, Result};
+use crate::logger::RosLogger;
+use crate::rosmsg::DecodeLimits;
 use crate::rosxmlrpc::Response;
 use crate::tcpros::{Client, Message, ServicePair, ServiceResult};
 use crate::util::FAILED_TO_LOCK;
@@ -12,6 +17,7 @@ use error_chain::bail;
 use lazy_static::lazy_static;
 use ros_message::{Duration, Time};
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::thread;
 use std::time;
 
@@ -41,13 +47,64 @@ pub fn try_init(name: &str) -> Result<()> {
     try_init_with_options(name, true)
 }
 
+/// Options for initializing the node beyond its name.
+pub struct InitOptions {
+    pub capture_sigint: bool,
+    pub clock: Option<Arc<dyn Clock>>,
+    pub logger: Option<RosLogger>,
+    /// Writes logs to a file under `ROS_LOG_DIR` when set, which is off by default.
+    pub log_file: Option<LogFileOptions>,
+    pub decode_limits: DecodeLimits,
+}
+
+impl Default for InitOptions {
+    fn default() -> Self {
+        Self {
+            capture_sigint: true,
+            clock: None,
+            logger: None,
+            log_file: None,
+            decode_limits: DecodeLimits::default(),
+        }
+    }
+}
+
+#[inline]
 pub fn try_init_with_options(name: &str, capture_sigint: bool) -> Result<()> {
+    try_init_with(
+        name,
+        InitOptions {
+            capture_sigint,
+            ..Default::default()
+        },
+    )
+}
+
+#[inline]
+pub fn try_init_with_clock(name: &str, capture_sigint: bool, clock: Arc<dyn Clock>) -> Result<()> {
+    try_init_with(
+        name,
+        InitOptions {
+            capture_sigint,
+            clock: Some(clock),
+            ..Default::default()
+        },
+    )
+}
+
+pub fn try_init_with(name: &str, options: InitOptions) -> Result<()> {
     let mut ros = ROS.write().expect(FAILED_TO_LOCK);
     if ros.is_some() {
         bail!(ErrorKind::MultipleInitialization);
     }
-    let client = Ros::new(name)?;
-    if capture_sigint {
+    if let Some(logger) = options.logger {
+        logger.install()?;
+    }
+    let client = Ros::new_with_decode_limits(name, options.clock, options.decode_limits)?;
+    if let Err(err) = client.set_log_file(options.log_file) {
+        log::warn!("Failed to open log file: {}", err);
+    }
+    if options.capture_sigint {
         let shutdown_sender = client.shutdown_sender();
         ctrlc::set_handler(move || {
             shutdown_sender.shutdown();
@@ -61,6 +118,11 @@ pub fn is_initialized() -> bool {
     ROS.read().expect(FAILED_TO_LOCK).is_some()
 }
 
+/// Runs the function on the node if it is initialized and not locked for initialization.
+pub(crate) fn with_initialized<T>(f: impl FnOnce(&Ros) -> T) -> Option<T> {
+    ROS.try_read().ok()?.as_ref().map(f)
+}
+
 macro_rules! ros {
     () => {
         ROS.read()
@@ -110,6 +172,14 @@ pub fn rate(rate: f64) -> Rate {
     ros!().rate(rate)
 }
 
+#[inline]
+pub fn on_clock_jump<F>(threshold: JumpThreshold, callback: F) -> JumpHandle
+where
+    F: Fn(&ClockJump) + Send + Sync + 'static,
+{
+    ros!().on_clock_jump(threshold, callback)
+}
+
 #[inline]
 pub fn is_ok() -> bool {
     ros!().is_ok()
@@ -232,6 +302,36 @@ pub fn log(level: i8, msg: String, file: &str, line: u32) {
     ros!().log(level, msg, file, line)
 }
 
+#[inline]
+pub fn log_named(level: i8, name: &str, msg: String, file: &str, line: u32) {
+    ros!().log_named(level, name, msg, file, line)
+}
+
+#[inline]
+pub fn log_enabled(level: i8, name: &str) -> bool {
+    ros!().log_enabled(level, name)
+}
+
+#[inline]
+pub fn set_logger_level(name: &str, level: i8) {
+    ros!().set_logger_level(name, level)
+}
+
+#[inline]
+pub fn set_log_file(options: Option<LogFileOptions>) -> Result<()> {
+    ros!().set_log_file(options)
+}
+
+#[inline]
+pub fn set_decode_limits(limits: DecodeLimits) {
+    ros!().set_decode_limits(limits)
+}
+
+#[inline]
+pub fn set_topic_decode_limits(name: &str, limits: DecodeLimits) -> Result<()> {
+    ros!().set_topic_decode_limits(name, limits)
+}
+
 #[inline]
 pub fn log_once(level: i8, msg: String, file: &str, line: u32) {
     ros!().log_once(level, msg, file, line)
@@ -1,10 +1,10 @@
 use super::error::{ErrorKind, Result, ResultExt};
 use super::header::{decode, encode};
-use super::{ServicePair, ServiceResult};
+use super::{decode_sized, ServicePair, ServiceResult};
 use crate::api::Master;
-use crate::rosmsg::RosMsg;
+use crate::rosmsg::{DecodeLimits, RosMsg};
 use crate::util::FAILED_TO_LOCK;
-use byteorder::{LittleEndian, ReadBytesExt};
+use byteorder::ReadBytesExt;
 use error_chain::bail;
 use log::error;
 use socket2::Socket;
@@ -39,6 +39,7 @@ impl<T: Send + 'static> ClientResponse<T> {
 struct ClientInfo {
     caller_id: String,
     service: String,
+    limits: DecodeLimits,
 }
 
 struct UriCache {
@@ -132,11 +133,17 @@ fn connect_to_tcp_with_multiple_attempts(
 }
 
 impl<T: ServicePair> Client<T> {
-    pub fn new(master: Arc<Master>, caller_id: &str, service: &str) -> Client<T> {
+    pub fn new(
+        master: Arc<Master>,
+        caller_id: &str,
+        service: &str,
+        limits: DecodeLimits,
+    ) -> Client<T> {
         Client {
             info: std::sync::Arc::new(ClientInfo {
                 caller_id: String::from(caller_id),
                 service: String::from(service),
+                limits,
             }),
             uri_cache: std::sync::Arc::new(UriCache {
                 master,
@@ -149,7 +156,12 @@ impl<T: ServicePair> Client<T> {
 
     fn probe_inner(&self, timeout: std::time::Duration) -> Result<()> {
         let mut stream = connect_to_tcp_attempt(&self.uri_cache, Some(timeout))?;
-        exchange_probe_headers(&mut stream, &self.info.caller_id, &self.info.service)?;
+        exchange_probe_headers(
+            &mut stream,
+            &self.info.caller_id,
+            &self.info.service,
+            &self.info.limits,
+        )?;
         Ok(())
     }
 
@@ -162,35 +174,32 @@ impl<T: ServicePair> Client<T> {
     }
 
     pub fn req(&self, args: &T::Request) -> Result<ServiceResult<T::Response>> {
-        Self::request_body(
-            args,
-            &self.uri_cache,
-            &self.info.caller_id,
-            &self.info.service,
-        )
+        Self::request_body(args, &self.uri_cache, &self.info)
     }
 
     pub fn req_async(&self, args: T::Request) -> ClientResponse<T::Response> {
         let info = Arc::clone(&self.info);
         let uri_cache = Arc::clone(&self.uri_cache);
         ClientResponse {
-            handle: thread::spawn(move || {
-                Self::request_body(&args, &uri_cache, &info.caller_id, &info.service)
-            }),
+            handle: thread::spawn(move || Self::request_body(&args, &uri_cache, &info)),
         }
     }
 
     fn request_body(
         args: &T::Request,
         uri_cache: &UriCache,
-        caller_id: &str,
-        service: &str,
+        info: &ClientInfo,
     ) -> Result<ServiceResult<T::Response>> {
+        let ClientInfo {
+            caller_id,
+            service,
+            limits,
+        } = info;
         let mut stream = connect_to_tcp_with_multiple_attempts(uri_cache, 15)
             .chain_err(|| ErrorKind::ServiceConnectionFail(service.into()))?;
 
         // Service request starts by exchanging connection headers
-        exchange_headers::<T, _>(&mut stream, caller_id, service)?;
+        exchange_headers::<T, _>(&mut stream, caller_id, service, limits)?;
 
         let mut writer = io::Cursor::new(Vec::with_capacity(128));
         // skip the first 4 bytes that will contain the message length
@@ -211,11 +220,7 @@ impl<T: ServicePair> Client<T> {
             .chain_err(|| ErrorKind::ServiceResponseInterruption)?;
         Ok(if success {
             // Decode response as response type upon success
-
-            // TODO: validate response length
-            let _length = stream.read_u32::<LittleEndian>();
-
-            let data = RosMsg::decode(&mut stream)?;
+            let data = decode_sized(&mut stream, limits)?;
 
             let mut dump = vec![];
             if let Err(err) = stream.read_to_end(&mut dump) {
@@ -225,7 +230,7 @@ impl<T: ServicePair> Client<T> {
             Ok(data)
         } else {
             // Decode response as string upon failure
-            let data = RosMsg::decode(&mut stream)?;
+            let data = limits.apply(|| RosMsg::decode(&mut stream))?;
 
             let mut dump = vec![];
             if let Err(err) = stream.read_to_end(&mut dump) {
@@ -269,30 +274,40 @@ where
     Ok(())
 }
 
-fn read_response<U>(mut stream: &mut U) -> Result<()>
+fn read_response<U>(mut stream: &mut U, limits: &DecodeLimits) -> Result<()>
 where
     U: std::io::Read,
 {
-    let fields = decode(&mut stream)?;
+    let fields = decode(&mut stream, limits)?;
     if fields.get("callerid").is_none() {
         bail!(ErrorKind::HeaderMissingField("callerid".into()));
     }
     Ok(())
 }
 
-fn exchange_headers<T, U>(stream: &mut U, caller_id: &str, service: &str) -> Result<()>
+fn exchange_headers<T, U>(
+    stream: &mut U,
+    caller_id: &str,
+    service: &str,
+    limits: &DecodeLimits,
+) -> Result<()>
 where
     T: ServicePair,
     U: std::io::Write + std::io::Read,
 {
     write_request::<T, U>(stream, caller_id, service)?;
-    read_response::<U>(stream)
+    read_response::<U>(stream, limits)
 }
 
-fn exchange_probe_headers<U>(stream: &mut U, caller_id: &str, service: &str) -> Result<()>
+fn exchange_probe_headers<U>(
+    stream: &mut U,
+    caller_id: &str,
+    service: &str,
+    limits: &DecodeLimits,
+) -> Result<()>
 where
     U: std::io::Write + std::io::Read,
 {
     write_probe_request::<U>(stream, caller_id, service)?;
-    read_response::<U>(stream)
+    read_response::<U>(stream, limits)
 }
@@ -16,6 +16,10 @@ error_chain::error_chain! {
             display("Data field '{}' within header mismatched. Expected: '{}' Actual: '{}'",
                     field, expected, actual)
         }
+        MessageDefinitionMismatch(msg_type: String, diff: crate::MessageDiff) {
+            description("Message definitions differ between the connected nodes")
+            display("Message definition of '{}' differs from the local one:\n{}", msg_type, diff)
+        }
         HeaderMissingField(field: String) {
             description("Data field within header missing")
             display("Data field '{}' within header missing", field)
@@ -52,3 +56,31 @@ impl Error {
         }
     }
 }
+
+/// Logs a failed connection attempt.
+///
+/// Message definition mismatches usually come from stale builds on another machine,
+/// so they go straight to `/rosout` when the node is initialized.
+pub fn log_connection_error(err: &Error) {
+    use crate::msg::rosgraph_msgs::Log;
+    let info = err
+        .iter()
+        .map(|v| format!("{}", v))
+        .collect::<Vec<_>>()
+        .join("\nCaused by:");
+    let is_definition_mismatch =
+        std::iter::successors(Some(err as &(dyn std::error::Error + 'static)), |v| {
+            v.source()
+        })
+        .filter_map(|v| v.downcast_ref::<Error>())
+        .any(|v| matches!(v.kind(), ErrorKind::MessageDefinitionMismatch(..)));
+    if is_definition_mismatch {
+        let logged = crate::singleton::with_initialized(|ros| {
+            ros.log(Log::ERROR, info.clone(), file!(), line!())
+        });
+        if logged.is_some() {
+            return;
+        }
+    }
+    log::error!("{}", info);
+}
@@ -1,10 +1,23 @@
-use crate::rosmsg::RosMsg;
+use crate::rosmsg::{DecodeLimits, RosMsg};
+use crate::{DynamicMsg, MessageDiff};
 use error_chain::bail;
 use std::collections::HashMap;
 use std::io::Error;
 
-pub fn decode<R: std::io::Read>(data: &mut R) -> Result<HashMap<String, String>, Error> {
-    RosMsg::decode(data)
+/// Decodes a connection header, which only has to fit the message size limit.
+///
+/// Limits on strings and arrays are meant for messages, while header fields like the message
+/// definition can be longer.
+pub fn decode<R: std::io::Read>(
+    data: &mut R,
+    limits: &DecodeLimits,
+) -> Result<HashMap<String, String>, Error> {
+    let limits = DecodeLimits {
+        max_message_size: limits.max_message_size,
+        max_string_length: limits.max_message_size,
+        max_array_length: limits.max_message_size,
+    };
+    limits.apply(|| RosMsg::decode(data))
 }
 
 pub fn encode<W: std::io::Write>(
@@ -34,6 +47,39 @@ pub fn match_field(
     Ok(())
 }
 
+/// Matches the MD5 sum, explaining mismatches with a diff of both message definitions.
+pub fn match_md5sum(
+    fields: &HashMap<String, String>,
+    md5sum: &str,
+    msg_type: &str,
+    msg_definition: &str,
+) -> Result<(), super::error::Error> {
+    use super::error::ErrorKind;
+    let err = match match_field(fields, "md5sum", md5sum) {
+        Ok(()) => return Ok(()),
+        Err(err) => err,
+    };
+    match definition_diff(fields, msg_type, msg_definition) {
+        Some(diff) => bail!(ErrorKind::MessageDefinitionMismatch(msg_type.into(), diff)),
+        None => Err(err),
+    }
+}
+
+fn definition_diff(
+    fields: &HashMap<String, String>,
+    msg_type: &str,
+    msg_definition: &str,
+) -> Option<MessageDiff> {
+    // Definitions can be missing or unparsable, which leaves only the plain mismatch to report
+    let remote = DynamicMsg::from_headers(fields.clone()).ok()?;
+    let local = DynamicMsg::new(msg_type, msg_definition).ok()?;
+    let diff = local.diff(&remote);
+    if diff.is_empty() {
+        return None;
+    }
+    Some(diff)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -86,14 +132,16 @@ mod tests {
     #[test]
     fn reads_empty_map() {
         let input = vec![0, 0, 0, 0];
-        let data = decode(&mut std::io::Cursor::new(input)).expect(FAILED_TO_DECODE);
+        let data = decode(&mut std::io::Cursor::new(input), &DecodeLimits::default())
+            .expect(FAILED_TO_DECODE);
         assert_eq!(0, data.len());
     }
 
     #[test]
     fn reads_single_element() {
         let input = vec![11, 0, 0, 0, 7, 0, 0, 0, 97, 98, 99, 61, 49, 50, 51];
-        let data = decode(&mut std::io::Cursor::new(input)).expect(FAILED_TO_DECODE);
+        let data = decode(&mut std::io::Cursor::new(input), &DecodeLimits::default())
+            .expect(FAILED_TO_DECODE);
         assert_eq!(1, data.len());
         assert_eq!(Some(&String::from("123")), data.get("abc"));
     }
@@ -115,7 +163,8 @@ mod tests {
             0x65, 0x72, 0x14, 0x00, 0x00, 0x00, 0x74, 0x79, 0x70, 0x65, 0x3d, 0x73, 0x74, 0x64,
             0x5f, 0x6d, 0x73, 0x67, 0x73, 0x2f, 0x53, 0x74, 0x72, 0x69, 0x6e, 0x67,
         ];
-        let data = decode(&mut std::io::Cursor::new(input)).expect(FAILED_TO_DECODE);
+        let data = decode(&mut std::io::Cursor::new(input), &DecodeLimits::default())
+            .expect(FAILED_TO_DECODE);
         assert_eq!(6, data.len());
         assert_eq!(
             Some(&String::from("string data\n\n")),
@@ -133,4 +182,49 @@ mod tests {
         assert_eq!(Some(&String::from("/chatter")), data.get("topic"));
         assert_eq!(Some(&String::from("std_msgs/String")), data.get("type"));
     }
+
+    #[test]
+    fn reads_headers_longer_than_message_string_limit() {
+        let mut fields = HashMap::<String, String>::new();
+        fields.insert("message_definition".into(), "string data\n".into());
+        let mut data = vec![];
+        encode(&mut data, &fields).unwrap();
+        let limits = DecodeLimits {
+            max_string_length: 4,
+            ..Default::default()
+        };
+        assert_eq!(
+            decode(&mut std::io::Cursor::new(data.clone()), &limits).unwrap(),
+            fields
+        );
+        let limits = DecodeLimits {
+            max_message_size: 4,
+            ..Default::default()
+        };
+        decode(&mut std::io::Cursor::new(data), &limits).unwrap_err();
+    }
+
+    #[test]
+    fn explains_md5sum_mismatch() {
+        use crate::tcpros::error::ErrorKind;
+        let mut fields = HashMap::<String, String>::new();
+        fields.insert("md5sum".into(), "abc".into());
+        fields.insert("type".into(), "foo/Bar".into());
+        fields.insert("message_definition".into(), "int32 x\nfloat64 y\n".into());
+        match_md5sum(&fields, "abc", "foo/Bar", "int32 x\n").unwrap();
+        let err = match_md5sum(&fields, "def", "foo/Bar", "int32 x\nfloat32 y\n").unwrap_err();
+        match err.kind() {
+            ErrorKind::MessageDefinitionMismatch(msg_type, diff) => {
+                assert_eq!(msg_type, "foo/Bar");
+                assert_eq!(
+                    diff.to_string(),
+                    "  - field `y` changed type from float32 to float64",
+                );
+            }
+            v => panic!("Unexpected error: {:?}", v),
+        }
+        fields.remove("message_definition");
+        let err = match_md5sum(&fields, "def", "foo/Bar", "int32 x\n").unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::HeaderMismatch(..)));
+    }
 }
@@ -4,9 +4,11 @@ pub use self::publisher::{Publisher, PublisherStream};
 pub use self::service::Service;
 pub use self::subscriber::SubscriberRosConnection;
 
-use crate::rosmsg::RosMsg;
+use crate::rosmsg::{DecodeLimits, RosMsg};
 use crate::Clock;
+use byteorder::{LittleEndian, ReadBytesExt};
 use std::fmt::Debug;
+use std::io::{self, Read};
 use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
 
@@ -32,9 +34,98 @@ pub trait ServicePair: Clone + Debug + Default + PartialEq + Message {
     type Response: RosMsg + Send + 'static;
 }
 
+/// Number of bytes reserved for a frame before its data arrives.
+const INITIAL_FRAME_CAPACITY: usize = 64 * 1024;
+
+/// Reads a frame together with its length prefix, without reading past the announced length.
+///
+/// The buffer only grows as data arrives, so a length prefix without the data behind it
+/// cannot make the node allocate memory.
+fn package_to_vector<R: io::Read>(r: &mut R, limits: &DecodeLimits) -> io::Result<Vec<u8>> {
+    let length = r.read_u32::<LittleEndian>()?;
+    limits.check_message_size(length as usize)?;
+    let mut frame = Vec::with_capacity(4 + (length as usize).min(INITIAL_FRAME_CAPACITY));
+    frame.extend_from_slice(&length.to_le_bytes());
+    r.take(u64::from(length)).read_to_end(&mut frame)?;
+    if frame.len() != 4 + length as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "Connection closed in the middle of a message",
+        ));
+    }
+    Ok(frame)
+}
+
+/// Decodes data with a length prefix, checking all lengths against the limits and the frame.
+fn decode_sized<T: RosMsg, R: io::Read>(r: &mut R, limits: &DecodeLimits) -> io::Result<T> {
+    let frame = Arc::new(package_to_vector(r, limits)?);
+    limits.apply(|| T::decode_shared(&frame))
+}
+
 #[derive(Clone, Debug)]
 pub struct Topic {
     pub name: String,
     pub msg_type: String,
     pub md5sum: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static FAILED_TO_READ_WRITE_VECTOR: &str = "Failed to read or write from vector";
+
+    #[test]
+    fn package_to_vector_creates_right_buffer_from_reader() {
+        let input = [7, 0, 0, 0, 1, 2, 3, 4, 5, 6, 7];
+        let data = package_to_vector(&mut std::io::Cursor::new(input), &DecodeLimits::default())
+            .expect(FAILED_TO_READ_WRITE_VECTOR);
+        assert_eq!(data, [7, 0, 0, 0, 1, 2, 3, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn package_to_vector_respects_provided_length() {
+        let input = [7, 0, 0, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12];
+        let data = package_to_vector(&mut std::io::Cursor::new(input), &DecodeLimits::default())
+            .expect(FAILED_TO_READ_WRITE_VECTOR);
+        assert_eq!(data, [7, 0, 0, 0, 1, 2, 3, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn package_to_vector_fails_if_stream_is_shorter_than_annotated() {
+        let input = [7, 0, 0, 0, 1, 2, 3, 4, 5];
+        package_to_vector(&mut std::io::Cursor::new(input), &DecodeLimits::default()).unwrap_err();
+    }
+
+    #[test]
+    fn package_to_vector_fails_leaves_cursor_at_end_of_reading() {
+        let input = [7, 0, 0, 0, 1, 2, 3, 4, 5, 6, 7, 4, 0, 0, 0, 11, 12, 13, 14];
+        let mut cursor = std::io::Cursor::new(input);
+        let data = package_to_vector(&mut cursor, &DecodeLimits::default())
+            .expect(FAILED_TO_READ_WRITE_VECTOR);
+        assert_eq!(data, [7, 0, 0, 0, 1, 2, 3, 4, 5, 6, 7]);
+        let data = package_to_vector(&mut cursor, &DecodeLimits::default())
+            .expect(FAILED_TO_READ_WRITE_VECTOR);
+        assert_eq!(data, [4, 0, 0, 0, 11, 12, 13, 14]);
+    }
+
+    #[test]
+    fn package_to_vector_fails_if_length_exceeds_limit() {
+        let input = [0xff, 0xff, 0xff, 0xff, 1, 2, 3];
+        let limits = DecodeLimits {
+            max_message_size: 1024,
+            ..Default::default()
+        };
+        let err = package_to_vector(&mut std::io::Cursor::new(input), &limits).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn package_to_vector_only_allocates_received_data() {
+        // Announces almost the whole default limit, while sending three bytes
+        let input = [0, 0, 0, 0x0f, 1, 2, 3];
+        let err = package_to_vector(&mut std::io::Cursor::new(input), &DecodeLimits::default())
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+}
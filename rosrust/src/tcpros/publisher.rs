@@ -1,8 +1,9 @@
-use super::error::{ErrorKind, Result, ResultExt};
+use super::error::{log_connection_error, ErrorKind, Result, ResultExt};
 use super::header;
 use super::util::streamfork::{fork, DataStream, TargetList};
 use super::util::tcpconnection;
 use super::{Message, Topic};
+use crate::rosmsg::DecodeLimits;
 use crate::util::FAILED_TO_LOCK;
 use crate::RawMessageDescription;
 use error_chain::bail;
@@ -31,8 +32,14 @@ fn match_headers(
     topic: &str,
     message_description: &RawMessageDescription,
 ) -> Result<()> {
-    header::match_field(fields, "md5sum", &message_description.md5sum)
-        .or_else(|e| header::match_field(fields, "md5sum", "*").or(Err(e)))?;
+    header::match_field(fields, "md5sum", "*").or_else(|_| {
+        header::match_md5sum(
+            fields,
+            &message_description.md5sum,
+            &message_description.msg_type,
+            &message_description.msg_definition,
+        )
+    })?;
     header::match_field(fields, "type", &message_description.msg_type)
         .or_else(|e| header::match_field(fields, "type", "*").or(Err(e)))?;
     header::match_field(fields, "topic", topic)?;
@@ -43,8 +50,9 @@ fn read_request<U: std::io::Read>(
     mut stream: &mut U,
     topic: &str,
     message_description: &RawMessageDescription,
+    limits: &DecodeLimits,
 ) -> Result<String> {
-    let fields = header::decode(&mut stream)?;
+    let fields = header::decode(&mut stream, limits)?;
     match_headers(&fields, topic, message_description)?;
     let caller_id = fields
         .get("callerid")
@@ -76,11 +84,12 @@ fn exchange_headers<U>(
     topic: &str,
     pub_caller_id: &str,
     message_description: &RawMessageDescription,
+    limits: &DecodeLimits,
 ) -> Result<String>
 where
     U: std::io::Write + std::io::Read,
 {
-    let caller_id = read_request(&mut stream, topic, message_description)?;
+    let caller_id = read_request(&mut stream, topic, message_description, limits)?;
     write_response(&mut stream, pub_caller_id, topic, message_description)?;
     Ok(caller_id)
 }
@@ -92,21 +101,23 @@ fn process_subscriber<U>(
     last_message: &Mutex<Arc<Vec<u8>>>,
     pub_caller_id: &str,
     message_description: &RawMessageDescription,
+    limits: &DecodeLimits,
 ) -> tcpconnection::Feedback
 where
     U: std::io::Read + std::io::Write + Send,
 {
-    let result = exchange_headers(&mut stream, topic, pub_caller_id, message_description)
-        .chain_err(|| ErrorKind::TopicConnectionFail(topic.into()));
+    let result = exchange_headers(
+        &mut stream,
+        topic,
+        pub_caller_id,
+        message_description,
+        limits,
+    )
+    .chain_err(|| ErrorKind::TopicConnectionFail(topic.into()));
     let caller_id = match result {
         Ok(caller_id) => caller_id,
         Err(err) => {
-            let info = err
-                .iter()
-                .map(|v| format!("{}", v))
-                .collect::<Vec<_>>()
-                .join("\nCaused by:");
-            error!("{}", info);
+            log_connection_error(&err);
             return tcpconnection::Feedback::AcceptNextStream;
         }
     };
@@ -133,6 +144,7 @@ impl Publisher {
         queue_size: usize,
         caller_id: &str,
         message_description: RawMessageDescription,
+        limits: DecodeLimits,
     ) -> Result<Publisher>
     where
         U: ToSocketAddrs,
@@ -164,6 +176,7 @@ impl Publisher {
                     &last_message,
                     &caller_id,
                     &message_description,
+                    &limits,
                 )
             }
         };
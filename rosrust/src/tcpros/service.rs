@@ -1,9 +1,9 @@
 use super::error::{ErrorKind, Result};
 use super::header;
 use super::util::tcpconnection;
-use super::{ServicePair, ServiceResult};
-use crate::rosmsg::{encode_str, RosMsg};
-use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use super::{decode_sized, ServicePair, ServiceResult};
+use crate::rosmsg::{encode_str, DecodeLimits, RosMsg};
+use byteorder::WriteBytesExt;
 use error_chain::bail;
 use log::error;
 use std::collections::HashMap;
@@ -32,6 +32,7 @@ impl Service {
         port: u16,
         service: &str,
         node_name: &str,
+        limits: DecodeLimits,
         handler: F,
     ) -> Result<Service>
     where
@@ -53,7 +54,13 @@ impl Service {
                 if !service_exists.load(atomic::Ordering::SeqCst) {
                     return tcpconnection::Feedback::StopAccepting;
                 }
-                consume_client::<T, _, _>(&service, &node_name, Arc::clone(&handler), stream);
+                consume_client::<T, _, _>(
+                    &service,
+                    &node_name,
+                    &limits,
+                    Arc::clone(&handler),
+                    stream,
+                );
                 tcpconnection::Feedback::AcceptNextStream
             }
         };
@@ -74,14 +81,19 @@ enum RequestType {
     Action,
 }
 
-fn consume_client<T, U, F>(service: &str, node_name: &str, handler: Arc<F>, mut stream: U)
-where
+fn consume_client<T, U, F>(
+    service: &str,
+    node_name: &str,
+    limits: &DecodeLimits,
+    handler: Arc<F>,
+    mut stream: U,
+) where
     T: ServicePair,
     U: std::io::Read + std::io::Write + Send + 'static,
     F: Fn(T::Request) -> ServiceResult<T::Response> + Send + Sync + 'static,
 {
     // Service request starts by exchanging connection headers
-    match exchange_headers::<T, _>(&mut stream, service, node_name) {
+    match exchange_headers::<T, _>(&mut stream, service, node_name, limits) {
         Err(err) => {
             // Connection can be closed when a client checks for a service.
             if !err.is_closed_connection() {
@@ -92,17 +104,24 @@ where
             }
         }
         // Spawn a thread for handling requests
-        Ok(RequestType::Action) => spawn_request_handler::<T, U, F>(stream, Arc::clone(&handler)),
+        Ok(RequestType::Action) => {
+            spawn_request_handler::<T, U, F>(stream, *limits, Arc::clone(&handler))
+        }
         Ok(RequestType::Probe) => (),
     }
 }
 
-fn exchange_headers<T, U>(stream: &mut U, service: &str, node_name: &str) -> Result<RequestType>
+fn exchange_headers<T, U>(
+    stream: &mut U,
+    service: &str,
+    node_name: &str,
+    limits: &DecodeLimits,
+) -> Result<RequestType>
 where
     T: ServicePair,
     U: std::io::Write + std::io::Read,
 {
-    let req_type = read_request::<T, U>(stream, service)?;
+    let req_type = read_request::<T, U>(stream, service, limits)?;
     write_response::<T, U>(stream, node_name)?;
     Ok(req_type)
 }
@@ -110,8 +129,9 @@ where
 fn read_request<T: ServicePair, U: std::io::Read>(
     stream: &mut U,
     service: &str,
+    limits: &DecodeLimits,
 ) -> Result<RequestType> {
-    let fields = header::decode(stream)?;
+    let fields = header::decode(stream, limits)?;
     header::match_field(&fields, "service", service)?;
     if fields.get("callerid").is_none() {
         bail!(ErrorKind::HeaderMissingField("callerid".into()));
@@ -136,14 +156,14 @@ where
     Ok(())
 }
 
-fn spawn_request_handler<T, U, F>(stream: U, handler: Arc<F>)
+fn spawn_request_handler<T, U, F>(stream: U, limits: DecodeLimits, handler: Arc<F>)
 where
     T: ServicePair,
     U: std::io::Read + std::io::Write + Send + 'static,
     F: Fn(T::Request) -> ServiceResult<T::Response> + Send + Sync + 'static,
 {
     thread::spawn(move || {
-        if let Err(err) = handle_request_loop::<T, U, F>(stream, &handler) {
+        if let Err(err) = handle_request_loop::<T, U, F>(stream, &limits, &handler) {
             if !err.is_closed_connection() {
                 let info = err
                     .iter()
@@ -156,20 +176,18 @@ where
     });
 }
 
-fn handle_request_loop<T, U, F>(mut stream: U, handler: &F) -> Result<()>
+fn handle_request_loop<T, U, F>(mut stream: U, limits: &DecodeLimits, handler: &F) -> Result<()>
 where
     T: ServicePair,
     U: std::io::Read + std::io::Write,
     F: Fn(T::Request) -> ServiceResult<T::Response>,
 {
     // Receive request from client
-    // TODO: validate message length
-    let _length = stream.read_u32::<LittleEndian>();
     // Break out of loop in case of failure to read request
     // TODO: handle retained connections
-    if let Ok(req) = RosMsg::decode(&mut stream) {
+    match decode_sized(&mut stream, limits) {
         // Call function that handles request and returns response
-        match handler(req) {
+        Ok(req) => match handler(req) {
             Ok(res) => {
                 // Send True flag and response in case of success
                 stream.write_u8(1)?;
@@ -191,7 +209,12 @@ where
                 stream.write_u8(0)?;
                 RosMsg::encode(&message, &mut stream)?;
             }
-        };
+        },
+        Err(err) => {
+            if err.kind() == io::ErrorKind::InvalidData {
+                error!("Dropping invalid request: {}", err);
+            }
+        }
     }
 
     // Upon failure to read request, send client failure message
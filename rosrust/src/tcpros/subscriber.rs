@@ -1,14 +1,13 @@
-use super::error::{ErrorKind, Result, ResultExt};
-use super::header::{decode, encode, match_field};
-use super::{Message, Topic};
-use crate::rosmsg::RosMsg;
+use super::error::{log_connection_error, ErrorKind, Result, ResultExt};
+use super::header::{decode, encode, match_field, match_md5sum};
+use super::{package_to_vector, Message, Topic};
+use crate::rosmsg::{is_limit_error, DecodeLimits, RosMsg};
 use crate::util::lossy_channel::{lossy_channel, LossyReceiver, LossySender};
 use crate::SubscriptionHandler;
-use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use crossbeam::channel::{bounded, select, Receiver, Sender, TrySendError};
 use log::error;
 use std::collections::{BTreeMap, BTreeSet, HashMap};
-use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::net::{Shutdown, SocketAddr, TcpStream, ToSocketAddrs};
 use std::sync::Arc;
 use std::thread;
 
@@ -28,6 +27,7 @@ pub struct SubscriberRosConnection {
     topic: Topic,
     pub connected_ids: BTreeSet<usize>,
     pub connected_publishers: BTreeSet<String>,
+    limits: DecodeLimits,
 }
 
 impl SubscriberRosConnection {
@@ -37,33 +37,31 @@ impl SubscriberRosConnection {
         msg_definition: String,
         msg_type: String,
         md5sum: String,
+        limits: DecodeLimits,
     ) -> SubscriberRosConnection {
         let subscriber_connection_queue_size = 8;
         let (data_stream_tx, data_stream_rx) = bounded(subscriber_connection_queue_size);
         let publisher_connection_queue_size = 8;
         let (pub_tx, pub_rx) = bounded(publisher_connection_queue_size);
         let caller_id = String::from(caller_id);
-        let topic_name = String::from(topic);
+        let topic = Topic {
+            name: String::from(topic),
+            msg_type,
+            md5sum,
+        };
         thread::spawn({
-            let msg_type = msg_type.clone();
-            let md5sum = md5sum.clone();
+            let topic = topic.clone();
             move || {
                 join_connections(
                     data_stream_rx,
                     pub_rx,
                     &caller_id,
-                    &topic_name,
+                    &topic,
                     &msg_definition,
-                    &md5sum,
-                    &msg_type,
+                    &limits,
                 )
             }
         });
-        let topic = Topic {
-            name: String::from(topic),
-            msg_type,
-            md5sum,
-        };
         SubscriberRosConnection {
             next_data_stream_id: 1,
             data_stream_tx,
@@ -71,6 +69,7 @@ impl SubscriberRosConnection {
             topic,
             connected_ids: BTreeSet::new(),
             connected_publishers: BTreeSet::new(),
+            limits,
         }
     }
 
@@ -100,7 +99,9 @@ impl SubscriberRosConnection {
             // TODO: we might want to panic here
             error!("Subscriber failed to connect to data stream");
         }
-        thread::spawn(move || handle_data::<T, H>(data_rx, connection_rx, handler));
+        let limits = self.limits;
+        let topic = self.topic.name.clone();
+        thread::spawn(move || handle_data::<T, H>(data_rx, connection_rx, topic, limits, handler));
         data_stream_id
     }
 
@@ -172,6 +173,8 @@ impl SubscriberRosConnection {
 fn handle_data<T, H>(
     data: LossyReceiver<MessageInfo>,
     connections: Receiver<HashMap<String, String>>,
+    topic: String,
+    limits: DecodeLimits,
     mut handler: H,
 ) where
     T: Message,
@@ -182,8 +185,18 @@ fn handle_data<T, H>(
             recv(data.kill_rx.kill_rx) -> _ => break,
             recv(data.data_rx) -> msg => match msg {
                 Err(_) => break,
-                Ok(buffer) => match RosMsg::decode_slice(&buffer.data) {
+                Ok(buffer) => match limits.apply(|| RosMsg::decode_shared(&buffer.data)) {
                     Ok(value) => handler.message(value, &buffer.caller_id),
+                    Err(err) if is_limit_error(&err) => {
+                        error!(
+                            "Dropping connection to '{}' on topic '{}': {}",
+                            buffer.caller_id, topic, err
+                        );
+                        // The reading thread fails on its next read, and ends the connection
+                        if let Err(err) = buffer.connection.shutdown(Shutdown::Both) {
+                            error!("Failed to close connection: {}", err);
+                        }
+                    }
                     Err(err) => error!("Failed to decode message: {}", err),
                 },
             },
@@ -199,10 +212,9 @@ fn join_connections(
     subscribers: Receiver<DataStreamConnectionChange>,
     publishers: Receiver<SocketAddr>,
     caller_id: &str,
-    topic: &str,
+    topic: &Topic,
     msg_definition: &str,
-    md5sum: &str,
-    msg_type: &str,
+    limits: &DecodeLimits,
 ) {
     type Sub = (LossySender<MessageInfo>, Sender<HashMap<String, String>>);
     let mut subs: BTreeMap<usize, Sub> = BTreeMap::new();
@@ -253,10 +265,9 @@ fn join_connections(
                             caller_id,
                             topic,
                             msg_definition,
-                            md5sum,
-                            msg_type,
+                            limits,
                         )
-                        .chain_err(|| ErrorKind::TopicConnectionFail(topic.into()));
+                        .chain_err(|| ErrorKind::TopicConnectionFail(topic.name.clone()));
                         match result {
                             Ok(headers) => {
                                 for sub in subs.values() {
@@ -266,14 +277,7 @@ fn join_connections(
                                 }
                                 existing_headers.push(headers);
                             }
-                            Err(err) => {
-                                let info = err
-                                    .iter()
-                                    .map(|v| format!("{}", v))
-                                    .collect::<Vec<_>>()
-                                    .join("\nCaused by:");
-                                error!("{}", info);
-                            }
+                            Err(err) => log_connection_error(&err),
                         }
                     }
                 }
@@ -286,28 +290,48 @@ fn join_connection(
     data_stream: &Sender<MessageInfo>,
     publisher: &SocketAddr,
     caller_id: &str,
-    topic: &str,
+    topic: &Topic,
     msg_definition: &str,
-    md5sum: &str,
-    msg_type: &str,
+    limits: &DecodeLimits,
 ) -> Result<HashMap<String, String>> {
     let mut stream = TcpStream::connect(publisher)?;
     let headers = exchange_headers::<_>(
         &mut stream,
         caller_id,
-        topic,
+        &topic.name,
         msg_definition,
-        md5sum,
-        msg_type,
+        &topic.md5sum,
+        &topic.msg_type,
+        limits,
     )?;
     let pub_caller_id = headers.get("callerid").cloned();
     let target = data_stream.clone();
+    let topic = topic.name.clone();
+    let limits = *limits;
+    // Subscribers close the connection through this when a message breaks the limits
+    let connection = Arc::new(stream.try_clone()?);
     thread::spawn(move || {
         let pub_caller_id = Arc::new(pub_caller_id.unwrap_or_default());
-        while let Ok(buffer) = package_to_vector(&mut stream) {
-            if let Err(TrySendError::Disconnected(_)) =
-                target.try_send(MessageInfo::new(Arc::clone(&pub_caller_id), buffer))
-            {
+        loop {
+            let buffer = match package_to_vector(&mut stream, &limits) {
+                Ok(buffer) => buffer,
+                Err(err) => {
+                    // Oversized frames leave the stream in an unknown state, so the
+                    // connection gets dropped by leaving the loop
+                    if is_limit_error(&err) {
+                        error!(
+                            "Dropping connection to '{}' on topic '{}': {}",
+                            pub_caller_id, topic, err
+                        );
+                    }
+                    break;
+                }
+            };
+            // Subscribers share the frame, and decoded messages can borrow parts of it
+            let buffer = Arc::new(buffer);
+            let info =
+                MessageInfo::new(Arc::clone(&pub_caller_id), buffer, Arc::clone(&connection));
+            if let Err(TrySendError::Disconnected(_)) = target.try_send(info) {
                 // Data receiver has been destroyed after
                 // Subscriber destructor's kill signal
                 break;
@@ -337,12 +361,14 @@ fn write_request<U: std::io::Write>(
 
 fn read_response<U: std::io::Read>(
     mut stream: &mut U,
+    msg_definition: &str,
     md5sum: &str,
     msg_type: &str,
+    limits: &DecodeLimits,
 ) -> Result<HashMap<String, String>> {
-    let fields = decode(&mut stream)?;
+    let fields = decode(&mut stream, limits)?;
     if md5sum != "*" {
-        match_field(&fields, "md5sum", md5sum)?;
+        match_md5sum(&fields, md5sum, msg_type, msg_definition)?;
     }
     if msg_type != "*" {
         match_field(&fields, "type", msg_type)?;
@@ -357,92 +383,95 @@ fn exchange_headers<U>(
     msg_definition: &str,
     md5sum: &str,
     msg_type: &str,
+    limits: &DecodeLimits,
 ) -> Result<HashMap<String, String>>
 where
     U: std::io::Write + std::io::Read,
 {
     write_request::<U>(stream, caller_id, topic, msg_definition, md5sum, msg_type)?;
-    read_response::<U>(stream, md5sum, msg_type)
-}
-
-#[inline]
-fn package_to_vector<R: std::io::Read>(stream: &mut R) -> std::io::Result<Vec<u8>> {
-    let length = stream.read_u32::<LittleEndian>()?;
-    let u32_size = std::mem::size_of::<u32>();
-    let num_bytes = length as usize + u32_size;
-
-    // Allocate memory of the proper size for the incoming message. We
-    // do not initialize the memory to zero here (as would be safe)
-    // because it is expensive and ultimately unnecessary. We know the
-    // length of the message and if the length is incorrect, the
-    // stream reading functions will bail with an Error rather than
-    // leaving memory uninitialized.
-    let mut out = Vec::<u8>::with_capacity(num_bytes);
-
-    let out_ptr = out.as_mut_ptr();
-    // Read length from stream.
-    std::io::Cursor::new(unsafe { std::slice::from_raw_parts_mut(out_ptr as *mut u8, u32_size) })
-        .write_u32::<LittleEndian>(length)?;
-
-    // Read data from stream.
-    let read_buf = unsafe { std::slice::from_raw_parts_mut(out_ptr as *mut u8, num_bytes) };
-    stream.read_exact(&mut read_buf[u32_size..])?;
-
-    // Don't drop the original Vec which has size==0 and instead use
-    // its memory to initialize a new Vec with size == capacity == num_bytes.
-    std::mem::forget(out);
-
-    // Return the new, now full and "safely" initialized.
-    Ok(unsafe { Vec::from_raw_parts(out_ptr, num_bytes, num_bytes) })
+    read_response::<U>(stream, msg_definition, md5sum, msg_type, limits)
 }
 
 #[derive(Clone)]
 struct MessageInfo {
     caller_id: Arc<String>,
-    data: Vec<u8>,
+    data: Arc<Vec<u8>>,
+    connection: Arc<TcpStream>,
 }
 
 impl MessageInfo {
-    fn new(caller_id: Arc<String>, data: Vec<u8>) -> Self {
-        Self { caller_id, data }
+    fn new(caller_id: Arc<String>, data: Arc<Vec<u8>>, connection: Arc<TcpStream>) -> Self {
+        Self {
+            caller_id,
+            data,
+            connection,
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-
-    static FAILED_TO_READ_WRITE_VECTOR: &str = "Failed to read or write from vector";
-
-    #[test]
-    fn package_to_vector_creates_right_buffer_from_reader() {
-        let input = [7, 0, 0, 0, 1, 2, 3, 4, 5, 6, 7];
-        let data =
-            package_to_vector(&mut std::io::Cursor::new(input)).expect(FAILED_TO_READ_WRITE_VECTOR);
-        assert_eq!(data, [7, 0, 0, 0, 1, 2, 3, 4, 5, 6, 7]);
-    }
-
-    #[test]
-    fn package_to_vector_respects_provided_length() {
-        let input = [7, 0, 0, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12];
-        let data =
-            package_to_vector(&mut std::io::Cursor::new(input)).expect(FAILED_TO_READ_WRITE_VECTOR);
-        assert_eq!(data, [7, 0, 0, 0, 1, 2, 3, 4, 5, 6, 7]);
-    }
-
-    #[test]
-    fn package_to_vector_fails_if_stream_is_shorter_than_annotated() {
-        let input = [7, 0, 0, 0, 1, 2, 3, 4, 5];
-        package_to_vector(&mut std::io::Cursor::new(input)).unwrap_err();
+    use crate::api::handlers::CallbackSubscriptionHandler;
+    use crate::msg::rosgraph_msgs::Log;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::time::Duration;
+
+    fn log_frame(msg: &str) -> Vec<u8> {
+        Log {
+            msg: msg.into(),
+            ..Default::default()
+        }
+        .encode_vec()
+        .unwrap()
     }
 
     #[test]
-    fn package_to_vector_fails_leaves_cursor_at_end_of_reading() {
-        let input = [7, 0, 0, 0, 1, 2, 3, 4, 5, 6, 7, 4, 0, 0, 0, 11, 12, 13, 14];
-        let mut cursor = std::io::Cursor::new(input);
-        let data = package_to_vector(&mut cursor).expect(FAILED_TO_READ_WRITE_VECTOR);
-        assert_eq!(data, [7, 0, 0, 0, 1, 2, 3, 4, 5, 6, 7]);
-        let data = package_to_vector(&mut cursor).expect(FAILED_TO_READ_WRITE_VECTOR);
-        assert_eq!(data, [4, 0, 0, 0, 11, 12, 13, 14]);
+    fn messages_over_limits_drop_the_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let limits = DecodeLimits {
+            max_string_length: 8,
+            ..Default::default()
+        };
+        let mut connection = SubscriberRosConnection::new(
+            "/subscriber",
+            "/rosout",
+            Log::msg_definition(),
+            Log::msg_type(),
+            Log::md5sum(),
+            limits,
+        );
+        let (message_tx, message_rx) = bounded(8);
+        connection.add_subscriber::<Log, _>(
+            8,
+            CallbackSubscriptionHandler::new(
+                move |message: Log, _: &str| message_tx.send(message.msg).unwrap(),
+                |_| {},
+            ),
+        );
+        connection
+            .connect_to("/publisher", listener.local_addr().unwrap())
+            .unwrap();
+
+        let (mut stream, _) = listener.accept().unwrap();
+        decode(&mut stream, &DecodeLimits::default()).unwrap();
+        let mut fields = HashMap::new();
+        fields.insert("callerid".to_owned(), "/publisher".to_owned());
+        fields.insert("md5sum".to_owned(), Log::md5sum());
+        fields.insert("type".to_owned(), Log::msg_type());
+        encode(&mut stream, &fields).unwrap();
+
+        stream.write_all(&log_frame("short")).unwrap();
+        let received = message_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(received, "short");
+
+        stream.write_all(&log_frame("far too long")).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        // The subscriber closes the connection instead of waiting for more messages
+        assert_eq!(stream.read(&mut [0; 16]).unwrap(), 0);
+        assert!(message_rx.try_recv().is_err());
     }
 }
@@ -0,0 +1,174 @@
+// Length prefixes from the wire are checked against limits before anything gets allocated.
+
+use rosrust::{DecodeLimits, DynamicMsg, RosMsg};
+use std::io;
+use std::sync::Arc;
+
+mod msg {
+    rosrust::rosmsg_include!(
+        std_msgs / String,
+        sensor_msgs / Image,
+        sensor_msgs / PointCloud
+    );
+}
+
+/// Frame of a message whose only field is an array or string claiming the given length.
+fn frame_with_length(length: u32, data: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::new();
+    ((data.len() + 4) as u32).encode(&mut frame).unwrap();
+    length.encode(&mut frame).unwrap();
+    frame.extend_from_slice(data);
+    frame
+}
+
+fn assert_invalid_data<T>(result: io::Result<T>) {
+    match result {
+        Ok(_) => panic!("Decoding should have failed"),
+        Err(err) => assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{}", err),
+    }
+}
+
+#[test]
+fn huge_lengths_fail_without_allocating() {
+    assert_invalid_data(msg::std_msgs::String::decode_slice(&frame_with_length(
+        u32::MAX,
+        b"abc",
+    )));
+
+    let frame = frame_with_length(1 << 25, b"abc");
+    // Within the default limits the data simply runs out, and nothing gets reserved up front
+    assert_eq!(
+        msg::std_msgs::String::decode(&frame[4..])
+            .unwrap_err()
+            .kind(),
+        io::ErrorKind::UnexpectedEof
+    );
+    assert_eq!(
+        rosrust::rosmsg::decode_variable_primitive_vec::<_, f64>(&frame[4..])
+            .unwrap_err()
+            .kind(),
+        io::ErrorKind::UnexpectedEof
+    );
+    // Slices and received frames are complete, so lengths past their end are rejected up front
+    assert_invalid_data(msg::std_msgs::String::decode_slice(&frame));
+    assert_invalid_data(msg::std_msgs::String::decode_shared(&Arc::new(frame)));
+}
+
+#[test]
+fn lengths_past_end_of_frame_fail() {
+    let image = msg::sensor_msgs::Image {
+        data: vec![1, 2, 3],
+        ..Default::default()
+    };
+    let mut frame = image.encode_vec().unwrap();
+    // The array length is the last prefix before the data
+    let length_offset = frame.len() - 3 - 4;
+    frame[length_offset..length_offset + 4].copy_from_slice(&4u32.to_le_bytes());
+    let err = msg::sensor_msgs::Image::decode_shared(&Arc::new(frame)).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert!(rosrust::rosmsg::is_limit_error(&err), "{}", err);
+
+    let cloud = msg::sensor_msgs::PointCloud {
+        points: vec![Default::default(); 3],
+        ..Default::default()
+    };
+    let mut frame = cloud.encode_vec().unwrap();
+    frame.truncate(frame.len() - 1);
+    let length = frame.len() as u32 - 4;
+    frame[..4].copy_from_slice(&length.to_le_bytes());
+    // Arrays of messages only grow as their items decode, so they simply run out of data
+    let err = msg::sensor_msgs::PointCloud::decode_shared(&Arc::new(frame)).unwrap_err();
+    assert!(!rosrust::rosmsg::is_limit_error(&err), "{}", err);
+}
+
+#[test]
+fn strings_longer_than_limit_fail() {
+    let limits = DecodeLimits {
+        max_string_length: 3,
+        ..Default::default()
+    };
+    let short = frame_with_length(3, b"abc");
+    let long = frame_with_length(4, b"abcd");
+    limits.apply(|| {
+        assert_eq!(
+            msg::std_msgs::String::decode_slice(&short).unwrap().data,
+            "abc"
+        );
+        assert_invalid_data(msg::std_msgs::String::decode_slice(&long));
+        assert_invalid_data(msg::std_msgs::String::decode_shared(&Arc::new(
+            long.clone(),
+        )));
+    });
+    assert!(msg::std_msgs::String::decode_slice(&long).is_ok());
+}
+
+#[test]
+fn arrays_longer_than_limit_fail() {
+    let limits = DecodeLimits {
+        max_array_length: 2,
+        ..Default::default()
+    };
+    let image = msg::sensor_msgs::Image {
+        data: vec![1, 2, 3],
+        ..Default::default()
+    };
+    let cloud = msg::sensor_msgs::PointCloud {
+        points: vec![Default::default(); 3],
+        ..Default::default()
+    };
+    let image_bytes = image.encode_vec().unwrap();
+    let cloud_bytes = cloud.encode_vec().unwrap();
+    limits.apply(|| {
+        assert_invalid_data(msg::sensor_msgs::Image::decode_slice(&image_bytes));
+        assert_invalid_data(msg::sensor_msgs::PointCloud::decode_slice(&cloud_bytes));
+    });
+    assert_eq!(
+        msg::sensor_msgs::Image::decode_slice(&image_bytes).unwrap(),
+        image
+    );
+}
+
+#[test]
+fn dynamic_messages_respect_limits() {
+    let dynamic = DynamicMsg::new("custom_msgs/Data", "string[] names\nuint16[] values").unwrap();
+    let mut bytes = Vec::new();
+    3u32.encode(&mut bytes).unwrap();
+    for _ in 0..3 {
+        String::from("ab").encode(&mut bytes).unwrap();
+    }
+    0u32.encode(&mut bytes).unwrap();
+    assert!(dynamic.decode(&bytes[..]).is_ok());
+
+    let limits = DecodeLimits {
+        max_array_length: 2,
+        ..Default::default()
+    };
+    limits.apply(|| assert_invalid_data(dynamic.decode(&bytes[..])));
+}
+
+#[test]
+fn applied_limits_are_restored() {
+    let outer = DecodeLimits {
+        max_message_size: 100,
+        ..Default::default()
+    };
+    let inner = DecodeLimits {
+        max_message_size: 10,
+        ..Default::default()
+    };
+    assert_eq!(DecodeLimits::current(), DecodeLimits::default());
+    outer.apply(|| {
+        assert_eq!(DecodeLimits::current(), outer);
+        let result = std::panic::catch_unwind(|| {
+            inner.apply(|| {
+                assert_eq!(DecodeLimits::current(), inner);
+                panic!("Decoding failed");
+            })
+        });
+        assert!(result.is_err());
+        assert_eq!(DecodeLimits::current(), outer);
+        assert!(outer.check_message_size(100).is_ok());
+        assert_invalid_data(outer.check_message_size(101));
+    });
+    assert_eq!(DecodeLimits::current(), DecodeLimits::default());
+}
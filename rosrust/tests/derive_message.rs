@@ -0,0 +1,93 @@
+// Messages derived from Rust structs need to match the messages generated
+// from equivalent MSG files, both in MD5 sums and in encoding.
+
+use rosrust::{Message, RosMessage, RosMsg};
+
+mod msg {
+    rosrust::rosmsg_include!(geometry_msgs / Polygon);
+}
+
+#[derive(Clone, Debug, Default, PartialEq, RosMessage)]
+#[ros(type = "geometry_msgs/Point32")]
+struct Point32 {
+    x: f32,
+    y: f32,
+    z: f32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, RosMessage)]
+#[ros(type = "geometry_msgs/Polygon")]
+struct Polygon {
+    points: Vec<Point32>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, RosMessage)]
+#[ros(type = "custom_msgs/Sample")]
+struct Sample {
+    stamp: rosrust::Time,
+    #[ros(name = "type")]
+    kind: u8,
+    r#ref: String,
+    polygon: Polygon,
+    corners: [Point32; 4],
+    data: Vec<u8>,
+}
+
+#[test]
+fn md5sum_matches_generated_messages() {
+    assert_eq!(Point32::md5sum(), msg::geometry_msgs::Point32::md5sum());
+    assert_eq!(Polygon::md5sum(), msg::geometry_msgs::Polygon::md5sum());
+    assert_eq!(Polygon::msg_type(), msg::geometry_msgs::Polygon::msg_type());
+}
+
+#[test]
+fn encoding_matches_generated_messages() {
+    let derived = Polygon {
+        points: vec![
+            Point32 {
+                x: 1.0,
+                y: 2.0,
+                z: 3.0,
+            },
+            Point32::default(),
+        ],
+    };
+    let generated = msg::geometry_msgs::Polygon {
+        points: vec![
+            msg::geometry_msgs::Point32 {
+                x: 1.0,
+                y: 2.0,
+                z: 3.0,
+            },
+            msg::geometry_msgs::Point32::default(),
+        ],
+    };
+    let bytes = derived.encode_vec().unwrap();
+    assert_eq!(bytes, generated.encode_vec().unwrap());
+    assert_eq!(Polygon::decode_slice(&bytes).unwrap(), derived);
+}
+
+#[test]
+fn definition_lists_fields_and_dependencies() {
+    let definition = Sample::msg_definition();
+    assert!(definition.starts_with(
+        "time stamp\nuint8 type\nstring ref\ngeometry_msgs/Polygon polygon\n\
+         geometry_msgs/Point32[4] corners\nuint8[] data\n\n"
+    ));
+    assert!(definition.contains("\nMSG: geometry_msgs/Polygon\n"));
+    assert_eq!(
+        definition.matches("\nMSG: geometry_msgs/Point32\n").count(),
+        1
+    );
+
+    let sample = Sample {
+        stamp: rosrust::Time::from_nanos(5),
+        kind: 3,
+        r#ref: "frame".into(),
+        polygon: Polygon::default(),
+        corners: Default::default(),
+        data: vec![1, 2, 3],
+    };
+    let bytes = sample.encode_vec().unwrap();
+    assert_eq!(Sample::decode_slice(&bytes).unwrap(), sample);
+}
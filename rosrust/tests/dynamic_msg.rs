@@ -1,8 +1,13 @@
-use ros_message::{MessageValue, Time, Value};
-use rosrust::{DynamicMsg, Message};
+use ros_message::{FieldPath, MessageValue, Time, Value};
+use rosrust::{DynamicMsg, Message, MessageChange, RosMsg, ValueMismatch};
+use std::borrow::Cow;
 
 mod msg {
-    rosrust::rosmsg_include!(geometry_msgs / PoseArray);
+    rosrust::rosmsg_include!(geometry_msgs / PoseArray, sensor_msgs / CameraInfo);
+}
+
+fn path(path: &str) -> FieldPath {
+    path.parse().unwrap()
 }
 
 fn make_message() -> DynamicMsg {
@@ -154,6 +159,38 @@ fn encodes_structures() {
     assert_eq!(get_message_bytes(), data);
 }
 
+#[test]
+fn encodes_like_compiled_messages() {
+    let camera_info = msg::sensor_msgs::CameraInfo {
+        header: msg::std_msgs::Header {
+            seq: 3,
+            stamp: Time { sec: 12, nsec: 34 },
+            frame_id: "camera".into(),
+        },
+        height: 480,
+        width: 640,
+        distortion_model: "plumb_bob".into(),
+        D: vec![0.1, -0.2, 0.0, 0.0, 0.05],
+        K: [500.0, 0.0, 320.0, 0.0, 500.0, 240.0, 0.0, 0.0, 1.0],
+        roi: msg::sensor_msgs::RegionOfInterest {
+            width: 8,
+            do_rectify: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let dynamic_msg = DynamicMsg::new(
+        "sensor_msgs/CameraInfo",
+        &msg::sensor_msgs::CameraInfo::msg_definition(),
+    )
+    .unwrap();
+    let mut data = vec![];
+    dynamic_msg
+        .encode(&camera_info.clone().into(), &mut data)
+        .unwrap();
+    assert_eq!(data, camera_info.encode_vec().unwrap()[4..]);
+}
+
 #[test]
 fn decodes_structures() {
     let dynamic_msg = make_message();
@@ -161,3 +198,297 @@ fn decodes_structures() {
     let data = dynamic_msg.decode(cursor).unwrap();
     assert_eq!(get_message_structure(), data);
 }
+
+#[test]
+fn identical_definitions_have_no_diff() {
+    let diff = make_message().diff(&make_message());
+    assert!(diff.is_empty());
+}
+
+#[test]
+fn diffs_nested_definitions() {
+    let local = make_message();
+    let definition = msg::geometry_msgs::PoseArray::msg_definition();
+    let (head, point) = definition.split_at(definition.find("MSG: geometry_msgs/Point").unwrap());
+    let point = point.replacen("float64 z\n", "float32 z\nfloat64 scale\n", 1);
+    let definition = format!("{}{}", head, point)
+        .replace("uint32 seq\n", "")
+        .replace(
+            "Quaternion orientation\n",
+            "Quaternion orientation\nuint8 FLAG=1\n",
+        );
+    let remote = DynamicMsg::new("geometry_msgs/PoseArray", &definition).unwrap();
+    let diff = local.diff(&remote);
+    assert_eq!(
+        diff.changes(),
+        &[
+            MessageChange::FieldRemoved {
+                path: path("header.seq"),
+                datatype: "uint32".into(),
+            },
+            MessageChange::ConstantAdded {
+                path: path("poses[*].FLAG"),
+                constant: "uint8 FLAG=1".into(),
+            },
+            MessageChange::FieldRetyped {
+                path: path("poses[*].position.z"),
+                expected: "float64".into(),
+                actual: "float32".into(),
+            },
+            MessageChange::FieldAdded {
+                path: path("poses[*].position.scale"),
+                datatype: "float64".into(),
+            },
+        ]
+    );
+    assert_eq!(
+        diff.to_string(),
+        "  - removed field `header.seq` of type uint32
+  - added constant `poses[*].FLAG`: uint8 FLAG=1
+  - field `poses[*].position.z` changed type from float64 to float32
+  - added field `poses[*].position.scale` of type float64",
+    );
+}
+
+#[test]
+fn diffs_reordered_fields_and_changed_constants() {
+    let local = DynamicMsg::new("foo/Bar", "uint8 A=1\nint32 x\nint32 y\nstring s\n").unwrap();
+    let remote = DynamicMsg::new("foo/Bar", "uint8 A=2\nint32 y\nint32 x\nstring[] s\n").unwrap();
+    assert_eq!(
+        local.diff(&remote).changes(),
+        &[
+            MessageChange::ConstantChanged {
+                path: path("A"),
+                expected: "uint8 A=1".into(),
+                actual: "uint8 A=2".into(),
+            },
+            MessageChange::FieldRetyped {
+                path: path("s"),
+                expected: "string".into(),
+                actual: "string[]".into(),
+            },
+            MessageChange::FieldsReordered {
+                path: FieldPath::default()
+            },
+        ]
+    );
+}
+
+#[test]
+fn queries_decoded_and_encoded_messages() {
+    let dynamic_msg = make_message();
+    let decoded = get_message_structure();
+    let bytes = get_message_bytes();
+    let cases: &[(&str, &str, Vec<Value>)] = &[
+        (
+            "header.frame_id",
+            "string",
+            vec![Value::String("abc".into())],
+        ),
+        ("poses[1].position.y", "float64", vec![Value::F64(9.0)]),
+        (
+            "poses[*].orientation.w",
+            "float64",
+            vec![Value::F64(7.0), Value::F64(14.0)],
+        ),
+        ("poses[:1].position.z", "float64", vec![Value::F64(3.0)]),
+        ("poses[1:].position.x", "float64", vec![Value::F64(8.0)]),
+        ("poses[5:].position.x", "float64", vec![]),
+        (
+            "header.stamp",
+            "time",
+            vec![Value::Time(Time { sec: 123, nsec: 0 })],
+        ),
+        (
+            "poses",
+            "geometry_msgs/Pose[]",
+            vec![decoded["poses"].clone()],
+        ),
+        (
+            "poses[0].position",
+            "geometry_msgs/Point",
+            vec![decoded["poses"].as_items().unwrap()[0].as_map().unwrap()["position"].clone()],
+        ),
+    ];
+    for (path, datatype, expected) in cases {
+        let query = dynamic_msg.query(path).unwrap();
+        assert_eq!(query.datatype(), *datatype, "{}", path);
+        assert_eq!(query.extract(&bytes[..]).unwrap(), *expected, "{}", path);
+        let selected = query
+            .select(&decoded)
+            .into_iter()
+            .map(Cow::into_owned)
+            .collect::<Vec<_>>();
+        assert_eq!(selected, *expected, "{}", path);
+    }
+    let query = dynamic_msg.query("poses[1].orientation.x").unwrap();
+    assert_eq!(query.get(&decoded).as_deref(), Some(&Value::F64(11.0)));
+    assert!(query.extract(&bytes[..bytes.len() - 30]).is_err());
+}
+
+#[test]
+fn query_compilation_catches_mistakes() {
+    let dynamic_msg = make_message();
+    for path in &[
+        "poses.position",
+        "header.sequence",
+        "header.seq.x",
+        "header[0]",
+        "poses[0].position.x[0]",
+        "poses[0",
+        "",
+    ] {
+        assert!(dynamic_msg.query(path).is_err(), "{} should fail", path);
+    }
+}
+
+#[test]
+fn encodes_and_decodes_typed_arrays() {
+    let dynamic_msg = DynamicMsg::new(
+        "custom_msgs/Arrays",
+        "uint8[] data\nfloat32[3] scale\nbool[] flags",
+    )
+    .unwrap();
+    let encode = |message: &MessageValue| {
+        let mut data = vec![];
+        dynamic_msg.encode(message, &mut data).map(|_| data)
+    };
+    let mut message = MessageValue::new();
+    message.insert("data".into(), Value::Bytes(vec![7, 8]));
+    message.insert("scale".into(), Value::F32Array(vec![1.0, 0.5, 2.0]));
+    message.insert("flags".into(), vec![true].into());
+    let bytes = vec![
+        2, 0, 0, 0, 7, 8, // data
+        0, 0, 0x80, 0x3f, 0, 0, 0, 0x3f, 0, 0, 0, 0x40, // scale
+        1, 0, 0, 0, 1, // flags
+    ];
+    assert_eq!(encode(&message).unwrap(), bytes);
+
+    let decoded = dynamic_msg.decode(&bytes[..]).unwrap();
+    assert_eq!(decoded, message);
+    assert_eq!(decoded["data"].as_bytes(), Some(&[7, 8][..]));
+    assert_eq!(decoded["scale"].as_f32_slice(), Some(&[1.0, 0.5, 2.0][..]));
+
+    // Boxed arrays still encode the same way
+    message.insert(
+        "data".into(),
+        Value::Array(vec![Value::U8(7), Value::U8(8)]),
+    );
+    assert_eq!(encode(&message).unwrap(), bytes);
+
+    message.insert("scale".into(), Value::F32Array(vec![1.0]));
+    assert!(encode(&message).is_err());
+    message.insert("scale".into(), Value::F64Array(vec![1.0, 0.5, 2.0]));
+    assert!(encode(&message).is_err());
+}
+
+#[test]
+fn builds_default_values() {
+    let dynamic_msg = DynamicMsg::new(
+        "custom_msgs/Defaults",
+        "Header header\nfloat32[3] scale\nstring[2] names\nuint8[] data\ngeometry_msgs/Point[] points\n\
+         ================================================================================\n\
+         MSG: std_msgs/Header\nuint32 seq\ntime stamp\nstring frame_id\n\
+         ================================================================================\n\
+         MSG: geometry_msgs/Point\nfloat64 x\nfloat64 y\nfloat64 z",
+    )
+    .unwrap();
+    let value = dynamic_msg.default_value().unwrap();
+    assert!(dynamic_msg.validate(&value).is_empty());
+    assert_eq!(
+        value["header"].as_map().unwrap()["stamp"],
+        Value::Time(Time::default())
+    );
+    assert_eq!(value["scale"].as_f32_slice(), Some(&[0.0; 3][..]));
+    assert_eq!(
+        value["names"],
+        Value::Array(vec![Value::String("".into()); 2])
+    );
+    assert_eq!(value["data"].array_len(), Some(0));
+
+    let mut bytes = vec![];
+    dynamic_msg.encode(&value, &mut bytes).unwrap();
+    assert_eq!(dynamic_msg.decode(&bytes[..]).unwrap(), value);
+}
+
+#[test]
+fn fills_in_missing_fields() {
+    let dynamic_msg = make_message();
+    let mut position = MessageValue::new();
+    position.insert("x".into(), Value::F64(1.5));
+    let mut pose = MessageValue::new();
+    pose.insert("position".into(), Value::Message(position));
+    let mut value = MessageValue::new();
+    value.insert("poses".into(), Value::Array(vec![Value::Message(pose)]));
+
+    assert!(dynamic_msg.encode(&value, &mut vec![]).is_err());
+    dynamic_msg.fill_defaults(&mut value).unwrap();
+    assert!(dynamic_msg.validate(&value).is_empty());
+
+    let decoded = {
+        let mut bytes = vec![];
+        dynamic_msg.encode(&value, &mut bytes).unwrap();
+        dynamic_msg.decode(&bytes[..]).unwrap()
+    };
+    let query = dynamic_msg.query("poses[0].position.x").unwrap();
+    assert_eq!(query.get(&decoded).as_deref(), Some(&Value::F64(1.5)));
+    let query = dynamic_msg.query("poses[0].orientation.w").unwrap();
+    assert_eq!(query.get(&decoded).as_deref(), Some(&Value::F64(0.0)));
+    assert_eq!(
+        decoded["header"].as_map().unwrap()["frame_id"],
+        Value::String("".into())
+    );
+}
+
+#[test]
+fn validation_lists_every_mismatch() {
+    let dynamic_msg = make_message();
+    let mut value = get_message_structure();
+    let header = match value.get_mut("header") {
+        Some(Value::Message(header)) => header,
+        _ => panic!("header is missing"),
+    };
+    header.insert("stamp".into(), Value::String("now".into()));
+    header.remove("seq");
+    value.insert("extra".into(), Value::U8(1));
+    if let Some(Value::Array(poses)) = value.get_mut("poses") {
+        poses[1] = Value::F64Array(vec![1.0]);
+    }
+
+    let mismatches = dynamic_msg.validate(&value);
+    assert_eq!(
+        mismatches,
+        vec![
+            ValueMismatch::MissingField {
+                path: path("header.seq"),
+                expected: "uint32".into(),
+            },
+            ValueMismatch::WrongType {
+                path: path("header.stamp"),
+                expected: "time".into(),
+                actual: "string".into(),
+            },
+            ValueMismatch::WrongType {
+                path: path("poses[1]"),
+                expected: "geometry_msgs/Pose".into(),
+                actual: "float64[]".into(),
+            },
+            ValueMismatch::UnknownField {
+                path: path("extra")
+            },
+        ]
+    );
+    assert_eq!(
+        mismatches[1].to_string(),
+        "header.stamp: expected time, got string"
+    );
+
+    let err = dynamic_msg.encode(&value, &mut vec![]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Passed in dynamic data value does not match geometry_msgs/PoseArray:\n  \
+         - header.seq: missing field of type uint32\n  \
+         - header.stamp: expected time, got string\n  \
+         - poses[1]: expected geometry_msgs/Pose, got float64[]"
+    );
+}
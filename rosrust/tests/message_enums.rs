@@ -0,0 +1,89 @@
+// Constant groups of generated messages get enums when `ENUMS` is passed.
+
+use std::convert::TryFrom;
+
+mod msg {
+    rosrust::rosmsg_include!(
+        sensor_msgs / NavSatStatus,
+        sensor_msgs / JoyFeedback,
+        sensor_msgs / BatteryState,
+        actionlib_msgs / GoalStatus,
+        diagnostic_msgs / DiagnosticStatus,
+        visualization_msgs / ImageMarker,
+        ENUMS
+    );
+}
+
+use msg::actionlib_msgs::{GoalStatus, GoalStatusStatus};
+use msg::diagnostic_msgs::{DiagnosticStatus, DiagnosticStatusLevel};
+use msg::sensor_msgs::{
+    BatteryState, BatteryStatePowerSupplyHealth, BatteryStatePowerSupplyStatus, JoyFeedback,
+    JoyFeedbackType, NavSatStatus, NavSatStatusService, NavSatStatusStatus,
+};
+
+#[test]
+fn converts_raw_values() {
+    assert_eq!(
+        NavSatStatusStatus::try_from(-1),
+        Ok(NavSatStatusStatus::NoFix)
+    );
+    assert_eq!(NavSatStatusStatus::try_from(7), Err(7));
+    assert_eq!(i8::from(NavSatStatusStatus::GbasFix), 2);
+    assert_eq!(
+        u16::from(NavSatStatusService::Galileo),
+        NavSatStatus::SERVICE_GALILEO
+    );
+    let status: u8 = BatteryStatePowerSupplyStatus::Full.into();
+    assert_eq!(status, BatteryState::POWER_SUPPLY_STATUS_FULL);
+    assert_eq!(BatteryStatePowerSupplyHealth::ALL.len(), 9);
+    assert_eq!(u8::from(GoalStatusStatus::Lost), GoalStatus::LOST);
+    let level: i8 = DiagnosticStatusLevel::Stale.into();
+    assert_eq!(level, DiagnosticStatus::STALE);
+    assert_eq!(GoalStatusStatus::ALL.len(), 10);
+}
+
+#[test]
+fn displays_constant_names() {
+    assert_eq!(NavSatStatusStatus::SbasFix.to_string(), "STATUS_SBAS_FIX");
+    assert_eq!(
+        BatteryStatePowerSupplyStatus::NotCharging.to_string(),
+        "POWER_SUPPLY_STATUS_NOT_CHARGING"
+    );
+    assert_eq!(GoalStatusStatus::Preempted.to_string(), "PREEMPTED");
+}
+
+#[test]
+fn accesses_fields_through_enums() {
+    let mut status = NavSatStatus::default();
+    assert_eq!(status.status_enum(), Ok(NavSatStatusStatus::Fix));
+    status.set_status_enum(NavSatStatusStatus::NoFix);
+    assert_eq!(status.status, NavSatStatus::STATUS_NO_FIX);
+    status.status = 100;
+    assert_eq!(status.status_enum(), Err(100));
+
+    let mut feedback = JoyFeedback::default();
+    feedback.set_type_enum(JoyFeedbackType::Buzzer);
+    assert_eq!(feedback.type_, JoyFeedback::TYPE_BUZZER);
+
+    let battery = BatteryState {
+        power_supply_status: BatteryState::POWER_SUPPLY_STATUS_CHARGING,
+        ..Default::default()
+    };
+    match battery.power_supply_status_enum() {
+        Ok(BatteryStatePowerSupplyStatus::Charging) => {}
+        Ok(BatteryStatePowerSupplyStatus::Unknown)
+        | Ok(BatteryStatePowerSupplyStatus::Discharging)
+        | Ok(BatteryStatePowerSupplyStatus::NotCharging)
+        | Ok(BatteryStatePowerSupplyStatus::Full)
+        | Err(_) => panic!(),
+    }
+
+    let status = DiagnosticStatus {
+        level: DiagnosticStatus::WARN,
+        ..Default::default()
+    };
+    match status.level_enum() {
+        Ok(DiagnosticStatusLevel::Ok) | Ok(DiagnosticStatusLevel::Stale) | Err(_) => panic!(),
+        Ok(DiagnosticStatusLevel::Warn) | Ok(DiagnosticStatusLevel::Error) => {}
+    }
+}
@@ -0,0 +1,190 @@
+// Raw messages are handled through their compiled types, found by the type name and MD5 sum.
+
+use ros_message::{MessageValue, Value};
+use rosrust::{DynamicMsg, Message, MessageRegistry, MessageSchema, RawMessage, RosMsg};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::io;
+
+mod msg {
+    rosrust::rosmsg_include!(
+        std_msgs / String,
+        sensor_msgs / Image,
+        diagnostic_msgs / SelfTest,
+        REGISTRY
+    );
+}
+
+fn registry() -> MessageRegistry {
+    let mut registry = MessageRegistry::new();
+    msg::register_messages(&mut registry);
+    registry
+}
+
+fn image() -> msg::sensor_msgs::Image {
+    msg::sensor_msgs::Image {
+        height: 1,
+        width: 2,
+        encoding: "mono8".into(),
+        data: vec![3, 4],
+        ..Default::default()
+    }
+}
+
+/// Older definition of `std_msgs/String`, registered under another MD5 sum.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct OldString(msg::std_msgs::String);
+
+impl RosMsg for OldString {
+    fn encode<W: io::Write>(&self, w: W) -> io::Result<()> {
+        self.0.encode(w)
+    }
+
+    fn decode<R: io::Read>(r: R) -> io::Result<Self> {
+        RosMsg::decode(r).map(OldString)
+    }
+}
+
+impl Message for OldString {
+    fn msg_definition() -> String {
+        msg::std_msgs::String::msg_definition()
+    }
+
+    fn md5sum() -> String {
+        "0123456789abcdef0123456789abcdef".into()
+    }
+
+    fn msg_type() -> String {
+        msg::std_msgs::String::msg_type()
+    }
+}
+
+impl From<OldString> for MessageValue {
+    fn from(src: OldString) -> Self {
+        src.0.into()
+    }
+}
+
+impl TryFrom<MessageValue> for OldString {
+    type Error = ();
+
+    fn try_from(src: MessageValue) -> Result<Self, ()> {
+        msg::std_msgs::String::try_from(src).map(OldString)
+    }
+}
+
+impl MessageSchema for OldString {
+    fn dynamic_msg() -> &'static DynamicMsg {
+        msg::std_msgs::String::dynamic_msg()
+    }
+
+    fn to_value(&self) -> MessageValue {
+        self.0.to_value()
+    }
+}
+
+/// Message body without the length prefix, as received in a `RawMessage`.
+fn body<T: RosMsg>(message: &T) -> Vec<u8> {
+    message.encode_vec().unwrap()[4..].to_vec()
+}
+
+#[test]
+fn generated_messages_are_registered() {
+    let registry = registry();
+    let mut types = registry
+        .iter()
+        .map(|v| v.msg_type().to_owned())
+        .collect::<Vec<_>>();
+    types.sort();
+    assert!(types.contains(&"std_msgs/String".to_owned()));
+    assert!(types.contains(&"std_msgs/Header".to_owned()));
+    assert!(types.contains(&"diagnostic_msgs/SelfTestReq".to_owned()));
+    assert!(types.contains(&"diagnostic_msgs/SelfTestRes".to_owned()));
+    assert_eq!(registry.len(), types.len());
+}
+
+#[test]
+fn types_are_found_by_name_and_md5sum() {
+    let registry = registry();
+    let md5sum = msg::sensor_msgs::Image::md5sum();
+    let found = registry.get("sensor_msgs/Image", &md5sum).unwrap();
+    assert_eq!(found.msg_type(), "sensor_msgs/Image");
+    assert_eq!(found.md5sum(), md5sum);
+    assert_eq!(
+        registry.get("sensor_msgs/Image", "*").unwrap().md5sum(),
+        md5sum
+    );
+    assert!(registry.get("sensor_msgs/Image", "0123").is_none());
+    assert!(registry.get("sensor_msgs/Imu", "*").is_none());
+
+    let mut headers = HashMap::new();
+    headers.insert("type".to_owned(), "std_msgs/String".to_owned());
+    assert!(registry.from_headers(&headers).is_none());
+    headers.insert("md5sum".to_owned(), msg::std_msgs::String::md5sum());
+    assert_eq!(
+        registry.from_headers(&headers).unwrap().msg_type(),
+        "std_msgs/String"
+    );
+}
+
+#[test]
+fn any_md5sum_only_matches_unambiguous_types() {
+    let mut registry = registry();
+    assert!(registry.get("std_msgs/String", "*").is_some());
+    registry.register::<OldString>();
+    assert!(registry.get("std_msgs/String", "*").is_none());
+    assert_eq!(
+        registry
+            .get("std_msgs/String", &OldString::md5sum())
+            .unwrap()
+            .md5sum(),
+        OldString::md5sum()
+    );
+    assert!(registry
+        .get("std_msgs/String", &msg::std_msgs::String::md5sum())
+        .is_some());
+}
+
+#[test]
+fn raw_messages_decode_into_compiled_types() {
+    let registry = registry();
+    let image_type = registry.get("sensor_msgs/Image", "*").unwrap();
+    let decoded = image_type.decode(&body(&image())).unwrap();
+    assert!(image_type.matches(decoded.as_ref()));
+    assert_eq!(
+        decoded.downcast_ref::<msg::sensor_msgs::Image>(),
+        Some(&image())
+    );
+    assert_eq!(
+        format!("{:?}", image_type.debug(decoded.as_ref()).unwrap()),
+        format!("{:?}", image())
+    );
+
+    let value = image_type.decode_value(&body(&image())).unwrap();
+    assert_eq!(value.get("width"), Some(&Value::U32(2)));
+    assert_eq!(
+        image_type
+            .dynamic_msg()
+            .decode(&body(&image())[..])
+            .unwrap(),
+        value
+    );
+
+    assert!(image_type.decode(&[1, 2]).is_err());
+}
+
+#[test]
+fn compiled_types_encode_into_raw_messages() {
+    let registry = registry();
+    let image_type = registry.get("sensor_msgs/Image", "*").unwrap();
+    let RawMessage(bytes) = image_type.encode(&image()).unwrap();
+    assert_eq!(bytes, body(&image()));
+
+    let string = msg::std_msgs::String { data: "x".into() };
+    assert!(!image_type.matches(&string));
+    assert!(image_type.debug(&string).is_none());
+    assert_eq!(
+        image_type.encode(&string).unwrap_err().kind(),
+        io::ErrorKind::InvalidInput
+    );
+}
@@ -0,0 +1,140 @@
+// Generated messages expose their parsed definitions and convert to and from dynamic values.
+
+use ros_message::{MessagePath, MessageValue, Value};
+use rosrust::{MessageSchema, RosMsg};
+use std::convert::TryInto;
+
+mod msg {
+    rosrust::rosmsg_include!(
+        geometry_msgs / PoseArray,
+        sensor_msgs / Image,
+        visualization_msgs / Marker,
+        diagnostic_msgs / SelfTest,
+        SCHEMA
+    );
+}
+
+fn path(name: &str) -> MessagePath {
+    name.try_into().unwrap()
+}
+
+/// Lists the dotted paths of all fields, using nothing but the schema.
+fn field_paths<T: MessageSchema>() -> Vec<String> {
+    fn collect<T: MessageSchema>(msg: &ros_message::Msg, prefix: &str, output: &mut Vec<String>) {
+        for field in msg.fields().iter().filter(|field| !field.is_constant()) {
+            let name = format!("{}{}", prefix, field.name());
+            let nested = field.datatype().message_path(msg.path());
+            match nested.and_then(|path| T::dependency_schemas().get(&path)) {
+                Some(nested) => collect::<T>(nested, &format!("{}.", name), output),
+                None => output.push(name),
+            }
+        }
+    }
+    let mut output = vec![];
+    collect::<T>(T::schema(), "", &mut output);
+    output
+}
+
+#[test]
+fn schema_describes_message_and_dependencies() {
+    type PoseArray = msg::geometry_msgs::PoseArray;
+    assert_eq!(PoseArray::schema().path(), &path("geometry_msgs/PoseArray"));
+    let mut dependencies = PoseArray::dependency_schemas()
+        .keys()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    dependencies.sort();
+    assert_eq!(
+        dependencies,
+        [
+            "geometry_msgs/Point",
+            "geometry_msgs/Pose",
+            "geometry_msgs/Quaternion",
+            "std_msgs/Header",
+        ]
+    );
+    assert_eq!(
+        field_paths::<PoseArray>(),
+        [
+            "header.seq",
+            "header.stamp",
+            "header.frame_id",
+            "poses.position.x",
+            "poses.position.y",
+            "poses.position.z",
+            "poses.orientation.x",
+            "poses.orientation.y",
+            "poses.orientation.z",
+            "poses.orientation.w",
+        ]
+    );
+}
+
+#[test]
+fn schema_is_built_once() {
+    type Marker = msg::visualization_msgs::Marker;
+    assert!(std::ptr::eq(Marker::dynamic_msg(), Marker::dynamic_msg()));
+    assert!(std::ptr::eq(Marker::schema(), Marker::schema()));
+    assert_eq!(
+        Marker::schema().constants().get("DELETEALL"),
+        Some(&Value::U8(3))
+    );
+}
+
+#[test]
+fn service_parts_have_schemas() {
+    type SelfTestRes = msg::diagnostic_msgs::SelfTestRes;
+    assert_eq!(
+        SelfTestRes::schema().path(),
+        &path("diagnostic_msgs/SelfTestRes")
+    );
+    assert!(SelfTestRes::dependency_schemas().contains_key(&path("diagnostic_msgs/KeyValue")));
+}
+
+#[test]
+fn messages_convert_to_and_from_values() {
+    let image = msg::sensor_msgs::Image {
+        height: 2,
+        width: 1,
+        encoding: "mono8".into(),
+        data: vec![3, 4],
+        ..Default::default()
+    };
+    let value = image.to_value();
+    assert_eq!(value.get("width"), Some(&Value::U32(1)));
+    assert_eq!(value.get("data"), Some(&Value::Bytes(vec![3, 4])));
+
+    // The value matches what decoding the encoded message dynamically produces
+    let bytes = image.encode_vec().unwrap();
+    let decoded = msg::sensor_msgs::Image::dynamic_msg()
+        .decode(&bytes[4..])
+        .unwrap();
+    assert_eq!(decoded, value);
+
+    assert_eq!(msg::sensor_msgs::Image::from_value(value), Some(image));
+}
+
+#[test]
+fn nested_messages_convert_like_owned_messages() {
+    let mut poses = msg::geometry_msgs::PoseArray::default();
+    poses.header.frame_id = "map".into();
+    poses.poses = vec![Default::default(); 2];
+    poses.poses[1].position.x = 1.5;
+    let value = poses.to_value();
+    assert_eq!(value, MessageValue::from(poses.clone()));
+    assert_eq!(
+        msg::geometry_msgs::PoseArray::from_value(value),
+        Some(poses)
+    );
+}
+
+#[test]
+fn values_with_missing_fields_are_rejected() {
+    let mut value = msg::sensor_msgs::Image::default().to_value();
+    value.remove("encoding");
+    assert_eq!(msg::sensor_msgs::Image::from_value(value), None);
+    assert_eq!(
+        msg::sensor_msgs::Image::from_value(MessageValue::new()),
+        None
+    );
+}
@@ -0,0 +1,83 @@
+// Generated messages can opt into serde support with the SERDE flag.
+//
+// Serialized messages must keep their ROS field names, and support arrays
+// that are longer than the ones serde handles out of the box.
+// Through `ros_message::wire`, they must also encode exactly like `RosMsg`.
+
+use rosrust::RosMsg;
+
+mod msg {
+    rosrust::rosmsg_include!(
+        visualization_msgs / ImageMarker,
+        geometry_msgs / PoseWithCovariance,
+        sensor_msgs / PointCloud2,
+        SERDE
+    );
+}
+
+#[test]
+fn keeps_ros_field_names() {
+    let message = msg::visualization_msgs::ImageMarker {
+        type_: 3,
+        lifetime: rosrust::Duration::from_nanos(1_500_000_000),
+        ..Default::default()
+    };
+    let value = serde_json::to_value(&message).unwrap();
+    assert_eq!(value["type"], 3);
+    assert_eq!(value["lifetime"]["sec"], 1);
+    assert_eq!(value["lifetime"]["nsec"], 500_000_000);
+    assert!(value.get("type_").is_none());
+    let decoded: msg::visualization_msgs::ImageMarker = serde_json::from_value(value).unwrap();
+    assert_eq!(decoded, message);
+}
+
+#[test]
+fn handles_large_arrays() {
+    let mut message = msg::geometry_msgs::PoseWithCovariance::default();
+    message.covariance[35] = 2.5;
+    let json = serde_json::to_string(&message).unwrap();
+    let decoded: msg::geometry_msgs::PoseWithCovariance = serde_json::from_str(&json).unwrap();
+    assert_eq!(decoded, message);
+
+    let mut value = serde_json::to_value(&message).unwrap();
+    value["covariance"].as_array_mut().unwrap().pop();
+    assert!(serde_json::from_value::<msg::geometry_msgs::PoseWithCovariance>(value).is_err());
+}
+
+#[test]
+fn wire_format_matches_rosmsg() {
+    let message = msg::sensor_msgs::PointCloud2 {
+        header: msg::std_msgs::Header {
+            seq: 4,
+            stamp: rosrust::Time::from_nanos(1_500_000_000),
+            frame_id: "base_link".into(),
+        },
+        height: 1,
+        width: 2,
+        fields: vec![msg::sensor_msgs::PointField {
+            name: "x".into(),
+            offset: 0,
+            datatype: msg::sensor_msgs::PointField::FLOAT32,
+            count: 1,
+        }],
+        is_bigendian: false,
+        point_step: 4,
+        row_step: 8,
+        data: vec![1, 2, 3, 4, 5, 6, 7, 8],
+        is_dense: true,
+    };
+    let data = ros_message::wire::to_vec(&message).unwrap();
+    assert_eq!(data, message.encode_vec().unwrap());
+    let decoded: msg::sensor_msgs::PointCloud2 = ros_message::wire::from_slice(&data).unwrap();
+    assert_eq!(decoded, message);
+
+    let mut pose = msg::geometry_msgs::PoseWithCovariance::default();
+    pose.pose.orientation.w = 1.0;
+    pose.covariance[7] = -0.5;
+    let data = ros_message::wire::to_vec(&pose).unwrap();
+    assert_eq!(data, pose.encode_vec().unwrap());
+    assert_eq!(
+        msg::geometry_msgs::PoseWithCovariance::decode_slice(&data).unwrap(),
+        pose
+    );
+}
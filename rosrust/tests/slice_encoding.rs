@@ -0,0 +1,96 @@
+// Generated messages know their exact encoded length, and encode to and decode from slices.
+
+use rosrust::{RosMessage, RosMsg};
+
+mod msg {
+    rosrust::rosmsg_include!(
+        sensor_msgs / Image,
+        sensor_msgs / PointCloud,
+        geometry_msgs / PoseWithCovariance,
+        diagnostic_msgs / DiagnosticArray
+    );
+}
+
+#[derive(Clone, Debug, Default, PartialEq, RosMessage)]
+#[ros(type = "custom_msgs/Sample")]
+struct Sample {
+    name: String,
+    corners: [msg::geometry_msgs::Point32; 2],
+    data: Vec<u16>,
+}
+
+fn assert_encoded_len<T: RosMsg + PartialEq + std::fmt::Debug>(value: &T) {
+    let bytes = value.encode_vec().unwrap();
+    assert_eq!(value.encoded_len() + 4, bytes.len());
+    assert_eq!(&bytes[..4], &(value.encoded_len() as u32).to_le_bytes());
+
+    let mut buffer = vec![0; value.encoded_len() + 3];
+    assert_eq!(
+        value.encode_to_slice(&mut buffer).unwrap(),
+        value.encoded_len()
+    );
+    assert_eq!(&buffer[..value.encoded_len()], &bytes[4..]);
+    assert_eq!(&T::decode_from_slice(&buffer).unwrap(), value);
+    assert_eq!(&T::decode_slice(&bytes).unwrap(), value);
+}
+
+#[test]
+fn encoded_len_matches_encoding() {
+    assert_encoded_len(&msg::sensor_msgs::Image {
+        encoding: "rgb8".into(),
+        data: vec![7; 300],
+        ..Default::default()
+    });
+    assert_encoded_len(&msg::sensor_msgs::PointCloud {
+        points: vec![Default::default(); 3],
+        channels: vec![msg::sensor_msgs::ChannelFloat32 {
+            name: "rgb".into(),
+            values: vec![1.0, 2.0, 3.0],
+        }],
+        ..Default::default()
+    });
+    assert_encoded_len(&msg::geometry_msgs::PoseWithCovariance::default());
+    let mut status = msg::diagnostic_msgs::DiagnosticStatus::default();
+    status.values.push(msg::diagnostic_msgs::KeyValue {
+        key: "temperature".into(),
+        value: "hot".into(),
+    });
+    assert_encoded_len(&msg::diagnostic_msgs::DiagnosticArray {
+        status: vec![status, Default::default()],
+        ..Default::default()
+    });
+    assert_encoded_len(&Sample {
+        name: "sample".into(),
+        corners: Default::default(),
+        data: vec![1, 2, 3],
+    });
+}
+
+#[test]
+fn encoding_to_short_slice_fails() {
+    let image = msg::sensor_msgs::Image {
+        data: vec![7; 300],
+        ..Default::default()
+    };
+    let mut buffer = vec![0; image.encoded_len() - 1];
+    image.encode_to_slice(&mut buffer).unwrap_err();
+    msg::sensor_msgs::Image::decode_from_slice(&buffer).unwrap_err();
+}
+
+#[test]
+fn decoding_slice_checks_lengths_against_data() {
+    let image = msg::sensor_msgs::Image {
+        data: vec![7; 300],
+        ..Default::default()
+    };
+    let mut bytes = vec![0; image.encoded_len()];
+    image.encode_to_slice(&mut bytes).unwrap();
+    let data_start = bytes.len() - 300 - 4;
+    bytes[data_start..data_start + 4].copy_from_slice(&301u32.to_le_bytes());
+    let err = msg::sensor_msgs::Image::decode_from_slice(&bytes).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+    let mut bytes = vec![0; 8];
+    bytes[..4].copy_from_slice(&u32::MAX.to_le_bytes());
+    String::decode_from_slice(&bytes).unwrap_err();
+}
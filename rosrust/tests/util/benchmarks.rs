@@ -1,6 +1,7 @@
-use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
 use crossbeam::channel::unbounded;
 use lazy_static::lazy_static;
+use rosrust::RosMsg;
 use std::process::Command;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time;
@@ -11,6 +12,7 @@ mod msg {
     rosrust::rosmsg_include!(
         roscpp_tutorials / TwoInts,
         rospy_tutorials / AddTwoInts,
+        sensor_msgs / PointCloud,
         std_msgs / String
     );
 }
@@ -340,8 +342,38 @@ fn call_service(criterion: &mut Criterion) {
     );
 }
 
+fn serialize_large_message(criterion: &mut Criterion) {
+    let cloud = msg::sensor_msgs::PointCloud {
+        points: (0..100_000)
+            .map(|i| msg::geometry_msgs::Point32 {
+                x: i as f32,
+                y: 0.5,
+                z: -1.0,
+            })
+            .collect(),
+        channels: vec![msg::sensor_msgs::ChannelFloat32 {
+            name: "intensity".into(),
+            values: vec![0.25; 100_000],
+        }],
+        ..Default::default()
+    };
+    let bytes = cloud.encode_vec().unwrap();
+
+    criterion.bench_function("encode large message to vector", |b| {
+        b.iter(|| black_box(&cloud).encode_vec().unwrap());
+    });
+    let mut buffer = vec![0; cloud.encoded_len()];
+    criterion.bench_function("encode large message to slice", |b| {
+        b.iter(|| black_box(&cloud).encode_to_slice(&mut buffer).unwrap());
+    });
+    criterion.bench_function("decode large message from slice", |b| {
+        b.iter(|| msg::sensor_msgs::PointCloud::decode_slice(black_box(&bytes)).unwrap());
+    });
+}
+
 criterion_group!(
     benches,
+    serialize_large_message,
     subscribe_publish_directly,
     subscribe_publish_relayed,
     call_service
@@ -0,0 +1,68 @@
+// Primitive arrays of messages generated with `ZERO_COPY` share the received buffer.
+
+use rosrust::RosMsg;
+use std::sync::Arc;
+
+mod msg {
+    rosrust::rosmsg_include!(sensor_msgs / Image, sensor_msgs / PointCloud, ZERO_COPY);
+}
+
+mod copied {
+    rosrust::rosmsg_include!(sensor_msgs / Image, sensor_msgs / PointCloud);
+}
+
+fn image() -> msg::sensor_msgs::Image {
+    msg::sensor_msgs::Image {
+        height: 2,
+        width: 3,
+        encoding: "mono8".into(),
+        step: 3,
+        data: vec![1, 2, 3, 4, 5, 6].into(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn encoding_matches_copied_messages() {
+    let image = image();
+    let copied = copied::sensor_msgs::Image {
+        height: 2,
+        width: 3,
+        encoding: "mono8".into(),
+        step: 3,
+        data: vec![1, 2, 3, 4, 5, 6],
+        ..Default::default()
+    };
+    let bytes = image.encode_vec().unwrap();
+    assert_eq!(bytes, copied.encode_vec().unwrap());
+    assert_eq!(
+        msg::sensor_msgs::Image::decode_slice(&bytes).unwrap(),
+        image
+    );
+}
+
+#[test]
+fn decoded_arrays_point_into_frame() {
+    let frame = Arc::new(image().encode_vec().unwrap());
+    let decoded = msg::sensor_msgs::Image::decode_shared(&frame).unwrap();
+    assert_eq!(decoded, image());
+    assert!(decoded.data.is_shared_with_frame());
+    let frame_range = frame.as_ptr_range();
+    assert!(frame_range.contains(&decoded.data.as_ptr()));
+    assert_eq!(&decoded.data.slice(4..)[..], &[5, 6]);
+}
+
+#[test]
+fn nested_arrays_are_decoded_from_frame() {
+    let cloud = msg::sensor_msgs::PointCloud {
+        channels: vec![msg::sensor_msgs::ChannelFloat32 {
+            name: "intensity".into(),
+            values: vec![0.5, 1.5, 2.5].into(),
+        }],
+        ..Default::default()
+    };
+    let frame = Arc::new(cloud.encode_vec().unwrap());
+    let decoded = msg::sensor_msgs::PointCloud::decode_shared(&frame).unwrap();
+    assert_eq!(decoded, cloud);
+    assert_eq!(&decoded.channels[0].values[..], &[0.5, 1.5, 2.5]);
+}
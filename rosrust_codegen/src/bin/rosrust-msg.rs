@@ -0,0 +1,74 @@
+//! Shows messages and services the way rosrust sees them, without needing ROS installed.
+
+use rosrust_codegen::Inspector;
+use std::env;
+use std::process;
+
+const USAGE: &str = "Usage: rosrust-msg [--path <folder>]... <command>
+
+Searches CMAKE_PREFIX_PATH, ROS_PACKAGE_PATH, ROSRUST_MSG_PATH and all passed folders.
+
+Commands:
+    show <type>          Show fields of a message, service or action, with nested messages expanded
+    md5 <type>           Show the MD5 sum of a message or service
+    definition <type>    Show the message_definition sent in connection headers
+    packages             List all packages with messages, services or actions
+    list [package]       List all messages, services and actions
+    lint [package]       Check definition files for problems";
+
+fn main() {
+    let mut inspector = Inspector::new().ros_environment_paths();
+    let mut args = env::args().skip(1);
+    let mut positional = vec![];
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-p" | "--path" => match args.next() {
+                Some(path) => inspector = inspector.search_path(path),
+                None => fail("Missing folder after --path"),
+            },
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ => positional.push(arg),
+        }
+    }
+    let positional = positional.iter().map(String::as_str).collect::<Vec<_>>();
+    let result = match positional.as_slice() {
+        ["show", name] => inspector.show(name),
+        ["md5", name] => inspector.md5(name).map(|v| format!("{}\n", v)),
+        ["definition", name] => inspector.definition(name),
+        ["packages"] => Ok(lines(inspector.packages())),
+        ["list"] => Ok(lines(inspector.list(None))),
+        ["list", package] => Ok(lines(inspector.list(Some(package)))),
+        ["lint"] | ["lint", _] => {
+            let lints = inspector.lint(positional.get(1).copied());
+            for lint in &lints {
+                println!("{}", lint);
+            }
+            if !lints.is_empty() {
+                process::exit(1);
+            }
+            return;
+        }
+        _ => fail(USAGE),
+    };
+    match result {
+        Ok(output) => print!("{}", output),
+        Err(err) => fail(
+            &err.iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("\nCaused by: "),
+        ),
+    }
+}
+
+fn lines(items: Vec<String>) -> String {
+    items.into_iter().map(|v| v + "\n").collect()
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(2)
+}
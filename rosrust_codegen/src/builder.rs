@@ -0,0 +1,503 @@
+use crate::error::{Result, ResultExt};
+use crate::genmsg;
+use crate::helpers::{self, MessageType};
+use crate::output_layout::Options;
+use quote::{quote, ToTokens};
+use ros_message::MessagePath;
+use std::collections::BTreeSet;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use syn::Ident;
+
+/// Generates message and service code into `.rs` files, meant for use in build scripts.
+///
+/// The output directory gets a `mod.rs` file with one module per package, and one
+/// `<package>.rs` file per package. The generated code can be included from `OUT_DIR`:
+///
+/// ```ignore
+/// mod msg {
+///     include!(concat!(env!("OUT_DIR"), "/msg/mod.rs"));
+/// }
+/// ```
+///
+/// or written to a checked-in directory and declared with `mod msg;`.
+///
+/// # Examples
+///
+/// ```no_run
+/// rosrust_codegen::Builder::new()
+///     .ros_environment_paths()
+///     .message("geometry_msgs/PoseStamped")
+///     .package("std_srvs")
+///     .generate()
+///     .unwrap();
+/// ```
+pub struct Builder {
+    search_paths: Vec<String>,
+    messages: BTreeSet<String>,
+    packages: BTreeSet<String>,
+    all_packages: bool,
+    excluded_packages: BTreeSet<String>,
+    ignore_bad_messages: bool,
+    crate_prefix: String,
+    actionlib_prefix: Option<String>,
+    serde: bool,
+    enums: bool,
+    zero_copy: bool,
+    schema: bool,
+    registry: bool,
+    out_dir: Option<PathBuf>,
+    rustfmt: bool,
+    rerun_if_changed: bool,
+    environment_variables: BTreeSet<&'static str>,
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self {
+            search_paths: Vec::new(),
+            messages: BTreeSet::new(),
+            packages: BTreeSet::new(),
+            all_packages: false,
+            excluded_packages: BTreeSet::new(),
+            ignore_bad_messages: false,
+            crate_prefix: "rosrust".into(),
+            actionlib_prefix: None,
+            serde: false,
+            enums: false,
+            zero_copy: false,
+            schema: false,
+            registry: false,
+            out_dir: None,
+            rustfmt: true,
+            rerun_if_changed: true,
+            environment_variables: BTreeSet::new(),
+        }
+    }
+}
+
+impl Builder {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a folder that gets searched recursively for `<package>/msg/*.msg`,
+    /// `<package>/srv/*.srv` and `<package>/action/*.action` files.
+    pub fn search_path(mut self, path: impl AsRef<Path>) -> Self {
+        self.search_paths
+            .push(path.as_ref().to_string_lossy().into_owned());
+        self
+    }
+
+    /// Adds the same folders that `rosmsg_include!` searches, based on `CMAKE_PREFIX_PATH`,
+    /// `ROS_PACKAGE_PATH` and `ROSRUST_MSG_PATH`.
+    pub fn ros_environment_paths(mut self) -> Self {
+        self.search_paths.extend(helpers::ros_environment_paths());
+        self.environment_variables
+            .extend(helpers::ROS_ENVIRONMENT_VARIABLES.iter().copied());
+        self
+    }
+
+    /// Generates a message or service, provided as `package/Name`, with all its dependencies.
+    pub fn message(mut self, message: impl Into<String>) -> Self {
+        self.messages.insert(message.into());
+        self
+    }
+
+    pub fn messages<I, S>(mut self, messages: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.messages.extend(messages.into_iter().map(Into::into));
+        self
+    }
+
+    /// Generates all messages and services of a package found in the search paths.
+    pub fn package(mut self, package: impl Into<String>) -> Self {
+        self.packages.insert(package.into());
+        self
+    }
+
+    /// Generates all messages and services found in the search paths.
+    pub fn all_packages(mut self) -> Self {
+        self.all_packages = true;
+        self
+    }
+
+    /// Skips the package when selecting messages through `package` and `all_packages`.
+    ///
+    /// Messages of the package are still generated if other messages depend on them.
+    pub fn exclude_package(mut self, package: impl Into<String>) -> Self {
+        self.excluded_packages.insert(package.into());
+        self
+    }
+
+    /// Replaces messages that fail to parse with empty messages, instead of failing.
+    pub fn ignore_bad_messages(mut self, ignore_bad_messages: bool) -> Self {
+        self.ignore_bad_messages = ignore_bad_messages;
+        self
+    }
+
+    /// Path of the crate that provides the rosrust API, `rosrust` by default.
+    pub fn crate_prefix(mut self, crate_prefix: impl Into<String>) -> Self {
+        self.crate_prefix = crate_prefix.into();
+        self
+    }
+
+    /// Implements the `rosrust_actionlib` traits for actions, using the given path to the crate.
+    pub fn actionlib(mut self, actionlib_prefix: impl Into<String>) -> Self {
+        self.actionlib_prefix = Some(actionlib_prefix.into());
+        self
+    }
+
+    /// Derives `Serialize` and `Deserialize` on messages, keeping ROS field names.
+    pub fn serde(mut self, serde: bool) -> Self {
+        self.serde = serde;
+        self
+    }
+
+    /// Generates an enum for each group of integer constants that enumerate the values
+    /// of a field, along with typed accessors for the field.
+    ///
+    /// Constants named `<FIELD>_<VALUE>`, such as `STATUS_FIX` for the `status` field,
+    /// form a group. Enums are named `<Message><Field>`, and skipped if that name is
+    /// taken by another type of the package.
+    pub fn enums(mut self, enums: bool) -> Self {
+        self.enums = enums;
+        self
+    }
+
+    /// Represents arrays of numbers as `rosrust::SharedSlice` instead of `Vec`.
+    ///
+    /// Subscribers decode these arrays without copying, pointing into the received data.
+    pub fn zero_copy(mut self, zero_copy: bool) -> Self {
+        self.zero_copy = zero_copy;
+        self
+    }
+
+    /// Implements `rosrust::MessageSchema` on messages, which parses their definitions
+    /// at runtime.
+    pub fn schema(mut self, schema: bool) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    /// Adds `register_messages` to `mod.rs`, which adds every generated message to a
+    /// `rosrust::MessageRegistry`.
+    ///
+    /// Registered messages need `MessageSchema`, so this implies `schema`.
+    pub fn registry(mut self, registry: bool) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// Directory to write files into, `$OUT_DIR/msg` by default.
+    pub fn out_dir(mut self, out_dir: impl Into<PathBuf>) -> Self {
+        self.out_dir = Some(out_dir.into());
+        self
+    }
+
+    /// Formats the output with `rustfmt` if it is available, enabled by default.
+    pub fn rustfmt(mut self, rustfmt: bool) -> Self {
+        self.rustfmt = rustfmt;
+        self
+    }
+
+    /// Prints `cargo:rerun-if-changed` lines for the search paths, enabled by default.
+    ///
+    /// With `ros_environment_paths`, it also prints `cargo:rerun-if-env-changed` lines for
+    /// the environment variables the paths come from.
+    pub fn rerun_if_changed(mut self, rerun_if_changed: bool) -> Self {
+        self.rerun_if_changed = rerun_if_changed;
+        self
+    }
+
+    /// Writes the generated code and returns paths of all the written files.
+    pub fn generate(&self) -> Result<Vec<PathBuf>> {
+        let out_dir = match &self.out_dir {
+            Some(out_dir) => out_dir.clone(),
+            None => Path::new(
+                &std::env::var("OUT_DIR")
+                    .chain_err(|| "OUT_DIR is not set, set out_dir instead")?,
+            )
+            .join("msg"),
+        };
+        let search_paths = self.search_paths();
+        if self.rerun_if_changed {
+            for key in &self.environment_variables {
+                println!("cargo:rerun-if-env-changed={}", key);
+            }
+            // Cargo reruns build scripts every time if a watched path does not exist
+            for path in search_paths.iter().filter(|v| Path::new(v).exists()) {
+                println!("cargo:rerun-if-changed={}", path);
+            }
+        }
+        let messages = self.selection(&search_paths);
+        let message_refs = messages.iter().map(String::as_str).collect::<Vec<&str>>();
+        let mut layout =
+            genmsg::depend_on_messages(self.ignore_bad_messages, &search_paths, &message_refs)?;
+
+        // Sorting keeps the output stable between runs
+        layout.packages.sort_by(|a, b| a.name.cmp(&b.name));
+        for package in &mut layout.packages {
+            package.messages.sort_by(|a, b| a.msg_type.cmp(&b.msg_type));
+            package.services.sort_by(|a, b| a.msg_type.cmp(&b.msg_type));
+            package.actions.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+
+        let crate_prefix = parse_prefix(&self.crate_prefix)?;
+        let options = Options {
+            actionlib_prefix: match &self.actionlib_prefix {
+                Some(prefix) => Some(parse_prefix(prefix)?),
+                None => None,
+            },
+            serde: self.serde,
+            enums: self.enums,
+            zero_copy: self.zero_copy,
+            schema: self.schema,
+            registry: self.registry,
+        };
+
+        fs::create_dir_all(&out_dir)?;
+        let mut written = vec![];
+        let mut modules = vec![];
+        for package in &layout.packages {
+            let file_name = format!("{}.rs", package.name);
+            let contents = package.contents_token_stream(&crate_prefix, &options);
+            let path = out_dir.join(&file_name);
+            self.write(&path, contents)?;
+            written.push(path);
+            let name = Ident::new(&package.name, proc_macro2::Span::call_site());
+            // Lints are not useful in generated code, which can't be fixed by hand
+            modules.push(quote! {
+                #[allow(unused, bare_trait_objects, clippy::all)]
+                pub mod #name {
+                    include!(#file_name);
+                }
+            });
+        }
+        let registry = if self.registry {
+            layout.registry_token_stream(&crate_prefix)
+        } else {
+            quote! {}
+        };
+        let path = out_dir.join("mod.rs");
+        self.write(&path, quote! { #(#modules)* #registry })?;
+        written.push(path);
+        Ok(written)
+    }
+
+    /// Returns the messages and services that `generate` starts from, as `(package, name)`,
+    /// sorted and without their dependencies.
+    ///
+    /// Actions are listed as the messages generated for them, like `FibonacciGoal`.
+    pub fn selected_messages(&self) -> Vec<(String, String)> {
+        self.selection(&self.search_paths())
+            .into_iter()
+            .map(|message| match message.split_once('/') {
+                Some((package, name)) => (package.to_owned(), name.to_owned()),
+                None => (String::new(), message),
+            })
+            .collect()
+    }
+
+    fn search_paths(&self) -> Vec<&str> {
+        self.search_paths.iter().map(String::as_str).collect()
+    }
+
+    fn selection(&self, search_paths: &[&str]) -> BTreeSet<String> {
+        let mut messages = self.messages.clone();
+        for (message, _, message_type) in self.selected_files(search_paths) {
+            match message_type {
+                MessageType::Service => {
+                    // Request and response messages get generated with the service
+                    messages.remove(&format!("{}Req", message));
+                    messages.remove(&format!("{}Res", message));
+                    messages.insert(message.to_string());
+                }
+                // Selecting the generated messages keeps actions apart from services
+                // of the same name, which would be found first
+                MessageType::Action => {
+                    for suffix in helpers::ACTION_MESSAGE_SUFFIXES {
+                        messages.insert(format!("{}{}", message, suffix));
+                    }
+                }
+                MessageType::Message => {
+                    messages.insert(message.to_string());
+                }
+            }
+        }
+        messages
+    }
+
+    /// Finds definition files of the packages selected through `package` and `all_packages`.
+    fn selected_files(&self, search_paths: &[&str]) -> Vec<(MessagePath, PathBuf, MessageType)> {
+        if !self.all_packages && self.packages.is_empty() {
+            return vec![];
+        }
+        search_paths
+            .iter()
+            .flat_map(|path| helpers::find_all_messages_and_services(Path::new(path)))
+            .filter(|(message, ..)| {
+                let package = message.package();
+                !self.excluded_packages.contains(package)
+                    && (self.all_packages || self.packages.contains(package))
+            })
+            .collect()
+    }
+
+    fn write(&self, path: &Path, contents: impl ToTokens) -> Result<()> {
+        let contents = format!(
+            "// Generated by rosrust_codegen. Do not edit.\n\n{}\n",
+            contents.into_token_stream()
+        );
+        fs::write(path, contents)?;
+        if self.rustfmt {
+            format_file(path);
+        }
+        Ok(())
+    }
+}
+
+fn parse_prefix(prefix: &str) -> Result<proc_macro2::TokenStream> {
+    format!("{}::", prefix)
+        .parse()
+        .map_err(|_| format!("Invalid crate prefix: {}", prefix).into())
+}
+
+fn format_file(path: &Path) {
+    let rustfmt = std::env::var_os("RUSTFMT").unwrap_or_else(|| OsStr::new("rustfmt").into());
+    // Formatting is a convenience, so unformatted output is kept if rustfmt is missing
+    let _ = Command::new(rustfmt)
+        .arg("--edition")
+        .arg("2018")
+        .arg(path)
+        .status();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static FILEPATH: &str = "../msg_examples";
+
+    #[test]
+    fn generates_selected_packages_with_dependencies() {
+        let out_dir = tempfile::tempdir().unwrap();
+        let out_dir = out_dir.path();
+        let written = Builder::new()
+            .search_path(FILEPATH)
+            .package("simple_srv")
+            .message("geometry_msgs/PoseStamped")
+            .rustfmt(false)
+            .rerun_if_changed(false)
+            .out_dir(out_dir)
+            .generate()
+            .unwrap();
+        let mut names = written
+            .iter()
+            .map(|v| v.file_name().unwrap().to_str().unwrap().to_owned())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(
+            names,
+            vec!["geometry_msgs.rs", "mod.rs", "simple_srv.rs", "std_msgs.rs"],
+        );
+        let module = fs::read_to_string(out_dir.join("mod.rs")).unwrap();
+        assert!(module.contains("pub mod geometry_msgs"));
+        assert!(module.contains("include ! (\"geometry_msgs.rs\")"));
+        let geometry_msgs = fs::read_to_string(out_dir.join("geometry_msgs.rs")).unwrap();
+        assert!(geometry_msgs.contains("pub struct PoseStamped"));
+        assert!(geometry_msgs.contains("rosrust :: Message for PoseStamped"));
+        assert!(!geometry_msgs.contains("MessageSchema"));
+    }
+
+    #[test]
+    fn lists_selected_messages() {
+        let messages = Builder::new()
+            .search_path(FILEPATH)
+            .package("nav_msgs")
+            .message("geometry_msgs/Point")
+            .selected_messages();
+        let has = |package: &str, name: &str| messages.contains(&(package.into(), name.into()));
+        assert!(has("geometry_msgs", "Point"));
+        assert!(has("nav_msgs", "OccupancyGrid"));
+        assert!(has("nav_msgs", "GetMap"));
+        assert!(has("nav_msgs", "GetMapGoal"));
+        assert!(has("nav_msgs", "GetMapActionFeedback"));
+        assert!(!has("nav_msgs", "GetMapReq"));
+        assert!(!has("std_msgs", "Header"));
+    }
+
+    #[test]
+    fn implements_actions_through_actionlib_macro() {
+        let out_dir = tempfile::tempdir().unwrap();
+        let out_dir = out_dir.path();
+        Builder::new()
+            .search_path(FILEPATH)
+            .message("nav_msgs/GetMapAction")
+            .actionlib("my_actionlib")
+            .rustfmt(false)
+            .rerun_if_changed(false)
+            .out_dir(out_dir)
+            .generate()
+            .unwrap();
+        let nav_msgs = fs::read_to_string(out_dir.join("nav_msgs.rs")).unwrap();
+        assert!(nav_msgs.contains("my_actionlib :: action ! (super ; nav_msgs : GetMap) ;"));
+    }
+
+    #[test]
+    fn skips_enums_named_like_other_types() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        let folder = root.join("clash_msgs").join("msg");
+        fs::create_dir_all(&folder).unwrap();
+        fs::write(
+            folder.join("NavSat.msg"),
+            "uint8 STATUS_OK=0\nuint8 status\n",
+        )
+        .unwrap();
+        fs::write(folder.join("NavSatStatus.msg"), "int32 x\n").unwrap();
+        fs::write(folder.join("Joy.msg"), "uint8 MODE_OFF=0\nuint8 mode\n").unwrap();
+        let out_dir = root.join("out");
+        Builder::new()
+            .search_path(root)
+            .package("clash_msgs")
+            .enums(true)
+            .rustfmt(false)
+            .rerun_if_changed(false)
+            .out_dir(&out_dir)
+            .generate()
+            .unwrap();
+        let clash_msgs = fs::read_to_string(out_dir.join("clash_msgs.rs")).unwrap();
+        assert!(clash_msgs.contains("pub enum JoyMode"));
+        assert!(clash_msgs.contains("pub struct NavSatStatus"));
+        assert!(!clash_msgs.contains("pub enum NavSatStatus"));
+    }
+
+    #[test]
+    fn registers_generated_messages_in_module() {
+        let out_dir = tempfile::tempdir().unwrap();
+        let out_dir = out_dir.path();
+        Builder::new()
+            .search_path(FILEPATH)
+            .message("geometry_msgs/PoseStamped")
+            .registry(true)
+            .rustfmt(false)
+            .rerun_if_changed(false)
+            .out_dir(out_dir)
+            .generate()
+            .unwrap();
+        let module = fs::read_to_string(out_dir.join("mod.rs")).unwrap();
+        assert!(module.contains("pub fn register_messages"));
+        assert!(module.contains("register :: < geometry_msgs :: PoseStamped > ()"));
+        assert!(module.contains("register :: < std_msgs :: Header > ()"));
+        // Registered messages need their schema
+        let geometry_msgs = fs::read_to_string(out_dir.join("geometry_msgs.rs")).unwrap();
+        assert!(geometry_msgs.contains("rosrust :: MessageSchema for PoseStamped"));
+    }
+}
@@ -1,4 +1,7 @@
 error_chain::error_chain! {
+    foreign_links {
+        Io(::std::io::Error);
+    }
     errors {
         MessageNotFound(msg: String, folders: String) {
             description("message not found in provided directories")
@@ -50,6 +50,7 @@ fn message_map_to_layout(message_map: &MessageMap) -> Result<output_layout::Layo
             name: package.clone(),
             messages: Vec::new(),
             services: Vec::new(),
+            actions: Vec::new(),
         };
         let names = message_map
             .messages
@@ -98,6 +99,14 @@ fn message_map_to_layout(message_map: &MessageMap) -> Result<output_layout::Layo
                 source,
             })
         }
+        package_data.actions = message_map
+            .actions
+            .iter()
+            .filter(|action| action.package() == package)
+            .map(|action| output_layout::Action {
+                name: action.name().into(),
+            })
+            .collect();
         output.packages.push(package_data);
     }
     Ok(output)
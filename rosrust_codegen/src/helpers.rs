@@ -5,9 +5,56 @@ use error_chain::bail;
 use lazy_static::lazy_static;
 use ros_message::MessagePath;
 use std::collections::{HashMap, HashSet, LinkedList};
+use std::env;
 use std::fs::{read_dir, File};
 use std::path::{Path, PathBuf};
 
+/// Folders searched for messages, based on the ROS environment variables and `ROSRUST_MSG_PATH`.
+pub fn ros_environment_paths() -> Vec<String> {
+    let cmake_paths = env_paths("CMAKE_PREFIX_PATH")
+        .iter()
+        .filter_map(|v| append_share_folder(v))
+        .collect::<Vec<String>>();
+    let cmake_alt_paths = env_paths("CMAKE_PREFIX_PATH")
+        .iter()
+        .filter_map(|v| append_src_folder(v))
+        .collect::<Vec<String>>();
+    let ros_package_paths = env_paths("ROS_PACKAGE_PATH");
+    let extra_paths = env_paths("ROSRUST_MSG_PATH");
+    cmake_paths
+        .into_iter()
+        .chain(cmake_alt_paths)
+        .chain(ros_package_paths)
+        .chain(extra_paths)
+        .collect()
+}
+
+/// Environment variables that `ros_environment_paths` reads.
+pub const ROS_ENVIRONMENT_VARIABLES: &[&str] =
+    &["CMAKE_PREFIX_PATH", "ROS_PACKAGE_PATH", "ROSRUST_MSG_PATH"];
+
+/// Splits a `:` separated list of paths, skipping empty entries.
+fn env_paths(key: &str) -> Vec<String> {
+    env::var(key)
+        .unwrap_or_default()
+        .split(':')
+        .filter(|v| !v.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn append_share_folder(path: &str) -> Option<String> {
+    Path::new(path).join("share").to_str().map(String::from)
+}
+
+fn append_src_folder(path: &str) -> Option<String> {
+    Path::new(path)
+        .join("..")
+        .join("src")
+        .to_str()
+        .map(String::from)
+}
+
 pub fn calculate_md5(message_map: &MessageMap) -> Result<HashMap<MessagePath, String>> {
     let mut representations = HashMap::<MessagePath, String>::new();
     let mut hashes = HashMap::<MessagePath, String>::new();
@@ -90,6 +137,7 @@ pub fn generate_message_definition<S: std::hash::BuildHasher>(
 pub struct MessageMap {
     pub messages: HashMap<MessagePath, Msg>,
     pub services: HashMap<MessagePath, Srv>,
+    pub actions: HashSet<MessagePath>,
 }
 
 pub fn get_message_map(
@@ -99,6 +147,7 @@ pub fn get_message_map(
 ) -> Result<MessageMap> {
     let mut message_locations = HashMap::new();
     let mut service_locations = HashMap::new();
+    let mut action_locations = HashMap::new();
 
     let mut messages_and_services = vec![];
     for folder in folders {
@@ -109,14 +158,16 @@ pub fn get_message_map(
         match message_type {
             MessageType::Message => message_locations.insert(message_path, file_path),
             MessageType::Service => service_locations.insert(message_path, file_path),
+            MessageType::Action => action_locations.insert(message_path, file_path),
         };
     }
 
     let mut messages = HashMap::new();
     let mut services = HashMap::new();
+    let mut actions = HashSet::new();
     let mut pending = message_paths.to_vec();
     while let Some(message_path) = pending.pop() {
-        if messages.contains_key(&message_path) {
+        if messages.contains_key(&message_path) || actions.contains(&message_path) {
             continue;
         }
         match get_message_or_service(
@@ -124,6 +175,7 @@ pub fn get_message_map(
             folders,
             &message_locations,
             &service_locations,
+            &action_locations,
             message_path,
         )? {
             MessageCase::Message(message) => {
@@ -143,30 +195,56 @@ pub fn get_message_map(
                 messages.insert(res.0.path().clone(), res);
                 services.insert(service.path.clone(), service);
             }
+            MessageCase::Action(action, action_messages) => {
+                for message in action_messages {
+                    for dependency in message.dependencies() {
+                        pending.push(dependency);
+                    }
+                    messages.insert(message.0.path().clone(), message);
+                }
+                actions.insert(action);
+            }
         }
     }
-    Ok(MessageMap { messages, services })
+    Ok(MessageMap {
+        messages,
+        services,
+        actions,
+    })
 }
 
-enum MessageType {
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MessageType {
     Message,
     Service,
+    Action,
+}
+
+pub fn find_all_messages_and_services(root: &Path) -> Vec<(MessagePath, PathBuf, MessageType)> {
+    find_all_definition_files(root)
+        .into_iter()
+        .filter_map(|(package, name, file, message_type)| {
+            Some((MessagePath::new(package, name).ok()?, file, message_type))
+        })
+        .collect()
 }
 
-fn find_all_messages_and_services(root: &Path) -> Vec<(MessagePath, PathBuf, MessageType)> {
+/// Finds all `<package>/<msg|srv|action>/<Name>.<msg|srv|action>` files, even if the package
+/// name does not follow REP 144.
+pub fn find_all_definition_files(root: &Path) -> Vec<(String, String, PathBuf, MessageType)> {
     if !root.is_dir() {
-        return identify_message_or_service(root).into_iter().collect();
+        return identify_definition_file(root).into_iter().collect();
     }
     let mut items = vec![];
     if let Ok(children) = read_dir(root) {
         for child in children.filter_map(|v| v.ok()) {
-            items.append(&mut find_all_messages_and_services(&child.path()));
+            items.append(&mut find_all_definition_files(&child.path()));
         }
     }
     items
 }
 
-fn identify_message_or_service(filename: &Path) -> Option<(MessagePath, PathBuf, MessageType)> {
+fn identify_definition_file(filename: &Path) -> Option<(String, String, PathBuf, MessageType)> {
     let extension = filename.extension()?;
     let message = filename.file_stem()?;
     let parent = filename.parent()?;
@@ -178,10 +256,12 @@ fn identify_message_or_service(filename: &Path) -> Option<(MessagePath, PathBuf,
     let message_type = match extension.to_str() {
         Some("msg") => MessageType::Message,
         Some("srv") => MessageType::Service,
+        Some("action") => MessageType::Action,
         _ => return None,
     };
     Some((
-        MessagePath::new(package.to_str()?, message.to_str()?).ok()?,
+        package.to_str()?.into(),
+        message.to_str()?.into(),
         filename.into(),
         message_type,
     ))
@@ -190,11 +270,25 @@ fn identify_message_or_service(filename: &Path) -> Option<(MessagePath, PathBuf,
 enum MessageCase {
     Message(Msg),
     Service(Srv, Msg, Msg),
+    Action(MessagePath, Vec<Msg>),
 }
 
+/// Suffixes of messages that genaction generates for each action.
+pub const ACTION_MESSAGE_SUFFIXES: &[&str] = &[
+    "ActionFeedback",
+    "ActionResult",
+    "ActionGoal",
+    "Action",
+    "Feedback",
+    "Result",
+    "Goal",
+];
+
 lazy_static! {
     static ref IN_MEMORY_MESSAGES: HashMap<MessagePath, &'static str> =
         generate_in_memory_messages();
+    static ref IN_MEMORY_SERVICES: HashMap<MessagePath, &'static str> =
+        generate_in_memory_services();
 }
 
 fn generate_in_memory_messages() -> HashMap<MessagePath, &'static str> {
@@ -211,6 +305,23 @@ fn generate_in_memory_messages() -> HashMap<MessagePath, &'static str> {
         MessagePath::new("std_msgs", "Header").expect(MESSAGE_NAME_SHOULD_BE_VALID),
         include_str!("in_memory_messages/Header.msg"),
     );
+    output.insert(
+        MessagePath::new("roscpp", "Logger").expect(MESSAGE_NAME_SHOULD_BE_VALID),
+        include_str!("in_memory_messages/Logger.msg"),
+    );
+    output
+}
+
+fn generate_in_memory_services() -> HashMap<MessagePath, &'static str> {
+    let mut output = HashMap::new();
+    output.insert(
+        MessagePath::new("roscpp", "GetLoggers").expect(MESSAGE_NAME_SHOULD_BE_VALID),
+        include_str!("in_memory_messages/GetLoggers.srv"),
+    );
+    output.insert(
+        MessagePath::new("roscpp", "SetLoggerLevel").expect(MESSAGE_NAME_SHOULD_BE_VALID),
+        include_str!("in_memory_messages/SetLoggerLevel.srv"),
+    );
     output
 }
 
@@ -219,6 +330,7 @@ fn get_message_or_service(
     folders: &[&str],
     message_locations: &HashMap<MessagePath, PathBuf>,
     service_locations: &HashMap<MessagePath, PathBuf>,
+    action_locations: &HashMap<MessagePath, PathBuf>,
     path: MessagePath,
 ) -> Result<MessageCase> {
     use std::io::Read;
@@ -228,7 +340,8 @@ fn get_message_or_service(
             let mut contents = String::new();
             f.read_to_string(&mut contents)
                 .chain_err(|| "Failed to read file to string!")?;
-            return create_message(path, &contents, ignore_bad_messages).map(MessageCase::Message);
+            return create_message(path, &contents, Some(full_path), ignore_bad_messages)
+                .map(MessageCase::Message);
         }
     }
     if let Some(full_path) = service_locations.get(&path) {
@@ -236,30 +349,28 @@ fn get_message_or_service(
             let mut contents = String::new();
             f.read_to_string(&mut contents)
                 .chain_err(|| "Failed to read file to string!")?;
-
-            let service = ros_message::Srv::new(path.clone(), &contents)
-                .or_else(|err| {
-                    if ignore_bad_messages {
-                        ros_message::Srv::new(path.clone(), "\n\n---\n\n")
-                    } else {
-                        Err(err)
-                    }
-                })
-                .chain_err(|| "Failed to build service messages")?;
-
-            return Ok(MessageCase::Service(
-                Srv {
-                    path: service.path().clone(),
-                    source: service.source().into(),
-                },
-                Msg(service.request().clone()),
-                Msg(service.response().clone()),
-            ));
+            return create_service(path, &contents, Some(full_path), ignore_bad_messages);
         }
     }
+    let action_path = Some(path.clone())
+        .into_iter()
+        .chain(ACTION_MESSAGE_SUFFIXES.iter().filter_map(|suffix| {
+            let name = path.name().strip_suffix(suffix)?;
+            MessagePath::new(path.package(), name).ok()
+        }))
+        .find(|action_path| action_locations.contains_key(action_path));
+    if let Some(action_path) = action_path {
+        let contents = std::fs::read_to_string(&action_locations[&action_path])
+            .chain_err(|| "Failed to read file to string!")?;
+        let full_path = &action_locations[&action_path];
+        return create_action(action_path, &contents, Some(full_path), ignore_bad_messages);
+    }
     if let Some(contents) = IN_MEMORY_MESSAGES.get(&path) {
         return Msg::new(path, contents).map(MessageCase::Message);
     }
+    if let Some(contents) = IN_MEMORY_SERVICES.get(&path) {
+        return create_service(path, contents, None, ignore_bad_messages);
+    }
     if ignore_bad_messages {
         return Msg::new(path, "").map(MessageCase::Message);
     }
@@ -269,14 +380,78 @@ fn get_message_or_service(
     ))
 }
 
-fn create_message(message: MessagePath, contents: &str, ignore_bad_messages: bool) -> Result<Msg> {
-    Msg::new(message.clone(), contents).or_else(|err| {
-        if ignore_bad_messages {
-            Msg::new(message, "")
-        } else {
-            Err(err)
-        }
-    })
+fn create_service(
+    path: MessagePath,
+    contents: &str,
+    file: Option<&Path>,
+    ignore_bad_messages: bool,
+) -> Result<MessageCase> {
+    let service = ros_message::Srv::new(path.clone(), contents)
+        .or_else(|err| {
+            if ignore_bad_messages {
+                ros_message::Srv::new(path, "\n\n---\n\n")
+            } else {
+                Err(in_file(err, file))
+            }
+        })
+        .chain_err(|| "Failed to build service messages")?;
+
+    Ok(MessageCase::Service(
+        Srv {
+            path: service.path().clone(),
+            source: service.source().into(),
+        },
+        Msg(service.request().clone()),
+        Msg(service.response().clone()),
+    ))
+}
+
+fn create_action(
+    path: MessagePath,
+    contents: &str,
+    file: Option<&Path>,
+    ignore_bad_messages: bool,
+) -> Result<MessageCase> {
+    let action = ros_message::Action::new(path.clone(), contents)
+        .or_else(|err| {
+            if ignore_bad_messages {
+                ros_message::Action::new(path, "\n---\n---\n")
+            } else {
+                Err(in_file(err, file))
+            }
+        })
+        .chain_err(|| "Failed to build action messages")?;
+
+    Ok(MessageCase::Action(
+        action.path().clone(),
+        action.messages().iter().map(|&v| Msg(v.clone())).collect(),
+    ))
+}
+
+fn create_message(
+    message: MessagePath,
+    contents: &str,
+    file: Option<&Path>,
+    ignore_bad_messages: bool,
+) -> Result<Msg> {
+    ros_message::Msg::new(message.clone(), contents)
+        .or_else(|err| {
+            if ignore_bad_messages {
+                ros_message::Msg::new(message, "")
+            } else {
+                Err(in_file(err, file))
+            }
+        })
+        .map(Msg)
+        .chain_err(|| "Failed to parse message")
+}
+
+/// Makes parse errors point at the file on disk instead of the message name.
+fn in_file(err: ros_message::Error, file: Option<&Path>) -> ros_message::Error {
+    match file {
+        Some(file) => err.with_file(file.display().to_string()),
+        None => err,
+    }
 }
 
 #[cfg(test)]
@@ -286,6 +461,15 @@ mod tests {
 
     static FILEPATH: &str = "../msg_examples";
 
+    #[test]
+    fn env_paths_skip_empty_entries() {
+        env::set_var("ROSRUST_CODEGEN_TEST_PATHS", ":/a::/b:");
+        assert_eq!(env_paths("ROSRUST_CODEGEN_TEST_PATHS"), vec!["/a", "/b"]);
+        env::set_var("ROSRUST_CODEGEN_TEST_PATHS", "");
+        assert!(env_paths("ROSRUST_CODEGEN_TEST_PATHS").is_empty());
+        assert!(env_paths("ROSRUST_CODEGEN_TEST_MISSING_PATHS").is_empty());
+    }
+
     #[test]
     fn get_message_map_fetches_leaf_message() {
         let message_map = get_message_map(
@@ -361,6 +545,32 @@ mod tests {
         assert!(message_map.contains_key(&MessagePath::new("rosgraph_msgs", "Log").unwrap()));
     }
 
+    #[test]
+    fn in_memory_logger_services_have_reference_md5() {
+        let message_map = get_message_map(
+            false,
+            &[],
+            &[
+                MessagePath::new("roscpp", "GetLoggers").unwrap(),
+                MessagePath::new("roscpp", "SetLoggerLevel").unwrap(),
+            ],
+        )
+        .unwrap();
+        let hashes = calculate_md5(&message_map).unwrap();
+        assert_eq!(
+            *hashes
+                .get(&MessagePath::new("roscpp", "GetLoggers").unwrap())
+                .unwrap(),
+            "32e97e85527d4678a8f9279894bb64b0".to_owned()
+        );
+        assert_eq!(
+            *hashes
+                .get(&MessagePath::new("roscpp", "SetLoggerLevel").unwrap())
+                .unwrap(),
+            "51da076440d78ca1684d36c868df61ea".to_owned()
+        );
+    }
+
     #[test]
     fn calculate_md5_works() {
         let message_map = get_message_map(
@@ -547,6 +757,37 @@ float64 w\n\
         );
     }
 
+    #[test]
+    fn calculate_md5_works_for_actions() {
+        let message_map = get_message_map(
+            false,
+            &[FILEPATH],
+            &[MessagePath::new("nav_msgs", "GetMapActionGoal").unwrap()],
+        )
+        .unwrap();
+        assert_eq!(
+            message_map.actions,
+            [MessagePath::new("nav_msgs", "GetMap").unwrap()]
+                .iter()
+                .cloned()
+                .collect(),
+        );
+        let hashes = calculate_md5(&message_map).unwrap();
+        let expected = [
+            ("GetMapGoal", "d41d8cd98f00b204e9800998ecf8427e"),
+            ("GetMapResult", "6cdd0a18e0aff5b0a3ca2326a89b54ff"),
+            ("GetMapAction", "e611ad23fbf237c031b7536416dc7cd7"),
+        ];
+        for (name, md5sum) in &expected {
+            assert_eq!(
+                hashes.get(&MessagePath::new("nav_msgs", *name).unwrap()),
+                Some(&md5sum.to_string()),
+                "MD5 sum mismatch for {}",
+                name,
+            );
+        }
+    }
+
     #[test]
     fn parse_tricky_srv_files() {
         get_message_map(
@@ -560,4 +801,29 @@ float64 w\n\
         )
         .unwrap();
     }
+
+    #[test]
+    fn parse_errors_point_into_message_files() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        let folder = root.join("bad_msgs").join("msg");
+        std::fs::create_dir_all(&folder).unwrap();
+        let file = folder.join("Broken.msg");
+        std::fs::write(&file, "int32 a\nfloat64[x] b\nuint8 C=256\n").unwrap();
+
+        let err = get_message_map(
+            false,
+            &[root.to_str().unwrap()],
+            &[MessagePath::new("bad_msgs", "Broken").unwrap()],
+        )
+        .err()
+        .unwrap();
+        let message = err.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+        assert_eq!(message.len(), 2);
+        let location = format!("{}:", file.display());
+        assert!(message[1].starts_with(&format!("{}2:1: ", location)));
+        assert!(message[1].contains(&format!("{}3:9: ", location)));
+        assert!(message[1].contains("3 | uint8 C=256\n  |         ^^^"));
+    }
 }
@@ -0,0 +1,516 @@
+use crate::error::{Result, ResultExt};
+use crate::helpers::{self, MessageMap, MessageType};
+use error_chain::bail;
+use ros_message::{is_valid_package_name, Action, FieldCase, MessagePath, Msg, Srv};
+use std::collections::{BTreeSet, HashMap};
+use std::convert::TryInto;
+use std::fmt::{self, Display, Formatter, Write};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Looks up messages and services the same way the code generator does, like `rosmsg` does
+/// with `genmsg`.
+///
+/// # Examples
+///
+/// ```no_run
+/// let inspector = rosrust_codegen::Inspector::new().ros_environment_paths();
+/// println!("{}", inspector.show("geometry_msgs/PoseStamped").unwrap());
+/// println!("{}", inspector.md5("geometry_msgs/PoseStamped").unwrap());
+/// for lint in inspector.lint(Some("geometry_msgs")) {
+///     println!("{}", lint);
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Inspector {
+    search_paths: Vec<String>,
+}
+
+/// Problem found in a message, service or action definition file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lint {
+    /// Definition file with the problem.
+    pub file: PathBuf,
+    /// Line of the problem, starting from 1, if it concerns a single line.
+    pub line: Option<usize>,
+    /// Description of the problem, without the file and line.
+    pub message: String,
+}
+
+impl Inspector {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a folder that gets searched recursively for `<package>/msg/*.msg`,
+    /// `<package>/srv/*.srv` and `<package>/action/*.action` files.
+    pub fn search_path(mut self, path: impl AsRef<Path>) -> Self {
+        self.search_paths
+            .push(path.as_ref().to_string_lossy().into_owned());
+        self
+    }
+
+    /// Adds the same folders that `rosmsg_include!` searches, based on `CMAKE_PREFIX_PATH`,
+    /// `ROS_PACKAGE_PATH` and `ROSRUST_MSG_PATH`.
+    pub fn ros_environment_paths(mut self) -> Self {
+        self.search_paths.extend(helpers::ros_environment_paths());
+        self
+    }
+
+    /// Returns all packages with definition files, sorted by name.
+    pub fn packages(&self) -> Vec<String> {
+        self.definition_files()
+            .into_iter()
+            .map(|(package, ..)| package)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Returns all messages, services and actions as `package/Name`, sorted by name.
+    pub fn list(&self, package: Option<&str>) -> Vec<String> {
+        self.definition_files()
+            .into_iter()
+            .filter(|(v, ..)| package.is_none() || package == Some(v.as_str()))
+            .map(|(package, name, ..)| format!("{}/{}", package, name))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Returns the fields of a message, service or action, with nested messages expanded.
+    ///
+    /// Actions show their goal, result and feedback, in the order of the action file.
+    pub fn show(&self, name: &str) -> Result<String> {
+        let (message_map, path) = self.load(name)?;
+        let mut output = String::new();
+        if message_map.actions.contains(&path) {
+            for (idx, suffix) in ["Goal", "Result", "Feedback"].iter().enumerate() {
+                if idx > 0 {
+                    output += "---\n";
+                }
+                let message = path.peer(format!("{}{}", path.name(), suffix));
+                show_message(
+                    &message_map,
+                    &get_message(&message_map, &message)?.0,
+                    0,
+                    &mut output,
+                )?;
+            }
+        } else if message_map.services.contains_key(&path) {
+            let (req, res) = service_messages(&message_map, &path)?;
+            show_message(&message_map, &req.0, 0, &mut output)?;
+            output += "---\n";
+            show_message(&message_map, &res.0, 0, &mut output)?;
+        } else {
+            show_message(
+                &message_map,
+                &get_message(&message_map, &path)?.0,
+                0,
+                &mut output,
+            )?;
+        }
+        Ok(output)
+    }
+
+    /// Returns the MD5 sum of a message or service, as sent in connection headers.
+    pub fn md5(&self, name: &str) -> Result<String> {
+        let (message_map, path) = self.load(name)?;
+        let mut hashes = helpers::calculate_md5(&message_map)?;
+        match hashes.remove(&path) {
+            Some(hash) => Ok(hash),
+            None => bail!("{} is not a message or service", path),
+        }
+    }
+
+    /// Returns the `message_definition` text sent in connection headers of a message.
+    pub fn definition(&self, name: &str) -> Result<String> {
+        let (message_map, path) = self.load(name)?;
+        if message_map.services.contains_key(&path) {
+            bail!("{} is a service, which has no message definition", path);
+        }
+        let message = get_message(&message_map, &path)?;
+        helpers::generate_message_definition(&message_map.messages, message)
+    }
+
+    /// Checks definition files for problems that ROS tools would complain about,
+    /// or that silently change how the messages get generated.
+    ///
+    /// This covers package names that do not follow REP 144, parse errors, duplicate fields,
+    /// and constants that look like they mean something else.
+    pub fn lint(&self, package: Option<&str>) -> Vec<Lint> {
+        let mut files = self
+            .definition_files()
+            .into_iter()
+            .filter(|(v, ..)| package.is_none() || package == Some(v.as_str()))
+            .collect::<Vec<_>>();
+        files.sort_by(|a, b| a.2.cmp(&b.2));
+        let mut lints = vec![];
+        for (package, name, file, message_type) in files {
+            lint_file(&package, &name, &file, message_type, &mut lints);
+        }
+        lints
+    }
+
+    fn definition_files(&self) -> Vec<(String, String, PathBuf, MessageType)> {
+        self.search_paths
+            .iter()
+            .flat_map(|folder| helpers::find_all_definition_files(Path::new(folder)))
+            .collect()
+    }
+
+    fn load(&self, name: &str) -> Result<(MessageMap, MessagePath)> {
+        let path: MessagePath = name
+            .try_into()
+            .chain_err(|| format!("Bad message name `{}`", name))?;
+        let folders = self
+            .search_paths
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        let message_map = helpers::get_message_map(false, &folders, std::slice::from_ref(&path))?;
+        Ok((message_map, path))
+    }
+}
+
+fn get_message<'a>(message_map: &'a MessageMap, path: &MessagePath) -> Result<&'a crate::msg::Msg> {
+    match message_map.messages.get(path) {
+        Some(message) => Ok(message),
+        None => bail!("{} is not a message or service", path),
+    }
+}
+
+fn service_messages<'a>(
+    message_map: &'a MessageMap,
+    path: &MessagePath,
+) -> Result<(&'a crate::msg::Msg, &'a crate::msg::Msg)> {
+    let req = path.peer(format!("{}Req", path.name()));
+    let res = path.peer(format!("{}Res", path.name()));
+    Ok((
+        get_message(message_map, &req)?,
+        get_message(message_map, &res)?,
+    ))
+}
+
+/// Writes fields like `rosmsg show`, with constants first and nested fields indented.
+fn show_message(
+    message_map: &MessageMap,
+    message: &Msg,
+    depth: usize,
+    output: &mut String,
+) -> Result<()> {
+    let indent = "  ".repeat(depth);
+    for field in message.fields().iter().filter(|v| v.is_constant()) {
+        if let FieldCase::Const(value) = field.case() {
+            let _ = writeln!(
+                output,
+                "{}{} {}={}",
+                indent,
+                field.datatype(),
+                field.name(),
+                value
+            );
+        }
+    }
+    for field in message.fields().iter().filter(|v| !v.is_constant()) {
+        let _ = writeln!(
+            output,
+            "{}{} {}",
+            indent,
+            field.qualified_type(message.path()),
+            field.name()
+        );
+        if let Some(path) = field.datatype().message_path(message.path()) {
+            let nested = get_message(message_map, &path)?;
+            show_message(message_map, &nested.0, depth + 1, output)?;
+        }
+    }
+    Ok(())
+}
+
+fn lint_file(
+    package: &str,
+    name: &str,
+    file: &Path,
+    message_type: MessageType,
+    lints: &mut Vec<Lint>,
+) {
+    let mut lint = |line: Option<usize>, message: String| {
+        lints.push(Lint {
+            file: file.into(),
+            line,
+            message,
+        })
+    };
+    if !is_valid_package_name(package) {
+        lint(
+            None,
+            format!(
+                "package name `{}` does not follow REP 144, so this file is ignored",
+                package,
+            ),
+        );
+        return;
+    }
+    let mut chars = name.chars();
+    if !matches!(chars.next(), Some(c) if c.is_ascii_uppercase())
+        || !chars.all(|c| c.is_ascii_alphanumeric())
+    {
+        lint(None, format!("type name `{}` should be CamelCase", name));
+    }
+    let source = match fs::read_to_string(file) {
+        Ok(source) => source,
+        Err(err) => return lint(None, format!("failed to read file: {}", err)),
+    };
+    let path = match MessagePath::new(package, name) {
+        Ok(path) => path,
+        Err(err) => return lint(None, err.to_string()),
+    };
+    let parsed = match message_type {
+        MessageType::Message => Msg::new(path, &source).map(|_| ()),
+        MessageType::Service => Srv::new(path, source.as_str()).map(|_| ()),
+        MessageType::Action => Action::new(path, &source).map(|_| ()),
+    };
+    if let Err(err) = parsed {
+        let diagnostics = err.diagnostics();
+        if diagnostics.is_empty() {
+            lint(None, err.to_string());
+        }
+        for diagnostic in diagnostics {
+            lint(Some(diagnostic.line()), diagnostic.message().into());
+        }
+        return;
+    }
+    for (line, message) in lint_fields(&source) {
+        lint(Some(line), message);
+    }
+}
+
+/// Checks lines of a definition that is known to parse, section by section.
+fn lint_fields(source: &str) -> Vec<(usize, String)> {
+    let mut lints = vec![];
+    let mut declared = HashMap::new();
+    for (idx, line) in source.split('\n').enumerate() {
+        let line_number = idx + 1;
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if line.starts_with("---") {
+            declared.clear();
+            continue;
+        }
+        let line = line.trim();
+        let is_string_constant = line.starts_with("string") && line.contains('=');
+        let content = if is_string_constant {
+            line
+        } else {
+            line.split('#').next().unwrap_or_default().trim()
+        };
+        let (datatype, rest) = match content.split_once(char::is_whitespace) {
+            Some(v) => v,
+            None => continue,
+        };
+        let (name, value) = match rest.split_once('=') {
+            Some((name, value)) => (name.trim(), Some(value.trim())),
+            None => (rest.trim(), None),
+        };
+        if let Some(previous) = declared.insert(name.to_owned(), line_number) {
+            lints.push((
+                line_number,
+                format!("`{}` is already declared on line {}", name, previous,),
+            ));
+        }
+        let value = match value {
+            Some(value) => value,
+            None => continue,
+        };
+        if name.chars().any(|c| c.is_ascii_lowercase()) {
+            lints.push((
+                line_number,
+                format!("constant `{}` should be UPPER_CASE", name),
+            ));
+        }
+        if datatype == "string" && value.contains('#') {
+            lints.push((
+                line_number,
+                format!(
+                    "`#` is part of the value of string constant `{}`, not the start of a comment",
+                    name,
+                ),
+            ));
+        }
+        if datatype == "string" && value.is_empty() {
+            lints.push((line_number, format!("string constant `{}` is empty", name)));
+        }
+        if matches!(datatype, "float32" | "float64") && !value.contains('.') {
+            lints.push((
+                line_number,
+                format!(
+                    "float constant `{}` has no decimal point, and might be meant as an integer",
+                    name,
+                ),
+            ));
+        }
+    }
+    lints
+}
+
+impl Display for Lint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.file.display(), line, self.message),
+            None => write!(f, "{}: {}", self.file.display(), self.message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static FILEPATH: &str = "../msg_examples";
+
+    #[test]
+    fn show_expands_nested_messages() {
+        let inspector = Inspector::new().search_path(FILEPATH);
+        assert_eq!(
+            inspector.show("geometry_msgs/PoseStamped").unwrap(),
+            "std_msgs/Header header
+  uint32 seq
+  time stamp
+  string frame_id
+geometry_msgs/Pose pose
+  geometry_msgs/Point position
+    float64 x
+    float64 y
+    float64 z
+  geometry_msgs/Quaternion orientation
+    float64 x
+    float64 y
+    float64 z
+    float64 w
+",
+        );
+    }
+
+    #[test]
+    fn show_splits_services_and_actions() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        let folder = root.join("count_msgs").join("action");
+        fs::create_dir_all(&folder).unwrap();
+        fs::write(
+            folder.join("Count.action"),
+            "int32 target\n---\nint32[] sequence\n---\ngeometry_msgs/Point last\n",
+        )
+        .unwrap();
+        let inspector = Inspector::new().search_path(FILEPATH).search_path(root);
+        assert_eq!(
+            inspector.show("count_msgs/Count").unwrap(),
+            "int32 target
+---
+int32[] sequence
+---
+geometry_msgs/Point last
+  float64 x
+  float64 y
+  float64 z
+",
+        );
+        assert_eq!(
+            inspector.show("simple_srv/Something").unwrap(),
+            "geometry_msgs/Vector3 a
+  float64 x
+  float64 y
+  float64 z
+---
+std_msgs/Header b
+  uint32 seq
+  time stamp
+  string frame_id
+geometry_msgs/Pose c
+  geometry_msgs/Point position
+    float64 x
+    float64 y
+    float64 z
+  geometry_msgs/Quaternion orientation
+    float64 x
+    float64 y
+    float64 z
+    float64 w
+",
+        );
+    }
+
+    #[test]
+    fn md5_and_definition_match_generated_code() {
+        let inspector = Inspector::new().search_path(FILEPATH);
+        assert_eq!(
+            inspector.md5("geometry_msgs/Point").unwrap(),
+            "4a842b65f413084dc2b10fb484ea7f17",
+        );
+        let definition = inspector.definition("geometry_msgs/PoseStamped").unwrap();
+        assert!(definition.contains("\nMSG: geometry_msgs/Quaternion\n"));
+        assert!(definition.ends_with('\n'));
+        assert!(inspector.definition("no_msgs/Missing").is_err());
+    }
+
+    #[test]
+    fn lint_reports_suspicious_definitions() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        let folder = root.join("lint_msgs").join("msg");
+        fs::create_dir_all(&folder).unwrap();
+        fs::create_dir_all(root.join("LintMsgs").join("msg")).unwrap();
+        fs::write(root.join("LintMsgs/msg/Ignored.msg"), "int32 a\n").unwrap();
+        fs::write(folder.join("Broken.msg"), "int32 a\nfloat64[x] b\n").unwrap();
+        fs::write(
+            folder.join("odd_name.msg"),
+            "int32 a\nuint8 mode=1\nfloat64 SCALE=2\nstring NAME=foo # bar\nint32 a\n",
+        )
+        .unwrap();
+        fs::write(folder.join("Fine.msg"), "int32 a # a=b\nstring NAME=foo\n").unwrap();
+
+        let inspector = Inspector::new().search_path(root);
+        let lints = inspector.lint(None);
+        assert_eq!(inspector.packages(), vec!["LintMsgs", "lint_msgs"]);
+        assert_eq!(
+            inspector.list(Some("lint_msgs")),
+            vec!["lint_msgs/Broken", "lint_msgs/Fine", "lint_msgs/odd_name"],
+        );
+
+        let lints = lints
+            .iter()
+            .map(|lint| {
+                let file = lint.file.strip_prefix(root).unwrap().display().to_string();
+                (file, lint.line, lint.message.as_str())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(lints.len(), 7);
+        assert_eq!(lints[0].0, "LintMsgs/msg/Ignored.msg");
+        assert!(lints[0].2.contains("REP 144"));
+        assert_eq!(&lints[1].0, "lint_msgs/msg/Broken.msg");
+        assert_eq!(lints[1].1, Some(2));
+        assert_eq!(
+            lints[2..]
+                .iter()
+                .map(|(file, line, message)| {
+                    assert_eq!(file, "lint_msgs/msg/odd_name.msg");
+                    (*line, *message)
+                })
+                .collect::<Vec<_>>(),
+            vec![
+                (None, "type name `odd_name` should be CamelCase"),
+                (Some(2), "constant `mode` should be UPPER_CASE"),
+                (
+                    Some(3),
+                    "float constant `SCALE` has no decimal point, and might be meant as an integer",
+                ),
+                (
+                    Some(4),
+                    "`#` is part of the value of string constant `NAME`, not the start of a comment",
+                ),
+                (Some(5), "`a` is already declared on line 1"),
+            ],
+        );
+    }
+}
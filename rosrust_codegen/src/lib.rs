@@ -1,18 +1,21 @@
 #![recursion_limit = "1024"]
 
-extern crate proc_macro;
-
 mod alerts;
-mod error;
+mod builder;
+pub mod error;
 mod genmsg;
 mod helpers;
+mod inspect;
 mod msg;
 mod output_layout;
 mod rosmsg_include;
 
-use proc_macro::TokenStream;
+pub use builder::Builder;
+pub use inspect::{Inspector, Lint};
+
+use proc_macro2::TokenStream;
 
-#[proc_macro]
+#[doc(hidden)]
 pub fn rosmsg_include(input: TokenStream) -> TokenStream {
     let mut messages = Vec::new();
     let mut next_item = String::new();
@@ -25,11 +28,28 @@ pub fn rosmsg_include(input: TokenStream) -> TokenStream {
             s => next_item += s,
         }
     }
-    let is_internal = next_item == "INTERNAL";
-    let ignore_bad = next_item == "IGNORE_BAD";
-    if !is_internal && !ignore_bad && !next_item.is_empty() {
+    if !next_item.is_empty() {
         messages.push(next_item);
     }
+    let mut is_internal = false;
+    let mut ignore_bad = false;
+    let mut options = output_layout::Options::default();
+    while let Some(flag) = messages.pop() {
+        match flag.as_str() {
+            "INTERNAL" => is_internal = true,
+            "IGNORE_BAD" => ignore_bad = true,
+            "ACTIONLIB" => options.actionlib_prefix = Some(quote::quote! { ::rosrust_actionlib:: }),
+            "SERDE" => options.serde = true,
+            "ENUMS" => options.enums = true,
+            "ZERO_COPY" => options.zero_copy = true,
+            "SCHEMA" => options.schema = true,
+            "REGISTRY" => options.registry = true,
+            _ => {
+                messages.push(flag);
+                break;
+            }
+        }
+    }
     let message_refs = messages.iter().map(String::as_str).collect::<Vec<&str>>();
-    rosmsg_include::depend_on_messages(&message_refs, is_internal, ignore_bad)
+    rosmsg_include::depend_on_messages(&message_refs, is_internal, ignore_bad, &options)
 }
@@ -1,9 +1,10 @@
 use crate::error::{Result, ResultExt};
+use crate::output_layout::Options;
 use lazy_static::lazy_static;
 use proc_macro2::{Literal, Span};
 use quote::{quote, ToTokens};
 use ros_message::{DataType, FieldCase, FieldInfo, MessagePath};
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use syn::Ident;
 
 #[derive(Clone, Debug)]
@@ -26,14 +27,35 @@ impl Msg {
         Ident::new(self.0.path().name(), Span::call_site())
     }
 
-    pub fn token_stream<T: ToTokens>(&self, crate_prefix: &T) -> impl ToTokens {
+    pub fn token_stream<T: ToTokens>(
+        &self,
+        crate_prefix: &T,
+        options: &Options,
+        definition: &str,
+        skipped_enums: &HashSet<String>,
+    ) -> impl ToTokens {
         let name = self.name_ident();
+        let comments = Comments::new(self.0.source());
+        let doc = definition_doc_token_stream(&comments.message, &self.full_name(), definition);
         let fields = self
             .0
             .fields()
             .iter()
-            .map(|v| field_info_field_token_stream(v, crate_prefix))
+            .enumerate()
+            .map(|(i, v)| {
+                field_info_field_token_stream(v, comments.field(i), crate_prefix, options)
+            })
             .collect::<Vec<_>>();
+        let serde_attributes = if options.serde {
+            let serde_crate = format!("{}serde", crate_path(crate_prefix));
+            let serde_derive = quote! { #crate_prefix serde_derive };
+            quote! {
+                #[derive(#serde_derive::Serialize, #serde_derive::Deserialize)]
+                #[serde(crate = #serde_crate)]
+            }
+        } else {
+            quote! {}
+        };
         let field_defaults = self
             .0
             .fields()
@@ -70,11 +92,23 @@ impl Msg {
             .0
             .fields()
             .iter()
-            .map(|v| field_info_const_token_stream(v, crate_prefix))
+            .enumerate()
+            .map(|(i, v)| field_info_const_token_stream(v, comments.field(i), crate_prefix))
             .collect::<Vec<_>>();
+        let constant_groups = if options.enums {
+            self.constant_groups()
+                .iter()
+                .filter(|group| !skipped_enums.contains(&group.enum_name(&name.to_string())))
+                .map(|group| group.token_stream(&name))
+                .collect::<Vec<_>>()
+        } else {
+            vec![]
+        };
         quote! {
+            #doc
             #[allow(dead_code, non_camel_case_types, non_snake_case)]
             #[derive(Clone)]
+            #serde_attributes
             pub struct #name {
                 #(#fields)*
             }
@@ -139,6 +173,89 @@ impl Msg {
                     }
                 }
             }
+
+            #(#constant_groups)*
+        }
+    }
+
+    /// Names of the enums generated for groups of constants, like `NavSatStatusStatus`.
+    pub fn enum_names(&self) -> Vec<String> {
+        let message = self.0.path().name();
+        self.constant_groups()
+            .iter()
+            .map(|group| group.enum_name(message))
+            .collect()
+    }
+
+    /// Groups integer constants by the field whose values they enumerate.
+    ///
+    /// Constants named `<FIELD>_<VALUE>` belong to the field of the same type with the
+    /// longest matching name. The remaining constants of a type belong to the only
+    /// field of that type without prefixed constants, if there is exactly one.
+    fn constant_groups(&self) -> Vec<ConstantGroup<'_>> {
+        let fields = self.0.fields();
+        let mut groups = fields
+            .iter()
+            .filter(|field| field.case() == &FieldCase::Unit)
+            .filter(|field| integer_type(field.datatype()).is_some())
+            .map(|field| ConstantGroup {
+                field,
+                variants: vec![],
+            })
+            .collect::<Vec<_>>();
+        let constants = fields
+            .iter()
+            .filter(|field| matches!(field.case(), FieldCase::Const(_)))
+            .filter(|field| integer_type(field.datatype()).is_some());
+        let mut unprefixed = vec![];
+        for constant in constants {
+            let group = groups
+                .iter_mut()
+                .filter(|group| group.accepts(constant))
+                .filter(|group| {
+                    let prefix = format!("{}_", group.field.name().to_uppercase());
+                    constant.name().starts_with(&prefix)
+                })
+                .max_by_key(|group| group.field.name().len());
+            match group {
+                Some(group) => {
+                    let value = &constant.name()[group.field.name().len() + 1..];
+                    let variant = variant_name(value, constant.name());
+                    group.variants.push((constant, variant));
+                }
+                None => unprefixed.push(constant),
+            }
+        }
+        let unclaimed = groups
+            .iter()
+            .enumerate()
+            .filter(|(_, group)| group.variants.is_empty())
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        for constant in unprefixed {
+            let mut candidates = unclaimed
+                .iter()
+                .filter(|&&index| groups[index].accepts(constant));
+            if let (Some(&index), None) = (candidates.next(), candidates.next()) {
+                let variant = variant_name(constant.name(), constant.name());
+                groups[index].variants.push((constant, variant));
+            }
+        }
+        groups.retain(ConstantGroup::is_valid);
+        groups
+    }
+
+    pub fn token_stream_to_value<T: ToTokens>(&self, crate_prefix: &T) -> impl ToTokens {
+        let fields = self
+            .0
+            .fields()
+            .iter()
+            .map(|v| field_info_field_to_value_token_stream(v, crate_prefix))
+            .collect::<Vec<_>>();
+        quote! {
+            let mut output = #crate_prefix MsgMessage::new();
+            #(#fields)*
+            output
         }
     }
 
@@ -155,12 +272,77 @@ impl Msg {
         }
     }
 
-    pub fn token_stream_decode<T: ToTokens>(&self, crate_prefix: &T) -> impl ToTokens {
+    pub fn token_stream_encode_into<T: ToTokens>(&self, crate_prefix: &T) -> impl ToTokens {
+        let fields = self
+            .0
+            .fields()
+            .iter()
+            .map(|v| field_info_field_token_stream_encode_into(v, crate_prefix))
+            .collect::<Vec<_>>();
+        quote! {
+            #(#fields)*
+            Ok(())
+        }
+    }
+
+    pub fn token_stream_encoded_len<T: ToTokens>(&self, crate_prefix: &T) -> impl ToTokens {
+        let fields = self
+            .0
+            .fields()
+            .iter()
+            .filter_map(|v| field_info_field_token_stream_encoded_len(v, crate_prefix))
+            .collect::<Vec<_>>();
+        quote! {
+            0 #(+ #fields)*
+        }
+    }
+
+    pub fn token_stream_decode<T: ToTokens>(
+        &self,
+        crate_prefix: &T,
+        options: &Options,
+    ) -> impl ToTokens {
         let fields = self
             .0
             .fields()
             .iter()
-            .map(|v| field_info_field_token_stream_decode(v, crate_prefix))
+            .map(|v| field_info_field_token_stream_decode(v, crate_prefix, options))
+            .collect::<Vec<_>>();
+        quote! {
+            Ok(Self {
+                #(#fields)*
+            })
+        }
+    }
+
+    pub fn token_stream_decode_frame<T: ToTokens>(
+        &self,
+        crate_prefix: &T,
+        options: &Options,
+    ) -> impl ToTokens {
+        let fields = self
+            .0
+            .fields()
+            .iter()
+            .map(|v| field_info_field_token_stream_decode_frame(v, crate_prefix, options))
+            .collect::<Vec<_>>();
+        quote! {
+            Ok(Self {
+                #(#fields)*
+            })
+        }
+    }
+
+    pub fn token_stream_decode_from<T: ToTokens>(
+        &self,
+        crate_prefix: &T,
+        options: &Options,
+    ) -> impl ToTokens {
+        let fields = self
+            .0
+            .fields()
+            .iter()
+            .map(|v| field_info_field_token_stream_decode_from(v, crate_prefix, options))
             .collect::<Vec<_>>();
         quote! {
             Ok(Self {
@@ -231,20 +413,60 @@ fn field_info_create_identifier(field_info: &FieldInfo, span: Span) -> Ident {
     Ident::new(field_info.name(), span)
 }
 
+fn crate_path<T: ToTokens>(crate_prefix: &T) -> String {
+    crate_prefix
+        .to_token_stream()
+        .to_string()
+        .split_whitespace()
+        .collect()
+}
+
 fn field_info_field_token_stream<T: ToTokens>(
     field_info: &FieldInfo,
+    doc: &str,
     crate_prefix: &T,
+    options: &Options,
 ) -> impl ToTokens {
     let datatype = datatype_token_stream(field_info.datatype(), crate_prefix);
     let name = field_info_create_identifier(field_info, Span::call_site());
+    let doc = doc_token_stream(doc);
+    let serde_attributes = if options.serde {
+        field_info_serde_attributes(field_info, crate_prefix)
+    } else {
+        quote! {}
+    };
     match field_info.case() {
-        FieldCase::Unit => quote! { pub #name: #datatype, },
-        FieldCase::Vector => quote! { pub #name: Vec<#datatype>, },
-        FieldCase::Array(l) => quote! { pub #name: [#datatype; #l], },
+        FieldCase::Unit => quote! { #doc #serde_attributes pub #name: #datatype, },
+        FieldCase::Vector if is_shared(field_info, options) => {
+            quote! { #doc #serde_attributes pub #name: #crate_prefix SharedSlice<#datatype>, }
+        }
+        FieldCase::Vector => quote! { #doc #serde_attributes pub #name: Vec<#datatype>, },
+        FieldCase::Array(l) => quote! { #doc #serde_attributes pub #name: [#datatype; #l], },
         FieldCase::Const(_) => quote! {},
     }
 }
 
+fn field_info_serde_attributes<T: ToTokens>(
+    field_info: &FieldInfo,
+    crate_prefix: &T,
+) -> proc_macro2::TokenStream {
+    let name = field_info_create_identifier(field_info, Span::call_site());
+    let mut attributes = quote! {};
+    // Fields that clash with Rust keywords keep their ROS names when serialized
+    if name != field_info.name() {
+        let rename = field_info.name();
+        attributes.extend(quote! { #[serde(rename = #rename)] });
+    }
+    // Serde only implements its traits for arrays of up to 32 elements
+    if let FieldCase::Array(l) = field_info.case() {
+        if *l > 32 {
+            let with = format!("{}serde_helpers::fixed_array", crate_path(crate_prefix));
+            attributes.extend(quote! { #[serde(with = #with)] });
+        }
+    }
+    attributes
+}
+
 fn field_info_field_name_eq_and_debug_token_stream(
     field_info: &FieldInfo,
 ) -> Option<(impl ToTokens, impl ToTokens, impl ToTokens)> {
@@ -291,6 +513,34 @@ fn field_info_field_into_value_token_stream<T: ToTokens>(
     }
 }
 
+/// Converts the field of `&self`, cloning only the data that the value has to own.
+fn field_info_field_to_value_token_stream<T: ToTokens>(
+    field_info: &FieldInfo,
+    crate_prefix: &T,
+) -> impl ToTokens {
+    let name = field_info_create_identifier(field_info, Span::call_site());
+    let name_str = field_info.name();
+    let is_message = matches!(
+        field_info.datatype(),
+        DataType::LocalMessage(_) | DataType::GlobalMessage(_)
+    );
+    let value = match field_info.case() {
+        FieldCase::Unit if is_message => quote! {
+            #crate_prefix MsgValue::Message(#crate_prefix MessageSchema::to_value(&self.#name))
+        },
+        FieldCase::Unit => quote! { self.#name.clone().into() },
+        FieldCase::Vector | FieldCase::Array(_) if is_message => quote! {
+            self.#name
+                .iter()
+                .map(|v| #crate_prefix MsgValue::Message(#crate_prefix MessageSchema::to_value(v)))
+                .collect()
+        },
+        FieldCase::Vector | FieldCase::Array(_) => quote! { self.#name.iter().cloned().collect() },
+        FieldCase::Const(_) => return quote! {},
+    };
+    quote! { output.insert(#name_str.into(), #value); }
+}
+
 fn field_info_field_from_value_token_stream<T: ToTokens>(
     field_info: &FieldInfo,
     _crate_prefix: &T,
@@ -331,13 +581,73 @@ fn field_info_field_token_stream_encode<T: ToTokens>(
     }
 }
 
+fn field_info_field_token_stream_encode_into<T: ToTokens>(
+    field_info: &FieldInfo,
+    crate_prefix: &T,
+) -> impl ToTokens {
+    let name = field_info_create_identifier(field_info, Span::call_site());
+    match field_info.case() {
+        FieldCase::Unit => quote! { #crate_prefix rosmsg::RosMsg::encode_into(&self.#name, w)?; },
+        FieldCase::Vector => match field_info.datatype() {
+            DataType::String
+            | DataType::Time
+            | DataType::Duration
+            | DataType::LocalMessage(_)
+            | DataType::GlobalMessage(_) => {
+                quote! { #crate_prefix rosmsg::encode_variable_slice_into(&self.#name, w)?; }
+            }
+            _ => {
+                quote! { #crate_prefix rosmsg::encode_variable_primitive_slice_into(&self.#name, w)?; }
+            }
+        },
+        FieldCase::Array(_l) => {
+            quote! { #crate_prefix rosmsg::encode_fixed_slice_into(&self.#name, w)?; }
+        }
+        FieldCase::Const(_) => quote! {},
+    }
+}
+
+fn field_info_field_token_stream_encoded_len<T: ToTokens>(
+    field_info: &FieldInfo,
+    crate_prefix: &T,
+) -> Option<proc_macro2::TokenStream> {
+    let name = field_info_create_identifier(field_info, Span::call_site());
+    let datatype = datatype_token_stream(field_info.datatype(), crate_prefix);
+    let is_primitive = !matches!(
+        field_info.datatype(),
+        DataType::String
+            | DataType::Time
+            | DataType::Duration
+            | DataType::LocalMessage(_)
+            | DataType::GlobalMessage(_)
+    );
+    Some(match field_info.case() {
+        FieldCase::Unit => quote! { #crate_prefix rosmsg::RosMsg::encoded_len(&self.#name) },
+        FieldCase::Vector if is_primitive => {
+            quote! { 4 + self.#name.len() * ::std::mem::size_of::<#datatype>() }
+        }
+        FieldCase::Vector => {
+            quote! { #crate_prefix rosmsg::encoded_len_variable_slice(&self.#name) }
+        }
+        FieldCase::Array(l) if is_primitive => quote! { #l * ::std::mem::size_of::<#datatype>() },
+        FieldCase::Array(_) => {
+            quote! { #crate_prefix rosmsg::encoded_len_fixed_slice(&self.#name) }
+        }
+        FieldCase::Const(_) => return None,
+    })
+}
+
 fn field_info_field_token_stream_decode<T: ToTokens>(
     field_info: &FieldInfo,
     crate_prefix: &T,
+    options: &Options,
 ) -> impl ToTokens {
     let name = field_info_create_identifier(field_info, Span::call_site());
     match field_info.case() {
         FieldCase::Unit => quote! { #name: #crate_prefix rosmsg::RosMsg::decode(r.by_ref())?, },
+        FieldCase::Vector if is_shared(field_info, options) => {
+            quote! { #name: #crate_prefix rosmsg::RosMsg::decode(r.by_ref())?, }
+        }
         FieldCase::Vector => match field_info.datatype() {
             DataType::String
             | DataType::Time
@@ -359,8 +669,77 @@ fn field_info_field_token_stream_decode<T: ToTokens>(
     }
 }
 
+fn field_info_field_token_stream_decode_frame<T: ToTokens>(
+    field_info: &FieldInfo,
+    crate_prefix: &T,
+    options: &Options,
+) -> impl ToTokens {
+    let name = field_info_create_identifier(field_info, Span::call_site());
+    match field_info.case() {
+        FieldCase::Unit => quote! { #name: #crate_prefix rosmsg::RosMsg::decode_frame(r)?, },
+        FieldCase::Vector if is_shared(field_info, options) => {
+            quote! { #name: #crate_prefix rosmsg::RosMsg::decode_frame(r)?, }
+        }
+        FieldCase::Vector => match field_info.datatype() {
+            DataType::String
+            | DataType::Time
+            | DataType::Duration
+            | DataType::LocalMessage(_)
+            | DataType::GlobalMessage(_) => {
+                quote! { #name: #crate_prefix rosmsg::decode_frame_vec(r)?, }
+            }
+            _ => quote! { #name: #crate_prefix rosmsg::decode_frame_primitive_vec(r)?, },
+        },
+        FieldCase::Array(l) => {
+            let lines = (0..*l).map(|_| quote! { #crate_prefix rosmsg::RosMsg::decode_frame(r)?, });
+            quote! { #name: [#(#lines)*], }
+        }
+        FieldCase::Const(_) => quote! {},
+    }
+}
+
+fn field_info_field_token_stream_decode_from<T: ToTokens>(
+    field_info: &FieldInfo,
+    crate_prefix: &T,
+    options: &Options,
+) -> impl ToTokens {
+    let name = field_info_create_identifier(field_info, Span::call_site());
+    match field_info.case() {
+        FieldCase::Unit => quote! { #name: #crate_prefix rosmsg::RosMsg::decode_from(r)?, },
+        FieldCase::Vector if is_shared(field_info, options) => {
+            quote! { #name: #crate_prefix rosmsg::RosMsg::decode_from(r)?, }
+        }
+        FieldCase::Vector => match field_info.datatype() {
+            DataType::String
+            | DataType::Time
+            | DataType::Duration
+            | DataType::LocalMessage(_)
+            | DataType::GlobalMessage(_) => {
+                quote! { #name: #crate_prefix rosmsg::decode_slice_vec(r)?, }
+            }
+            _ => quote! { #name: #crate_prefix rosmsg::decode_slice_primitive_vec(r)?, },
+        },
+        FieldCase::Array(l) => {
+            let lines = (0..*l).map(|_| quote! { #crate_prefix rosmsg::RosMsg::decode_from(r)?, });
+            quote! { #name: [#(#lines)*], }
+        }
+        FieldCase::Const(_) => quote! {},
+    }
+}
+
+/// Whether the field is a primitive array that shares the memory of received messages.
+fn is_shared(field_info: &FieldInfo, options: &Options) -> bool {
+    options.zero_copy
+        && field_info.case() == &FieldCase::Vector
+        && match field_info.datatype() {
+            DataType::F32 | DataType::F64 => true,
+            datatype => integer_type(datatype).is_some(),
+        }
+}
+
 fn field_info_const_token_stream<T: ToTokens>(
     field_info: &FieldInfo,
+    doc: &str,
     crate_prefix: &T,
 ) -> impl ToTokens {
     let value = match field_info.case() {
@@ -424,12 +803,282 @@ fn field_info_const_token_stream<T: ToTokens>(
             quote! { #name: #datatype = #numeric_value as #datatype }
         }
     };
+    let doc = doc_token_stream(doc);
     quote! {
+        #doc
         #[allow(dead_code,non_upper_case_globals)]
         pub const #insides;
     }
 }
 
+/// Integer constants that enumerate the values of a field.
+struct ConstantGroup<'a> {
+    field: &'a FieldInfo,
+    variants: Vec<(&'a FieldInfo, String)>,
+}
+
+impl<'a> ConstantGroup<'a> {
+    fn accepts(&self, constant: &FieldInfo) -> bool {
+        integer_type(self.field.datatype()) == integer_type(constant.datatype())
+    }
+
+    fn is_valid(&self) -> bool {
+        let mut values = BTreeSet::new();
+        let mut variants = BTreeSet::new();
+        !self.variants.is_empty()
+            && self.variants.iter().all(|(constant, variant)| {
+                let value = match constant.case() {
+                    FieldCase::Const(value) => value.parse::<i128>().ok(),
+                    _ => None,
+                };
+                // Aliased values can not be represented as distinct variants
+                matches!(value, Some(value) if values.insert(value))
+                    && variants.insert(variant.as_str())
+                    && variant != "Self"
+                    && variant.starts_with(char::is_alphabetic)
+            })
+    }
+
+    fn enum_name(&self, message: &str) -> String {
+        let field_name = self.field.name();
+        format!("{}{}", message, variant_name(field_name, field_name))
+    }
+
+    fn token_stream(&self, message: &Ident) -> impl ToTokens {
+        let field_name = self.field.name();
+        let name = Ident::new(&self.enum_name(&message.to_string()), Span::call_site());
+        let datatype = Ident::new(
+            integer_type(self.field.datatype()).expect("Groups only hold integers"),
+            Span::call_site(),
+        );
+        let field = field_info_create_identifier(self.field, Span::call_site());
+        let getter = Ident::new(&format!("{}_enum", field_name), Span::call_site());
+        let setter = Ident::new(&format!("set_{}_enum", field_name), Span::call_site());
+        let constants = self
+            .variants
+            .iter()
+            .map(|(constant, _)| field_info_create_identifier(constant, Span::call_site()))
+            .collect::<Vec<_>>();
+        let constant_names = self
+            .variants
+            .iter()
+            .map(|(constant, _)| constant.name())
+            .collect::<Vec<_>>();
+        let variants = self
+            .variants
+            .iter()
+            .map(|(_, variant)| Ident::new(variant, Span::call_site()))
+            .collect::<Vec<_>>();
+        let enum_doc = format!(" Values of the `{}` field of [`{}`].", field_name, message);
+        let variant_docs = constant_names
+            .iter()
+            .map(|constant| format!(" [`{}::{}`]", message, constant));
+        let getter_doc = format!(
+            " Value of the `{}` field, or the raw value if it is not a known constant.",
+            field_name,
+        );
+        let setter_doc = format!(" Sets the `{}` field to a known constant.", field_name);
+        quote! {
+            #[doc = #enum_doc]
+            #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+            pub enum #name {
+                #(#[doc = #variant_docs] #variants,)*
+            }
+
+            impl #name {
+                /// All values, in the order of their definition.
+                pub const ALL: &'static [Self] = &[#(Self::#variants),*];
+            }
+
+            impl std::convert::From<#name> for #datatype {
+                fn from(src: #name) -> Self {
+                    match src {
+                        #(#name::#variants => #message::#constants,)*
+                    }
+                }
+            }
+
+            impl std::convert::TryFrom<#datatype> for #name {
+                type Error = #datatype;
+
+                fn try_from(src: #datatype) -> Result<Self, #datatype> {
+                    match src {
+                        #(#message::#constants => Ok(Self::#variants),)*
+                        _ => Err(src),
+                    }
+                }
+            }
+
+            impl std::fmt::Display for #name {
+                fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                    f.write_str(match self {
+                        #(Self::#variants => #constant_names,)*
+                    })
+                }
+            }
+
+            #[allow(non_snake_case)]
+            impl #message {
+                #[doc = #getter_doc]
+                pub fn #getter(&self) -> Result<#name, #datatype> {
+                    std::convert::TryFrom::try_from(self.#field)
+                }
+
+                #[doc = #setter_doc]
+                pub fn #setter(&mut self, value: #name) {
+                    self.#field = value.into();
+                }
+            }
+        }
+    }
+}
+
+fn integer_type(datatype: &DataType) -> Option<&'static str> {
+    Some(match datatype {
+        DataType::I8(_) => "i8",
+        DataType::I16 => "i16",
+        DataType::I32 => "i32",
+        DataType::I64 => "i64",
+        DataType::U8(_) => "u8",
+        DataType::U16 => "u16",
+        DataType::U32 => "u32",
+        DataType::U64 => "u64",
+        _ => return None,
+    })
+}
+
+/// Converts `SNAKE_CASE` names to `CamelCase`, using the fallback if the name is not usable.
+fn variant_name(name: &str, fallback: &str) -> String {
+    let name = if name.starts_with(char::is_alphabetic) {
+        name
+    } else {
+        fallback
+    };
+    name.split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            let first = chars.next().map(|c| c.to_ascii_uppercase());
+            first
+                .into_iter()
+                .chain(chars.map(|c| c.to_ascii_lowercase()))
+                .collect::<String>()
+        })
+        .collect()
+}
+
+/// Documentation taken from comments in a message source.
+struct Comments {
+    /// Comment block at the start of the message, separated from fields by a blank line.
+    message: String,
+    /// Comments directly preceding or trailing each field, in the order of fields.
+    fields: Vec<String>,
+}
+
+impl Comments {
+    fn new(source: &str) -> Self {
+        let mut message = vec![];
+        let mut fields: Vec<Vec<String>> = vec![];
+        let mut pending = vec![];
+        let mut continues_trailing = false;
+        for raw_line in source.lines() {
+            let line = raw_line.trim();
+            if line.is_empty() {
+                if fields.is_empty() && message.is_empty() {
+                    message = std::mem::take(&mut pending);
+                }
+                pending.clear();
+                continues_trailing = false;
+                continue;
+            }
+            if line.starts_with('#') {
+                // Indented comments right below a trailing comment continue it
+                let continuation = continues_trailing && raw_line.starts_with(char::is_whitespace);
+                match fields.last_mut() {
+                    Some(doc) if continuation => doc.push(comment_text(line)),
+                    _ => {
+                        pending.push(comment_text(line));
+                        continues_trailing = false;
+                    }
+                }
+                continue;
+            }
+            let mut doc = std::mem::take(&mut pending);
+            continues_trailing = false;
+            // String constants keep everything after the `=` as their value
+            if !is_string_constant(line) {
+                if let Some(index) = line.find('#') {
+                    doc.push(comment_text(&line[index..]));
+                    continues_trailing = true;
+                }
+            }
+            fields.push(doc);
+        }
+        Self {
+            message: join_comment_lines(&message),
+            fields: fields.iter().map(|doc| join_comment_lines(doc)).collect(),
+        }
+    }
+
+    fn field(&self, index: usize) -> &str {
+        self.fields
+            .get(index)
+            .map(String::as_str)
+            .unwrap_or_default()
+    }
+}
+
+fn is_string_constant(line: &str) -> bool {
+    let rest = match line.strip_prefix("string") {
+        Some(rest) if rest.starts_with(char::is_whitespace) => rest,
+        _ => return false,
+    };
+    match (rest.find('='), rest.find('#')) {
+        (Some(assignment), Some(comment)) => assignment < comment,
+        (Some(_), None) => true,
+        (None, _) => false,
+    }
+}
+
+fn comment_text(comment: &str) -> String {
+    // Leading whitespace is dropped so indented comments are not rendered as code blocks
+    comment.trim_start_matches('#').trim().to_owned()
+}
+
+fn join_comment_lines(lines: &[String]) -> String {
+    lines.join("\n").trim().to_owned()
+}
+
+fn doc_token_stream(doc: &str) -> proc_macro2::TokenStream {
+    if doc.is_empty() {
+        return quote! {};
+    }
+    let lines = doc.lines().map(|line| format!(" {}", line));
+    quote! { #(#[doc = #lines])* }
+}
+
+pub fn definition_doc_token_stream(
+    description: &str,
+    msg_type: &str,
+    definition: &str,
+) -> proc_macro2::TokenStream {
+    let mut doc = String::new();
+    if !description.is_empty() {
+        doc += description;
+        doc += "\n\n";
+    }
+    doc += &format!(
+        "Definition of `{}`:\n\n```text\n{}\n```",
+        msg_type,
+        definition.trim()
+    );
+    doc_token_stream(&doc)
+}
+
+pub fn description(source: &str) -> String {
+    Comments::new(source).message
+}
+
 fn datatype_token_stream<T: ToTokens>(data_type: &DataType, crate_prefix: &T) -> impl ToTokens {
     match data_type {
         DataType::Bool => quote! { bool },
@@ -457,3 +1106,111 @@ fn datatype_token_stream<T: ToTokens>(data_type: &DataType, crate_prefix: &T) ->
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryInto;
+
+    #[test]
+    fn comments_are_assigned_to_fields() {
+        let comments = Comments::new(
+            "# A sample message\n\
+             #   with a description\n\
+             \n\
+             # Kinds of samples\n\
+             \n\
+             uint8 KIND_A=1 # first kind\n\
+             string NAME=hash # not a comment\n\
+             # Sample kind\n\
+             # from the constants above\n\
+             uint8 kind\n\
+             float64 value # trailing comment\n\
+             \x20   # continued below\n\
+             # Number of samples\n\
+             int32 count\n",
+        );
+        assert_eq!(comments.message, "A sample message\nwith a description");
+        assert_eq!(
+            comments.fields,
+            vec![
+                "first kind",
+                "",
+                "Sample kind\nfrom the constants above",
+                "trailing comment\ncontinued below",
+                "Number of samples",
+            ],
+        );
+        assert_eq!(comments.field(10), "");
+    }
+
+    #[test]
+    fn comments_before_first_field_document_the_field() {
+        let comments = Comments::new("# sequence ID\nuint32 seq\n");
+        assert_eq!(comments.message, "");
+        assert_eq!(comments.fields, vec!["sequence ID"]);
+    }
+
+    fn constant_groups(source: &str) -> Vec<(String, Vec<String>)> {
+        let message = Msg::new("foo/Bar".try_into().unwrap(), source).unwrap();
+        message
+            .constant_groups()
+            .iter()
+            .map(|group| {
+                let variants = group.variants.iter().map(|(_, v)| v.clone()).collect();
+                (group.field.name().to_owned(), variants)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn constants_are_grouped_by_field_prefix() {
+        let groups = constant_groups(
+            "int8 STATUS_NO_FIX=-1\nint8 STATUS_FIX=0\nint8 status\n\
+             uint16 SERVICE_GPS=1\nuint16 SERVICE_GLONASS=2\nuint16 service\n\
+             uint8 POWER_SUPPLY_STATUS_FULL=4\nuint8 POWER_SUPPLY_HEALTH_GOOD=1\n\
+             uint8 power_supply_status\nuint8 power_supply_health\nuint8 power\n",
+        );
+        assert_eq!(
+            groups,
+            vec![
+                (
+                    "status".to_owned(),
+                    vec!["NoFix".to_owned(), "Fix".to_owned()]
+                ),
+                ("service".to_owned(), vec!["Gps".into(), "Glonass".into()]),
+                ("power_supply_status".to_owned(), vec!["Full".into()]),
+                ("power_supply_health".to_owned(), vec!["Good".into()]),
+            ],
+        );
+    }
+
+    #[test]
+    fn constants_need_field_prefix() {
+        let groups = constant_groups(
+            "byte OK=0\nbyte WARN=1\nbyte level\nstring name\nuint8 id\nuint8 ID_MAX=3\nint32 x\n",
+        );
+        assert_eq!(
+            groups,
+            vec![
+                ("level".to_owned(), vec!["Ok".to_owned(), "Warn".to_owned()]),
+                ("id".to_owned(), vec!["Max".to_owned()]),
+            ],
+        );
+        // Unprefixed constants need exactly one field of their type to belong to
+        assert!(constant_groups("uint8 A=0\nuint8 B=1\nuint8 x\nuint8 y\n").is_empty());
+        // Aliased values do not get enums
+        assert!(constant_groups("uint8 X_ADD=0\nuint8 X_CIRCLE=0\nuint8 x\n").is_empty());
+        assert!(constant_groups("uint8 ADD=0\nuint8 CIRCLE=0\nuint8 x\n").is_empty());
+    }
+
+    #[test]
+    fn definition_is_embedded_in_documentation() {
+        let doc =
+            definition_doc_token_stream("Description", "foo/Bar", "int32 x # value\n").to_string();
+        assert!(doc.contains(r#"" Description""#));
+        assert!(doc.contains(r#"" Definition of `foo/Bar`:""#));
+        assert!(doc.contains(r#"" ```text""#));
+        assert!(doc.contains(r#"" int32 x # value""#));
+    }
+}
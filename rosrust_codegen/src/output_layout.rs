@@ -1,22 +1,70 @@
-use crate::msg::Msg;
-use proc_macro2::Span;
+use crate::msg::{self, Msg};
+use proc_macro2::{Span, TokenStream};
 use quote::quote;
 use quote::ToTokens;
+use std::collections::{HashMap, HashSet};
 use syn::Ident;
 
 pub struct Layout {
     pub packages: Vec<Package>,
 }
 
+/// Optional features of the generated code.
+#[derive(Clone, Default)]
+pub struct Options {
+    /// Path to `rosrust_actionlib`, for implementing its traits on actions.
+    pub actionlib_prefix: Option<TokenStream>,
+    /// Derives serde traits on messages.
+    pub serde: bool,
+    /// Generates enums for groups of integer constants.
+    pub enums: bool,
+    /// Uses `SharedSlice` for primitive arrays, which share the memory of received messages.
+    pub zero_copy: bool,
+    /// Implements `MessageSchema` on messages.
+    pub schema: bool,
+    /// Generates `register_messages` for adding all messages to a `MessageRegistry`.
+    ///
+    /// The registry needs `MessageSchema`, so this implements it as well.
+    pub registry: bool,
+}
+
 impl Layout {
-    pub fn token_stream<T: ToTokens>(&self, crate_prefix: &T) -> impl ToTokens {
+    pub fn token_stream<T: ToTokens>(&self, crate_prefix: &T, options: &Options) -> impl ToTokens {
         let packages = self
             .packages
             .iter()
-            .map(|v| v.token_stream(crate_prefix))
+            .map(|v| v.token_stream(crate_prefix, options))
             .collect::<Vec<_>>();
+        let registry = if options.registry {
+            self.registry_token_stream(crate_prefix)
+        } else {
+            quote! {}
+        };
         quote! {
             #(#packages)*
+            #registry
+        }
+    }
+
+    /// Function that registers every generated message, placed next to the package modules.
+    pub fn registry_token_stream<T: ToTokens>(&self, crate_prefix: &T) -> TokenStream {
+        let messages = self
+            .packages
+            .iter()
+            .flat_map(|package| {
+                let package_name = Ident::new(&package.name, Span::call_site());
+                package.messages.iter().map(move |message| {
+                    let name = message.message.name_ident();
+                    quote! { registry.register::<#package_name::#name>(); }
+                })
+            })
+            .collect::<Vec<_>>();
+        quote! {
+            /// Adds all generated messages to the registry.
+            #[allow(dead_code)]
+            pub fn register_messages(registry: &mut #crate_prefix MessageRegistry) {
+                #(#messages)*
+            }
         }
     }
 }
@@ -26,32 +74,78 @@ pub struct Package {
     pub name: String,
     pub messages: Vec<Message>,
     pub services: Vec<Service>,
+    pub actions: Vec<Action>,
 }
 
 impl Package {
-    pub fn token_stream<T: ToTokens>(&self, crate_prefix: &T) -> impl ToTokens {
+    pub fn token_stream<T: ToTokens>(&self, crate_prefix: &T, options: &Options) -> impl ToTokens {
         let name = Ident::new(&self.name, Span::call_site());
+        let contents = self.contents_token_stream(crate_prefix, options);
+        quote! {
+            pub mod #name {
+                #contents
+            }
+        }
+    }
+
+    pub fn contents_token_stream<T: ToTokens>(
+        &self,
+        crate_prefix: &T,
+        options: &Options,
+    ) -> impl ToTokens {
+        let skipped_enums = if options.enums {
+            self.clashing_enum_names()
+        } else {
+            HashSet::new()
+        };
         let messages = self
             .messages
             .iter()
-            .map(|v| v.token_stream(crate_prefix))
+            .map(|v| v.token_stream(crate_prefix, options, &skipped_enums))
             .collect::<Vec<_>>();
         let services = self
             .services
             .iter()
             .map(|v| v.token_stream(crate_prefix))
             .collect::<Vec<_>>();
+        let actions = match &options.actionlib_prefix {
+            Some(actionlib_prefix) => self
+                .actions
+                .iter()
+                .map(|v| v.token_stream(actionlib_prefix, &self.name))
+                .collect::<Vec<_>>(),
+            None => vec![],
+        };
         quote! {
-            pub mod #name {
-                #(#messages)*
-                #(#services)*
-            }
+            #(#messages)*
+            #(#services)*
+            #(#actions)*
+        }
+    }
+    /// Enum names that are also used by messages, services or other enums of the package,
+    /// like `NavSatStatus` for the `status` field of a `NavSat` message.
+    fn clashing_enum_names(&self) -> HashSet<String> {
+        let mut counts = HashMap::<String, usize>::new();
+        let types = self
+            .messages
+            .iter()
+            .map(|v| v.message.name_ident().to_string())
+            .chain(self.services.iter().map(|v| v.name.clone()));
+        for name in types {
+            *counts.entry(name).or_default() += 1;
         }
+        let enums = self
+            .messages
+            .iter()
+            .flat_map(|v| v.message.enum_names())
+            .collect::<Vec<_>>();
+        for name in &enums {
+            *counts.entry(name.clone()).or_default() += 1;
+        }
+        enums.into_iter().filter(|name| counts[name] > 1).collect()
     }
 }
 
-// TODO: Present source code of messages and services somehow
-
 #[derive(Debug)]
 pub struct Message {
     pub message: Msg,
@@ -62,19 +156,49 @@ pub struct Message {
 }
 
 impl Message {
-    pub fn token_stream<T: ToTokens>(&self, crate_prefix: &T) -> impl ToTokens {
+    pub fn token_stream<T: ToTokens>(
+        &self,
+        crate_prefix: &T,
+        options: &Options,
+        skipped_enums: &HashSet<String>,
+    ) -> impl ToTokens {
         let Message {
             message,
             msg_definition,
             md5sum,
             msg_type,
-            source: _,
+            source,
         } = self;
-        let base_message = message.token_stream(crate_prefix);
+        let base_message = message.token_stream(crate_prefix, options, source, skipped_enums);
         let encode_message = message.token_stream_encode(crate_prefix);
-        let decode_message = message.token_stream_decode(crate_prefix);
+        let encoded_len = message.token_stream_encoded_len(crate_prefix);
+        let decode_message = message.token_stream_decode(crate_prefix, options);
+        let decode_frame_message = message.token_stream_decode_frame(crate_prefix, options);
+        let encode_into_message = message.token_stream_encode_into(crate_prefix);
+        let decode_from_message = message.token_stream_decode_from(crate_prefix, options);
         let name = message.name_ident();
         let header_tokens = message.header_token_stream(crate_prefix);
+        let schema = if options.schema || options.registry {
+            let to_value = message.token_stream_to_value(crate_prefix);
+            quote! {
+                impl #crate_prefix MessageSchema for #name {
+                    fn dynamic_msg() -> &'static #crate_prefix DynamicMsg {
+                        #crate_prefix lazy_static::lazy_static! {
+                            static ref SCHEMA: #crate_prefix DynamicMsg =
+                                #crate_prefix DynamicMsg::new(#msg_type, #msg_definition)
+                                    .expect("Generated message has an invalid definition");
+                        }
+                        &SCHEMA
+                    }
+
+                    fn to_value(&self) -> #crate_prefix MsgMessage {
+                        #to_value
+                    }
+                }
+            }
+        } else {
+            quote! {}
+        };
         quote! {
             #base_message
 
@@ -102,10 +226,29 @@ impl Message {
                     #encode_message
                 }
 
+                #[inline]
+                fn encoded_len(&self) -> usize {
+                    #encoded_len
+                }
+
                 fn decode<R: ::std::io::Read>(mut r: R) -> ::std::io::Result<Self> {
                     #decode_message
                 }
+
+                fn decode_frame(r: &mut #crate_prefix rosmsg::FrameReader) -> ::std::io::Result<Self> {
+                    #decode_frame_message
+                }
+
+                fn encode_into(&self, w: &mut #crate_prefix rosmsg::SliceWriter) -> ::std::io::Result<()> {
+                    #encode_into_message
+                }
+
+                fn decode_from(r: &mut #crate_prefix rosmsg::SliceReader) -> ::std::io::Result<Self> {
+                    #decode_from_message
+                }
             }
+
+            #schema
         }
     }
 }
@@ -124,13 +267,15 @@ impl Service {
             name,
             md5sum,
             msg_type,
-            source: _,
+            source,
         } = self;
+        let doc = msg::definition_doc_token_stream(&msg::description(source), msg_type, source);
         let name_ident = Ident::new(name, Span::call_site());
         let req_ident = Ident::new(&format!("{}Req", name), Span::call_site());
         let res_ident = Ident::new(&format!("{}Res", name), Span::call_site());
 
         quote! {
+            #doc
             #[allow(dead_code,non_camel_case_types,non_snake_case)]
             #[derive(Clone, Debug, Default, PartialEq)]
             pub struct #name_ident;
@@ -169,3 +314,20 @@ impl Service {
         }
     }
 }
+
+#[derive(Debug)]
+pub struct Action {
+    pub name: String,
+}
+
+impl Action {
+    /// Implements the `rosrust_actionlib` traits through its `action!` macro, which gets
+    /// invoked from within the package module.
+    pub fn token_stream<T: ToTokens>(&self, actionlib_prefix: &T, package: &str) -> impl ToTokens {
+        let package = Ident::new(package, Span::call_site());
+        let name = Ident::new(&self.name, Span::call_site());
+        quote! {
+            #actionlib_prefix action!(super; #package: #name);
+        }
+    }
+}
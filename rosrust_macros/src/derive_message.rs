@@ -0,0 +1,133 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::ext::IdentExt;
+use syn::spanned::Spanned;
+use syn::{Attribute, Data, DeriveInput, Error, Fields, Lit, Meta, NestedMeta, Result};
+
+pub fn derive(input: DeriveInput) -> Result<TokenStream> {
+    let msg_type = match find_attribute(&input.attrs, "type")? {
+        Some(msg_type) => msg_type,
+        None => {
+            return Err(Error::new(
+                input.ident.span(),
+                "RosMessage requires a #[ros(type = \"package/Name\")] attribute",
+            ))
+        }
+    };
+    let crate_prefix = match find_attribute(&input.attrs, "crate")? {
+        Some(path) => syn::parse_str::<syn::Path>(&path)?,
+        None => syn::parse_quote! { ::rosrust },
+    };
+    if !input.generics.params.is_empty() {
+        return Err(Error::new(
+            input.generics.span(),
+            "RosMessage can not be derived for generic structs",
+        ));
+    }
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => fields.named.iter().collect::<Vec<_>>(),
+            Fields::Unit => vec![],
+            Fields::Unnamed(fields) => {
+                return Err(Error::new(
+                    fields.span(),
+                    "RosMessage requires named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new(
+                input.ident.span(),
+                "RosMessage can only be derived for structs",
+            ))
+        }
+    };
+
+    let name = &input.ident;
+    let mut idents = vec![];
+    let mut ros_names = vec![];
+    let mut types = vec![];
+    for field in fields {
+        let ident = field.ident.as_ref().expect("Fields are named");
+        let ros_name = match find_attribute(&field.attrs, "name")? {
+            Some(ros_name) => ros_name,
+            None => ident.unraw().to_string(),
+        };
+        idents.push(ident);
+        ros_names.push(ros_name);
+        types.push(&field.ty);
+    }
+
+    Ok(quote! {
+        impl #crate_prefix::Message for #name {
+            fn msg_definition() -> ::std::string::String {
+                let lines: &[::std::string::String] = &[
+                    #(#crate_prefix::derive::field_line::<#types>(#ros_names),)*
+                ];
+                let dependencies = [
+                    #(<#types as #crate_prefix::derive::MessageField>::dependency(),)*
+                ];
+                #crate_prefix::derive::msg_definition(&lines.join("\n"), &dependencies)
+            }
+
+            fn md5sum() -> ::std::string::String {
+                let lines: &[::std::string::String] = &[
+                    #(#crate_prefix::derive::field_line::<#types>(#ros_names),)*
+                ];
+                let dependencies = [
+                    #(<#types as #crate_prefix::derive::MessageField>::dependency(),)*
+                ];
+                #crate_prefix::derive::md5sum(#msg_type, &lines.join("\n"), &dependencies)
+            }
+
+            #[inline]
+            fn msg_type() -> ::std::string::String {
+                #msg_type.into()
+            }
+        }
+
+        impl #crate_prefix::rosmsg::RosMsg for #name {
+            #[allow(unused_mut)]
+            fn encode<W: ::std::io::Write>(&self, mut w: W) -> ::std::io::Result<()> {
+                #(#crate_prefix::derive::MessageField::encode_field(&self.#idents, w.by_ref())?;)*
+                Ok(())
+            }
+
+            #[inline]
+            fn encoded_len(&self) -> usize {
+                0 #(+ #crate_prefix::derive::MessageField::encoded_len_field(&self.#idents))*
+            }
+
+            #[allow(unused_mut, unused_variables)]
+            fn decode<R: ::std::io::Read>(mut r: R) -> ::std::io::Result<Self> {
+                Ok(Self {
+                    #(#idents: #crate_prefix::derive::MessageField::decode_field(r.by_ref())?,)*
+                })
+            }
+        }
+    })
+}
+
+fn find_attribute(attrs: &[Attribute], key: &str) -> Result<Option<String>> {
+    let mut output = None;
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("ros")) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => return Err(Error::new(meta.span(), "Expected #[ros(key = \"value\")]")),
+        };
+        for item in list.nested {
+            let pair = match item {
+                NestedMeta::Meta(Meta::NameValue(pair)) => pair,
+                item => return Err(Error::new(item.span(), "Expected key = \"value\"")),
+            };
+            if !pair.path.is_ident(key) {
+                continue;
+            }
+            match pair.lit {
+                Lit::Str(value) => output = Some(value.value()),
+                lit => return Err(Error::new(lit.span(), "Expected a string literal")),
+            }
+        }
+    }
+    Ok(output)
+}
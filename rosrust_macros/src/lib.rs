@@ -0,0 +1,36 @@
+extern crate proc_macro;
+
+mod derive_message;
+
+use proc_macro::TokenStream;
+
+#[proc_macro]
+pub fn rosmsg_include(input: TokenStream) -> TokenStream {
+    rosrust_codegen::rosmsg_include(input.into()).into()
+}
+
+/// Implements `Message` and `RosMsg` for a struct with named fields.
+///
+/// The message type is provided with `#[ros(type = "package/Name")]`, and fields can
+/// be renamed with `#[ros(name = "...")]`. Fields can be primitives, `String`, `Time`,
+/// `Duration`, other messages, and `Vec`s or arrays of those.
+///
+/// # Examples
+///
+/// ```ignore
+/// #[derive(Clone, Debug, Default, PartialEq, rosrust::RosMessage)]
+/// #[ros(type = "my_msgs/Reading")]
+/// pub struct Reading {
+///     pub stamp: rosrust::Time,
+///     #[ros(name = "type")]
+///     pub kind: u8,
+///     pub values: Vec<f64>,
+/// }
+/// ```
+#[proc_macro_derive(RosMessage, attributes(ros))]
+pub fn derive_ros_message(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+    derive_message::derive(input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
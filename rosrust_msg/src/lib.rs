@@ -1 +1,14 @@
 include!(concat!(env!("OUT_DIR"), "/", "messages.rs"));
+
+/// Registry of all generated messages, built on first use.
+#[cfg(feature = "registry")]
+pub fn registry() -> &'static rosrust::MessageRegistry {
+    rosrust::lazy_static::lazy_static! {
+        static ref REGISTRY: rosrust::MessageRegistry = {
+            let mut registry = rosrust::MessageRegistry::new();
+            register_messages(&mut registry);
+            registry
+        };
+    }
+    &REGISTRY
+}